@@ -0,0 +1,442 @@
+use std::{fmt::Display, fs, path::{Path, PathBuf}, sync::Arc, time::Duration};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use reqwest::{Certificate, Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+
+use crate::{config::config::{Server, ServerAuth}, modules::{financial::{planned::PlannedPurchase, recurring::RecurringPurchase, Account, Purchase}, health::{fitbit_sync::FitbitAccount, sleep::SleepLog, CaloriesLog, WeightLog, WeightUnit}, kitchen_inventory::InventoryItem, notebooks::Notebook, users::User}};
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The server could not be reached at all
+    Connection(reqwest::Error),
+    /// The server answered but refused the request
+    Status { status: StatusCode, message: String },
+    /// The server answered with something that is not the expected JSON
+    Decode(reqwest::Error),
+    /// The CA certificate configured for the server could not be used
+    Certificate { path: PathBuf, message: String }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::Connection(error) => write!(f, "could not reach the server: {}", error),
+            ApiError::Status { status, message } if message.is_empty() => write!(f, "the server returned {}", status),
+            ApiError::Status { status, message } => write!(f, "the server returned {}: {}", status, message),
+            ApiError::Decode(error) => write!(f, "unexpected response from the server: {}", error),
+            ApiError::Certificate { path, message } => write!(f, "could not use the CA certificate {}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// How long a health check waits before calling the server offline.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(serde::Deserialize)]
+pub struct ServerInfo {
+    pub version: String
+}
+
+#[derive(serde::Serialize)]
+struct Login<'a> {
+    username: &'a str,
+    password: &'a str
+}
+
+#[derive(serde::Deserialize)]
+struct Session {
+    token: String
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewUser {
+    pub name: String
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewNotebook {
+    pub name: String,
+    pub description: String
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewItem {
+    pub name: String,
+    pub in_kitchen: bool
+}
+
+/// Amounts in the financial requests are in cents.
+#[derive(serde::Serialize, Clone)]
+pub struct NewAccount {
+    pub name: String,
+    pub account_type: String,
+    pub starting_balance: i64
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewPurchase {
+    pub account_id: i64,
+    pub date: NaiveDate,
+    pub amount: i64,
+    pub payee: String,
+    pub category: String,
+    pub recurring_id: Option<i64>
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewRecurring {
+    pub account_id: i64,
+    pub amount: i64,
+    pub payee: String,
+    pub category: String,
+    pub schedule: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>
+}
+
+#[derive(serde::Serialize)]
+struct Materialized {
+    last_materialized: NaiveDate
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewPlanned {
+    pub account_id: i64,
+    pub name: String,
+    pub target_amount: i64,
+    pub target_date: NaiveDate
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewContribution {
+    pub date: NaiveDate,
+    pub amount: i64
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewWeight {
+    pub date: NaiveDate,
+    pub weight: f64,
+    pub unit: WeightUnit,
+    pub fitbit_log_id: Option<i64>
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewCalories {
+    pub date: NaiveDate,
+    pub calories: u32
+}
+
+#[derive(serde::Serialize)]
+struct Calories {
+    calories: u32
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct NewSleep {
+    pub bed_time: NaiveDateTime,
+    pub wake_time: NaiveDateTime,
+    pub quality: Option<u8>,
+    pub fitbit_log_id: Option<i64>
+}
+
+/// Client for a single tracker server, requests and responses are JSON.
+#[derive(Clone, Default)]
+pub struct ApiClient {
+    base_url: String,
+    http_client: Client,
+    auth: ServerAuth,
+    /// Token from logging in with a password, shared by every clone of the client
+    session: Arc<Mutex<Option<String>>>
+}
+
+/// A client that also trusts the CA in `path`, for servers with a self-signed certificate.
+fn client_trusting(path: &Path) -> Result<Client, String> {
+    let pem = fs::read(path).map_err(|error| error.to_string())?;
+    let certificate = Certificate::from_pem(&pem).map_err(|error| error.to_string())?;
+    Client::builder().add_root_certificate(certificate).build().map_err(|error| error.to_string())
+}
+
+impl ApiClient {
+    pub fn new(server: &Server, http_client: &Client) -> ApiResult<Self> {
+        let http_client = match &server.ca_certificate {
+            Some(path) => client_trusting(path).map_err(|message| ApiError::Certificate { path: path.clone(), message })?,
+            None => http_client.clone(),
+        };
+
+        // IPv6 addresses have to be bracketed to tell them apart from the port
+        let address = &server.address;
+        let host = if address.contains(':') && !address.starts_with('[') { format!("[{}]", address) } else { address.clone() };
+        Ok(ApiClient {
+            base_url: format!("{}://{}:{}/api", server.scheme.as_str(), host, server.port),
+            http_client,
+            auth: server.auth.clone(),
+            session: Arc::default(),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    fn user_url(&self, user_id: i64, path: &str) -> String {
+        self.url(&format!("users/{}/{}", user_id, path))
+    }
+
+    /// Sends `request` as it is, turning error statuses into `ApiError::Status`.
+    async fn check(request: RequestBuilder) -> ApiResult<Response> {
+        let response = request.send().await.map_err(ApiError::Connection)?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(ApiError::Status { status, message });
+        }
+        Ok(response)
+    }
+
+    async fn login(&self, username: &str, password: &str) -> ApiResult<String> {
+        let request = self.http_client.post(self.url("session")).json(&Login { username, password });
+        let session: Session = ApiClient::check(request).await?.json().await.map_err(ApiError::Decode)?;
+        Ok(session.token)
+    }
+
+    /// Adds the server's credentials to `request`, logging in first if there is no session yet.
+    async fn authorize(&self, request: RequestBuilder) -> ApiResult<RequestBuilder> {
+        match &self.auth {
+            ServerAuth::None => Ok(request),
+            ServerAuth::Token { token } => Ok(request.bearer_auth(token)),
+            ServerAuth::Password { username, password } => {
+                // Held across the login so requests sent at the same time share one session
+                let mut session = self.session.lock().await;
+                if session.is_none() {
+                    *session = Some(self.login(username, password).await?);
+                }
+                Ok(request.bearer_auth(session.as_deref().unwrap_or_default()))
+            },
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> ApiResult<Response> {
+        // Sessions end when the server restarts, so a rejected session is renewed once
+        let retry = request.try_clone();
+        match (ApiClient::check(self.authorize(request).await?).await, retry) {
+            (Err(ApiError::Status { status: StatusCode::UNAUTHORIZED, .. }), Some(retry)) if matches!(self.auth, ServerAuth::Password { .. }) => {
+                *self.session.lock().await = None;
+                ApiClient::check(self.authorize(retry).await?).await
+            },
+            (response, _) => response,
+        }
+    }
+
+    async fn delete(&self, url: String) -> ApiResult<()> {
+        self.send(self.http_client.delete(url)).await.map(|_| ())
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> ApiResult<T> {
+        self.send(request).await?.json().await.map_err(ApiError::Decode)
+    }
+
+    /// Answered without credentials, so it only tells whether the server is up.
+    pub async fn server_info(&self) -> ApiResult<ServerInfo> {
+        ApiClient::check(self.http_client.get(self.url("health")).timeout(PING_TIMEOUT)).await?.json().await.map_err(ApiError::Decode)
+    }
+
+    pub async fn users(&self) -> ApiResult<Vec<User>> {
+        self.json(self.http_client.get(self.url("users"))).await
+    }
+
+    pub async fn create_user(&self, user: &NewUser) -> ApiResult<User> {
+        self.json(self.http_client.post(self.url("users")).json(user)).await
+    }
+
+    pub async fn update_user(&self, user_id: i64, user: &NewUser) -> ApiResult<User> {
+        self.json(self.http_client.put(self.url(&format!("users/{}", user_id))).json(user)).await
+    }
+
+    /// Removes the user along with everything they have stored on the server.
+    pub async fn delete_user(&self, user_id: i64) -> ApiResult<()> {
+        self.delete(self.url(&format!("users/{}", user_id))).await
+    }
+
+    pub async fn notebooks(&self, user_id: i64) -> ApiResult<Vec<Notebook>> {
+        self.json(self.http_client.get(self.user_url(user_id, "notebooks"))).await
+    }
+
+    pub async fn create_notebook(&self, user_id: i64, notebook: &NewNotebook) -> ApiResult<Notebook> {
+        self.json(self.http_client.post(self.user_url(user_id, "notebooks")).json(notebook)).await
+    }
+
+    pub async fn update_notebook(&self, user_id: i64, notebook_id: i64, notebook: &NewNotebook) -> ApiResult<Notebook> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("notebooks/{}", notebook_id))).json(notebook)).await
+    }
+
+    pub async fn delete_notebook(&self, user_id: i64, notebook_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("notebooks/{}", notebook_id))).await
+    }
+
+    pub async fn mark_notebook_used(&self, user_id: i64, notebook_id: i64) -> ApiResult<Notebook> {
+        self.json(self.http_client.post(self.user_url(user_id, &format!("notebooks/{}/used", notebook_id)))).await
+    }
+
+    pub async fn inventory(&self, user_id: i64) -> ApiResult<Vec<InventoryItem>> {
+        self.json(self.http_client.get(self.user_url(user_id, "inventory"))).await
+    }
+
+    pub async fn create_item(&self, user_id: i64, item: &NewItem) -> ApiResult<InventoryItem> {
+        self.json(self.http_client.post(self.user_url(user_id, "inventory")).json(item)).await
+    }
+
+    pub async fn update_item(&self, user_id: i64, item_id: i64, item: &NewItem) -> ApiResult<InventoryItem> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("inventory/{}", item_id))).json(item)).await
+    }
+
+    pub async fn delete_item(&self, user_id: i64, item_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("inventory/{}", item_id))).await
+    }
+
+    pub async fn accounts(&self, user_id: i64) -> ApiResult<Vec<Account>> {
+        self.json(self.http_client.get(self.user_url(user_id, "accounts"))).await
+    }
+
+    pub async fn create_account(&self, user_id: i64, account: &NewAccount) -> ApiResult<Account> {
+        self.json(self.http_client.post(self.user_url(user_id, "accounts")).json(account)).await
+    }
+
+    pub async fn update_account(&self, user_id: i64, account_id: i64, account: &NewAccount) -> ApiResult<Account> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("accounts/{}", account_id))).json(account)).await
+    }
+
+    /// Fails with a conflict while purchases, recurring or planned purchases still use the account.
+    pub async fn delete_account(&self, user_id: i64, account_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("accounts/{}", account_id))).await
+    }
+
+    pub async fn purchases(&self, user_id: i64) -> ApiResult<Vec<Purchase>> {
+        self.json(self.http_client.get(self.user_url(user_id, "purchases"))).await
+    }
+
+    pub async fn create_purchase(&self, user_id: i64, purchase: &NewPurchase) -> ApiResult<Purchase> {
+        self.json(self.http_client.post(self.user_url(user_id, "purchases")).json(purchase)).await
+    }
+
+    pub async fn update_purchase(&self, user_id: i64, purchase_id: i64, purchase: &NewPurchase) -> ApiResult<Purchase> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("purchases/{}", purchase_id))).json(purchase)).await
+    }
+
+    pub async fn delete_purchase(&self, user_id: i64, purchase_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("purchases/{}", purchase_id))).await
+    }
+
+    pub async fn recurring(&self, user_id: i64) -> ApiResult<Vec<RecurringPurchase>> {
+        self.json(self.http_client.get(self.user_url(user_id, "recurring"))).await
+    }
+
+    pub async fn create_recurring(&self, user_id: i64, recurring: &NewRecurring) -> ApiResult<RecurringPurchase> {
+        self.json(self.http_client.post(self.user_url(user_id, "recurring")).json(recurring)).await
+    }
+
+    /// Purchases that were already materialized are left as they are.
+    pub async fn update_recurring(&self, user_id: i64, recurring_id: i64, recurring: &NewRecurring) -> ApiResult<RecurringPurchase> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("recurring/{}", recurring_id))).json(recurring)).await
+    }
+
+    /// The purchases it materialized are kept, without the link to it.
+    pub async fn delete_recurring(&self, user_id: i64, recurring_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("recurring/{}", recurring_id))).await
+    }
+
+    pub async fn set_last_materialized(&self, user_id: i64, recurring_id: i64, date: NaiveDate) -> ApiResult<RecurringPurchase> {
+        let url = self.user_url(user_id, &format!("recurring/{}/last_materialized", recurring_id));
+        self.json(self.http_client.put(url).json(&Materialized { last_materialized: date })).await
+    }
+
+    pub async fn planned(&self, user_id: i64) -> ApiResult<Vec<PlannedPurchase>> {
+        self.json(self.http_client.get(self.user_url(user_id, "planned"))).await
+    }
+
+    pub async fn create_planned(&self, user_id: i64, planned: &NewPlanned) -> ApiResult<PlannedPurchase> {
+        self.json(self.http_client.post(self.user_url(user_id, "planned")).json(planned)).await
+    }
+
+    pub async fn update_planned(&self, user_id: i64, planned_id: i64, planned: &NewPlanned) -> ApiResult<PlannedPurchase> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("planned/{}", planned_id))).json(planned)).await
+    }
+
+    /// Removes the planned purchase together with its contributions.
+    pub async fn delete_planned(&self, user_id: i64, planned_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("planned/{}", planned_id))).await
+    }
+
+    pub async fn create_contribution(&self, user_id: i64, planned_id: i64, contribution: &NewContribution) -> ApiResult<PlannedPurchase> {
+        let url = self.user_url(user_id, &format!("planned/{}/contributions", planned_id));
+        self.json(self.http_client.post(url).json(contribution)).await
+    }
+
+    pub async fn weights(&self, user_id: i64) -> ApiResult<Vec<WeightLog>> {
+        self.json(self.http_client.get(self.user_url(user_id, "weight"))).await
+    }
+
+    pub async fn create_weight(&self, user_id: i64, weight: &NewWeight) -> ApiResult<WeightLog> {
+        self.json(self.http_client.post(self.user_url(user_id, "weight")).json(weight)).await
+    }
+
+    pub async fn update_weight(&self, user_id: i64, weight_id: i64, weight: &NewWeight) -> ApiResult<WeightLog> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("weight/{}", weight_id))).json(weight)).await
+    }
+
+    pub async fn delete_weight(&self, user_id: i64, weight_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("weight/{}", weight_id))).await
+    }
+
+    pub async fn calories(&self, user_id: i64) -> ApiResult<Vec<CaloriesLog>> {
+        self.json(self.http_client.get(self.user_url(user_id, "calories"))).await
+    }
+
+    /// Sets the calories burned on a day, replacing any earlier value for that day.
+    pub async fn set_calories(&self, user_id: i64, calories: &NewCalories) -> ApiResult<CaloriesLog> {
+        self.json(self.http_client.post(self.user_url(user_id, "calories")).json(calories)).await
+    }
+
+    pub async fn update_calories(&self, user_id: i64, date: NaiveDate, calories: u32) -> ApiResult<CaloriesLog> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("calories/{}", date))).json(&Calories { calories })).await
+    }
+
+    pub async fn delete_calories(&self, user_id: i64, date: NaiveDate) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("calories/{}", date))).await
+    }
+
+    pub async fn sleeps(&self, user_id: i64) -> ApiResult<Vec<SleepLog>> {
+        self.json(self.http_client.get(self.user_url(user_id, "sleep"))).await
+    }
+
+    pub async fn create_sleep(&self, user_id: i64, sleep: &NewSleep) -> ApiResult<SleepLog> {
+        self.json(self.http_client.post(self.user_url(user_id, "sleep")).json(sleep)).await
+    }
+
+    pub async fn update_sleep(&self, user_id: i64, sleep_id: i64, sleep: &NewSleep) -> ApiResult<SleepLog> {
+        self.json(self.http_client.put(self.user_url(user_id, &format!("sleep/{}", sleep_id))).json(sleep)).await
+    }
+
+    pub async fn delete_sleep(&self, user_id: i64, sleep_id: i64) -> ApiResult<()> {
+        self.delete(self.user_url(user_id, &format!("sleep/{}", sleep_id))).await
+    }
+
+    /// The user's Fitbit tokens, or `None` if they have not connected an account yet.
+    pub async fn fitbit_account(&self, user_id: i64) -> ApiResult<Option<FitbitAccount>> {
+        match self.json(self.http_client.get(self.user_url(user_id, "fitbit"))).await {
+            Ok(account) => Ok(Some(account)),
+            Err(ApiError::Status { status: StatusCode::NOT_FOUND, .. }) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub async fn set_fitbit_account(&self, user_id: i64, account: &FitbitAccount) -> ApiResult<FitbitAccount> {
+        self.json(self.http_client.put(self.user_url(user_id, "fitbit")).json(account)).await
+    }
+}
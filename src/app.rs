@@ -1,28 +1,30 @@
 use std::time::Duration;
 
 use color_eyre::Result;
-use crossterm::event::{Event, EventStream};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{DefaultTerminal, Frame};
 use futures::StreamExt;
 use reqwest::Client;
 
-use crate::{config::config::Config, modules::{servers::{ServerEntry, ServerModule, ServerModules}, users::UserModule, ModuleData, RenderableModule, SelectedModule}};
+use crate::{api::ApiClient, config::config::Config, tasks::Tasks, modules::{health::{HealthModule, HealthModules}, financial::{FinancialModule, FinancialModules}, kitchen_inventory::{KitchenInventoryModule, KitchenInventoryModules}, notebooks::{NotebookModule, NotebookModules}, selection::SelectionModule, servers::{ServerEntry, ServerModule, ServerModules}, users::{UserModule, UserModules}, ModuleData, RenderableModule, SelectedModule}};
 
 pub struct App {
     pub should_quit: bool,
     pub selected_module: SelectedModule,
     pub module_data: ModuleData,
     pub config: Config,
-    pub http_client: Client
+    pub http_client: Client,
+    /// Client for the server picked in the server list
+    pub api_client: ApiClient,
+    pub tasks: Tasks
 }
 
 impl App {
     const FPS: f32 = 60.0;
 
     pub async fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        self.config.initialize();
-        
-        self.module_data.server_module.server_list = self.config.servers.iter().map(|s| ServerEntry::from(s)).collect();
+        self.module_data.server_module.server_list = self.config.servers.iter().map(ServerEntry::from).collect();
+        ServerModule::ping_servers(&mut self);
 
         let period = Duration::from_secs_f32(1.0 / Self::FPS);
         let mut interval = tokio::time::interval(period);
@@ -31,7 +33,8 @@ impl App {
         while !self.should_quit {
             tokio::select! {
                 _ = interval.tick() => { terminal.draw(|frame| self.draw(frame))?; },
-                Some(Ok(event)) = events.next() => self.handle_event(&event).await,
+                Some(Ok(event)) = events.next() => self.handle_event(&event),
+                Some(finished) = self.tasks.receiver.recv() => self.finish_request(finished),
             }
         }
         Ok(())
@@ -41,27 +44,85 @@ impl App {
         match self.selected_module {
             SelectedModule::ServerModule(_) => ServerModule::render(self, frame),
             SelectedModule::UserModule(_) => UserModule::render(self, frame),
+            SelectedModule::SelectionModule(_) => SelectionModule::render(self, frame),
+            SelectedModule::NotebookModule(_) => NotebookModule::render(self, frame),
+            SelectedModule::KitchenInventoryModule(_) => KitchenInventoryModule::render(self, frame),
+            SelectedModule::FinancialModule(_) => FinancialModule::render(self, frame),
+            SelectedModule::HealthModule(_) => HealthModule::render(self, frame),
         }
+        self.render_tasks(frame);
     }
 
-    async fn handle_event(&mut self, event: &Event) {
-        if let Event::Key(key) = event {
-            match self.selected_module {
-                SelectedModule::ServerModule(_) => ServerModule::input_handling(self, *key).await,
-                SelectedModule::UserModule(_) => UserModule::input_handling(self, *key).await,
-            };
+    fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::Key(key) => self.handle_key(*key),
+            // Pasted text is typed into the focused input, which keeps the forms' validation in
+            // charge of it. The inputs are a single line, so line breaks become spaces.
+            Event::Paste(text) if self.text_input_focused() && !self.tasks.has_error() => {
+                for c in text.trim_end_matches(['\r', '\n']).chars().filter(|c| *c != '\r') {
+                    let c = if c.is_control() { ' ' } else { c };
+                    self.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+                }
+            },
+            _ => {}
         }
     }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        // An error popup takes every key until it is retried or dismissed
+        if self.tasks.has_error() {
+            self.tasks.input_handling(key);
+            return;
+        }
+        match self.selected_module {
+            SelectedModule::ServerModule(_) => ServerModule::input_handling(self, key),
+            SelectedModule::UserModule(_) => UserModule::input_handling(self, key),
+            SelectedModule::SelectionModule(_) => SelectionModule::input_handling(self, key),
+            SelectedModule::NotebookModule(_) => NotebookModule::input_handling(self, key),
+            SelectedModule::KitchenInventoryModule(_) => KitchenInventoryModule::input_handling(self, key),
+            SelectedModule::FinancialModule(_) => FinancialModule::input_handling(self, key),
+            SelectedModule::HealthModule(_) => HealthModule::input_handling(self, key),
+        };
+        self.tasks.visit(&self.selected_module);
+    }
+
+    /// Whether a form with a text input is open. Elsewhere letters are keybinds, so pasting
+    /// there could delete things.
+    fn text_input_focused(&self) -> bool {
+        matches!(self.selected_module,
+            SelectedModule::ServerModule(ServerModules::ServerEditing(_))
+            | SelectedModule::UserModule(UserModules::UserEditing(_))
+            | SelectedModule::NotebookModule(NotebookModules::NotebookEditing(_))
+            | SelectedModule::KitchenInventoryModule(KitchenInventoryModules::ItemEditing(_))
+            | SelectedModule::FinancialModule(
+                FinancialModules::AccountEditing(_)
+                | FinancialModules::PurchaseEditing(_)
+                | FinancialModules::RecurringEditing(_)
+                | FinancialModules::PlannedEditing(_)
+                | FinancialModules::ContributionEditing(_))
+            | SelectedModule::HealthModule(
+                HealthModules::WeightEditing(_)
+                | HealthModules::CaloriesEditing(_)
+                | HealthModules::SleepEditing(_)
+                | HealthModules::ImportSetup(_)
+                | HealthModules::FitbitAuthorizing))
+    }
 }
 
-impl Default for App {
-    fn default() -> Self {
+impl App {
+    /// `config_warning` is shown above the server list, it is set when the config file could not
+    /// be read.
+    pub fn new(config: Config, config_warning: Option<String>) -> Self {
+        let mut module_data = ModuleData::default();
+        module_data.server_module.notice = config_warning;
         Self {
             should_quit: false,
-            module_data: ModuleData::default(),
+            module_data,
             selected_module: SelectedModule::ServerModule(ServerModules::ServerList),
-            config: Config::default(),
+            config,
             http_client: reqwest::Client::new(),
+            api_client: ApiClient::default(),
+            tasks: Tasks::default(),
         }
     }
 }
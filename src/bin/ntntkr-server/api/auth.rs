@@ -0,0 +1,170 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use axum::{extract::{Request, State}, http::header::AUTHORIZATION, middleware::Next, response::{IntoResponse, Response}, routing::post, Json, Router};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+use super::{ApiError, ApiResult};
+
+/// How long a token from `/api/session` is accepted. Clients log in again once theirs runs out.
+const SESSION_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Sessions kept at once, the oldest one makes room when a login would go over.
+const MAX_SESSIONS: usize = 1000;
+
+/// Credentials the server was started with. When there are none every request is let through,
+/// which is how servers on a trusted machine have always worked.
+#[derive(Default)]
+pub struct Auth {
+    /// Accepted as a bearer token on every request
+    pub token: Option<String>,
+    /// Username and password that can be exchanged for a session token
+    pub login: Option<(String, String)>,
+    /// Tokens handed out by `/api/session` and when, they last `SESSION_LIFETIME` at most
+    sessions: Mutex<HashMap<String, Instant>>
+}
+
+pub type SharedAuth = Arc<Auth>;
+
+impl Auth {
+    fn is_open(&self) -> bool {
+        self.token.is_none() && self.login.is_none()
+    }
+
+    fn accepts(&self, token: &str) -> bool {
+        self.token.as_deref().is_some_and(|expected| same_secret(token, expected)) || self.has_session(token, Instant::now())
+    }
+
+    fn has_session(&self, token: &str, now: Instant) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(token) {
+            Some(started) if now.duration_since(*started) < SESSION_LIFETIME => true,
+            Some(_) => {
+                sessions.remove(token);
+                false
+            },
+            None => false,
+        }
+    }
+
+    fn add_session(&self, token: String, now: Instant) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, started| now.duration_since(*started) < SESSION_LIFETIME);
+        if sessions.len() >= MAX_SESSIONS {
+            if let Some(oldest) = sessions.iter().min_by_key(|(_, started)| **started).map(|(token, _)| token.clone()) {
+                sessions.remove(&oldest);
+            }
+        }
+        sessions.insert(token, now);
+    }
+}
+
+/// Compares digests rather than the secrets themselves, so how long the comparison takes says
+/// nothing about how much of a guess was right.
+fn same_secret(given: &str, expected: &str) -> bool {
+    Sha256::digest(given.as_bytes()) == Sha256::digest(expected.as_bytes())
+}
+
+pub fn routes<S>(auth: SharedAuth) -> Router<S> {
+    Router::new()
+        .route("/api/session", post(login))
+        .with_state(auth)
+}
+
+#[derive(serde::Deserialize)]
+struct Login {
+    username: String,
+    password: String
+}
+
+#[derive(serde::Serialize)]
+struct Session {
+    token: String
+}
+
+async fn login(State(auth): State<SharedAuth>, Json(request): Json<Login>) -> ApiResult<Json<Session>> {
+    let Some((username, password)) = &auth.login else { return Err(ApiError::Unauthorized("password login is not enabled on this server")) };
+    if !(same_secret(&request.username, username) && same_secret(&request.password, password)) {
+        return Err(ApiError::Unauthorized("wrong username or password"));
+    }
+
+    let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(48).map(char::from).collect();
+    auth.add_session(token.clone(), Instant::now());
+    Ok(Json(Session { token }))
+}
+
+/// Turns away requests without a bearer token the server knows about.
+pub async fn require_auth(State(auth): State<SharedAuth>, request: Request, next: Next) -> Response {
+    if auth.is_open() {
+        return next.run(request).await;
+    }
+
+    let token = request.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok()).and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if auth.accepts(token) => next.run(request).await,
+        Some(_) => ApiError::Unauthorized("invalid or expired token").into_response(),
+        None => ApiError::Unauthorized("this server needs a token or a login").into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_auth() -> Auth {
+        Auth { login: Some(("sam".to_string(), "secret".to_string())), ..Auth::default() }
+    }
+
+    #[test]
+    fn sessions_expire() {
+        let auth = login_auth();
+        let now = Instant::now();
+        auth.add_session("abc".to_string(), now);
+
+        assert!(auth.has_session("abc", now));
+        assert!(auth.has_session("abc", now + SESSION_LIFETIME - Duration::from_secs(1)));
+        assert!(!auth.has_session("abc", now + SESSION_LIFETIME));
+        // An expired session is gone for good
+        assert!(!auth.has_session("abc", now));
+        assert!(!auth.has_session("other", now));
+    }
+
+    #[test]
+    fn oldest_session_makes_room() {
+        let auth = login_auth();
+        let now = Instant::now();
+        for i in 0..MAX_SESSIONS {
+            auth.add_session(format!("token{}", i), now + Duration::from_secs(i as u64));
+        }
+        let later = now + Duration::from_secs(MAX_SESSIONS as u64);
+        auth.add_session("newest".to_string(), later);
+
+        assert_eq!(auth.sessions.lock().unwrap().len(), MAX_SESSIONS);
+        assert!(!auth.has_session("token0", later));
+        assert!(auth.has_session("token1", later));
+        assert!(auth.has_session("newest", later));
+    }
+
+    #[test]
+    fn expired_sessions_are_dropped_on_login() {
+        let auth = login_auth();
+        let now = Instant::now();
+        auth.add_session("old".to_string(), now);
+        auth.add_session("new".to_string(), now + SESSION_LIFETIME);
+
+        let sessions = auth.sessions.lock().unwrap();
+        assert!(!sessions.contains_key("old"));
+        assert!(sessions.contains_key("new"));
+    }
+
+    #[test]
+    fn token_or_session_is_accepted() {
+        let auth = Auth { token: Some("api-token".to_string()), ..login_auth() };
+        auth.add_session("session".to_string(), Instant::now());
+        assert!(auth.accepts("api-token"));
+        assert!(auth.accepts("session"));
+        assert!(!auth.accepts("api-token2"));
+        assert!(!auth.is_open());
+        assert!(Auth::default().is_open());
+    }
+}
@@ -0,0 +1,289 @@
+use axum::{extract::{Path, State}, http::StatusCode, routing::{get, post, put}, Json, Router};
+use chrono::NaiveDate;
+use ntntkr::modules::financial::recurring::Schedule;
+
+use crate::{models::{Account, Contribution, PlannedPurchase, Purchase, RecurringPurchase}, store::Store};
+
+use super::{deleted, require_text, require_user, with_store, ApiError, ApiResult, SharedStore};
+
+pub fn routes() -> Router<SharedStore> {
+    Router::new()
+        .route("/api/users/{user_id}/accounts", get(list_accounts).post(create_account))
+        .route("/api/users/{user_id}/accounts/{id}", put(update_account).delete(delete_account))
+        .route("/api/users/{user_id}/purchases", get(list_purchases).post(create_purchase))
+        .route("/api/users/{user_id}/purchases/{id}", put(update_purchase).delete(delete_purchase))
+        .route("/api/users/{user_id}/recurring", get(list_recurring).post(create_recurring))
+        .route("/api/users/{user_id}/recurring/{id}", put(update_recurring).delete(delete_recurring))
+        .route("/api/users/{user_id}/recurring/{id}/last_materialized", put(set_last_materialized))
+        .route("/api/users/{user_id}/planned", get(list_planned).post(create_planned))
+        .route("/api/users/{user_id}/planned/{id}", put(update_planned).delete(delete_planned))
+        .route("/api/users/{user_id}/planned/{id}/contributions", post(create_contribution))
+}
+
+/// Records that reference an account may only use the user's own accounts.
+fn require_account(store: &Store, user_id: i64, account_id: i64) -> ApiResult<i64> {
+    match store.accounts(user_id)?.iter().any(|a| a.id == account_id) {
+        true => Ok(account_id),
+        false => Err(ApiError::BadRequest(format!("unknown account {}", account_id))),
+    }
+}
+
+/// Purchases can only be linked to the user's own recurring purchases.
+fn require_recurring(store: &Store, user_id: i64, recurring_id: Option<i64>) -> ApiResult<Option<i64>> {
+    let Some(recurring_id) = recurring_id else { return Ok(None) };
+    match store.recurring(user_id)?.iter().any(|r| r.id == recurring_id) {
+        true => Ok(Some(recurring_id)),
+        false => Err(ApiError::BadRequest(format!("unknown recurring purchase {}", recurring_id))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NewAccount {
+    name: String,
+    account_type: String,
+    starting_balance: i64
+}
+
+impl NewAccount {
+    fn into_account(self, user_id: i64, id: i64) -> ApiResult<Account> {
+        Ok(Account {
+            id,
+            user_id,
+            name: require_text("name", &self.name)?,
+            account_type: require_text("account_type", &self.account_type)?,
+            starting_balance: self.starting_balance,
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NewPurchase {
+    account_id: i64,
+    date: NaiveDate,
+    amount: i64,
+    payee: String,
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    recurring_id: Option<i64>
+}
+
+impl NewPurchase {
+    fn into_purchase(self, store: &Store, user_id: i64, id: i64) -> ApiResult<Purchase> {
+        Ok(Purchase {
+            id,
+            user_id,
+            account_id: require_account(store, user_id, self.account_id)?,
+            date: self.date,
+            amount: self.amount,
+            payee: require_text("payee", &self.payee)?,
+            category: self.category.trim().to_string(),
+            recurring_id: require_recurring(store, user_id, self.recurring_id)?,
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NewRecurring {
+    account_id: i64,
+    amount: i64,
+    payee: String,
+    #[serde(default)]
+    category: String,
+    schedule: String,
+    start_date: NaiveDate,
+    #[serde(default)]
+    end_date: Option<NaiveDate>
+}
+
+impl NewRecurring {
+    /// Nothing has been materialized for the returned recurring purchase yet. The schedule has
+    /// to be one the client can parse, it is stored in its canonical form.
+    fn into_recurring(self, store: &Store, user_id: i64, id: i64) -> ApiResult<RecurringPurchase> {
+        let schedule = self.schedule.parse::<Schedule>().map_err(ApiError::BadRequest)?;
+        Ok(RecurringPurchase {
+            id,
+            user_id,
+            account_id: require_account(store, user_id, self.account_id)?,
+            amount: self.amount,
+            payee: require_text("payee", &self.payee)?,
+            category: self.category.trim().to_string(),
+            schedule: schedule.to_string(),
+            start_date: self.start_date,
+            end_date: self.end_date,
+            last_materialized: None,
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Materialized {
+    last_materialized: NaiveDate
+}
+
+#[derive(serde::Deserialize)]
+struct NewPlanned {
+    account_id: i64,
+    name: String,
+    target_amount: i64,
+    target_date: NaiveDate
+}
+
+impl NewPlanned {
+    fn into_planned(self, store: &Store, user_id: i64, id: i64) -> ApiResult<PlannedPurchase> {
+        Ok(PlannedPurchase {
+            id,
+            user_id,
+            account_id: require_account(store, user_id, self.account_id)?,
+            name: require_text("name", &self.name)?,
+            target_amount: self.target_amount,
+            target_date: self.target_date,
+            contributions: Vec::new(),
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NewContribution {
+    date: NaiveDate,
+    amount: i64
+}
+
+async fn list_accounts(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<Account>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.accounts(user_id)?))
+    }).await
+}
+
+async fn create_account(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewAccount>) -> ApiResult<(StatusCode, Json<Account>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let account = request.into_account(user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_account(account)?)))
+    }).await
+}
+
+async fn update_account(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewAccount>) -> ApiResult<Json<Account>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let account = request.into_account(user_id, id)?;
+        store.update_account(&account)?.map(Json).ok_or(ApiError::NotFound("account"))
+    }).await
+}
+
+async fn delete_account(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_account(user_id, id)?, "account")
+    }).await
+}
+
+async fn list_purchases(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<Purchase>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.purchases(user_id)?))
+    }).await
+}
+
+async fn create_purchase(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewPurchase>) -> ApiResult<(StatusCode, Json<Purchase>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let purchase = request.into_purchase(store, user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_purchase(purchase)?)))
+    }).await
+}
+
+async fn update_purchase(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewPurchase>) -> ApiResult<Json<Purchase>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let purchase = request.into_purchase(store, user_id, id)?;
+        store.update_purchase(&purchase)?.map(Json).ok_or(ApiError::NotFound("purchase"))
+    }).await
+}
+
+async fn delete_purchase(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_purchase(user_id, id)?, "purchase")
+    }).await
+}
+
+async fn list_recurring(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<RecurringPurchase>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.recurring(user_id)?))
+    }).await
+}
+
+async fn create_recurring(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewRecurring>) -> ApiResult<(StatusCode, Json<RecurringPurchase>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let recurring = request.into_recurring(store, user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_recurring(recurring)?)))
+    }).await
+}
+
+async fn update_recurring(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewRecurring>) -> ApiResult<Json<RecurringPurchase>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let recurring = request.into_recurring(store, user_id, id)?;
+        store.update_recurring(&recurring)?.map(Json).ok_or(ApiError::NotFound("recurring purchase"))
+    }).await
+}
+
+async fn delete_recurring(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_recurring(user_id, id)?, "recurring purchase")
+    }).await
+}
+
+async fn set_last_materialized(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<Materialized>) -> ApiResult<Json<RecurringPurchase>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        store.set_last_materialized(user_id, id, request.last_materialized)?.map(Json).ok_or(ApiError::NotFound("recurring purchase"))
+    }).await
+}
+
+async fn list_planned(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<PlannedPurchase>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.planned(user_id)?))
+    }).await
+}
+
+async fn create_planned(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewPlanned>) -> ApiResult<(StatusCode, Json<PlannedPurchase>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let planned = request.into_planned(store, user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_planned(planned)?)))
+    }).await
+}
+
+async fn update_planned(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewPlanned>) -> ApiResult<Json<PlannedPurchase>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let planned = request.into_planned(store, user_id, id)?;
+        store.update_planned(&planned)?.map(Json).ok_or(ApiError::NotFound("planned purchase"))
+    }).await
+}
+
+async fn delete_planned(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_planned(user_id, id)?, "planned purchase")
+    }).await
+}
+
+async fn create_contribution(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewContribution>) -> ApiResult<(StatusCode, Json<PlannedPurchase>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let contribution = Contribution {
+            date: request.date,
+            amount: request.amount,
+        };
+        let planned = store.add_contribution(user_id, id, contribution)?.ok_or(ApiError::NotFound("planned purchase"))?;
+        Ok((StatusCode::CREATED, Json(planned)))
+    }).await
+}
@@ -0,0 +1,200 @@
+use axum::{extract::{Path, State}, http::StatusCode, routing::{get, put}, Json, Router};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::models::{CaloriesLog, FitbitAccount, SleepLog, WeightLog};
+
+use super::{deleted, require_text, require_user, with_store, ApiError, ApiResult, SharedStore};
+
+pub fn routes() -> Router<SharedStore> {
+    Router::new()
+        .route("/api/users/{user_id}/weight", get(list_weights).post(create_weight))
+        .route("/api/users/{user_id}/weight/{id}", put(update_weight).delete(delete_weight))
+        .route("/api/users/{user_id}/calories", get(list_calories).post(set_calories))
+        .route("/api/users/{user_id}/calories/{date}", put(update_calories).delete(delete_calories))
+        .route("/api/users/{user_id}/sleep", get(list_sleeps).post(create_sleep))
+        .route("/api/users/{user_id}/sleep/{id}", put(update_sleep).delete(delete_sleep))
+        .route("/api/users/{user_id}/fitbit", get(get_fitbit_account).put(set_fitbit_account))
+}
+
+#[derive(serde::Deserialize)]
+struct NewWeight {
+    date: NaiveDate,
+    weight: f64,
+    unit: String,
+    #[serde(default)]
+    fitbit_log_id: Option<i64>
+}
+
+impl NewWeight {
+    fn into_weight(self, user_id: i64, id: i64) -> ApiResult<WeightLog> {
+        let unit = self.unit.trim().to_lowercase();
+        if unit != "kg" && unit != "lb" {
+            return Err(ApiError::BadRequest(format!("invalid unit '{}'", self.unit)));
+        }
+        let weight = self.weight;
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(ApiError::BadRequest(format!("invalid weight '{}'", weight)));
+        }
+        Ok(WeightLog { id, user_id, date: self.date, weight, unit, fitbit_log_id: self.fitbit_log_id })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NewCalories {
+    date: NaiveDate,
+    calories: u32
+}
+
+/// The calories for the day in the path.
+#[derive(serde::Deserialize)]
+struct Calories {
+    calories: u32
+}
+
+#[derive(serde::Deserialize)]
+struct NewSleep {
+    bed_time: NaiveDateTime,
+    wake_time: NaiveDateTime,
+    #[serde(default)]
+    quality: Option<u8>,
+    #[serde(default)]
+    fitbit_log_id: Option<i64>
+}
+
+impl NewSleep {
+    fn into_sleep(self, user_id: i64, id: i64) -> ApiResult<SleepLog> {
+        let (bed_time, wake_time) = (self.bed_time, self.wake_time);
+        if wake_time <= bed_time {
+            return Err(ApiError::BadRequest("wake_time must be after bed_time".to_string()));
+        }
+        let quality = self.quality;
+        if let Some(quality) = quality.filter(|q| *q > 100) {
+            return Err(ApiError::BadRequest(format!("invalid quality '{}'", quality)));
+        }
+        Ok(SleepLog { id, user_id, bed_time, wake_time, quality, fitbit_log_id: self.fitbit_log_id })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NewFitbitAccount {
+    access_token: String,
+    refresh_token: String,
+    expires_at: DateTime<Utc>,
+    #[serde(default)]
+    last_synced: Option<NaiveDate>
+}
+
+async fn list_weights(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<WeightLog>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.weights(user_id)?))
+    }).await
+}
+
+async fn create_weight(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewWeight>) -> ApiResult<(StatusCode, Json<WeightLog>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let log = request.into_weight(user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_weight(log)?)))
+    }).await
+}
+
+async fn update_weight(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewWeight>) -> ApiResult<Json<WeightLog>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let log = request.into_weight(user_id, id)?;
+        store.update_weight(&log)?.map(Json).ok_or(ApiError::NotFound("weight log"))
+    }).await
+}
+
+async fn delete_weight(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_weight(user_id, id)?, "weight log")
+    }).await
+}
+
+async fn list_calories(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<CaloriesLog>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.calories(user_id)?))
+    }).await
+}
+
+async fn set_calories(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewCalories>) -> ApiResult<Json<CaloriesLog>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let log = CaloriesLog {
+            user_id,
+            date: request.date,
+            calories: request.calories,
+        };
+        Ok(Json(store.set_calories(log)?))
+    }).await
+}
+
+/// Like posting calories, the day is created when nothing was logged for it yet.
+async fn update_calories(State(store): State<SharedStore>, Path((user_id, date)): Path<(i64, NaiveDate)>, Json(request): Json<Calories>) -> ApiResult<Json<CaloriesLog>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.set_calories(CaloriesLog { user_id, date, calories: request.calories })?))
+    }).await
+}
+
+async fn delete_calories(State(store): State<SharedStore>, Path((user_id, date)): Path<(i64, NaiveDate)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_calories(user_id, date)?, "calories log")
+    }).await
+}
+
+async fn list_sleeps(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<SleepLog>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.sleeps(user_id)?))
+    }).await
+}
+
+async fn create_sleep(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewSleep>) -> ApiResult<(StatusCode, Json<SleepLog>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let log = request.into_sleep(user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_sleep(log)?)))
+    }).await
+}
+
+async fn update_sleep(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewSleep>) -> ApiResult<Json<SleepLog>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let log = request.into_sleep(user_id, id)?;
+        store.update_sleep(&log)?.map(Json).ok_or(ApiError::NotFound("sleep log"))
+    }).await
+}
+
+async fn delete_sleep(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_sleep(user_id, id)?, "sleep log")
+    }).await
+}
+
+async fn get_fitbit_account(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<FitbitAccount>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        store.fitbit_account(user_id)?.map(Json).ok_or(ApiError::NotFound("fitbit account"))
+    }).await
+}
+
+async fn set_fitbit_account(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewFitbitAccount>) -> ApiResult<Json<FitbitAccount>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let account = FitbitAccount {
+            user_id,
+            access_token: require_text("access_token", &request.access_token)?,
+            refresh_token: require_text("refresh_token", &request.refresh_token)?,
+            expires_at: request.expires_at,
+            last_synced: request.last_synced,
+        };
+        Ok(Json(store.set_fitbit_account(account)?))
+    }).await
+}
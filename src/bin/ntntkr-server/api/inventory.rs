@@ -0,0 +1,53 @@
+use axum::{extract::{Path, State}, http::StatusCode, routing::{get, put}, Json, Router};
+
+use crate::models::InventoryItem;
+
+use super::{deleted, require_text, require_user, with_store, ApiError, ApiResult, SharedStore};
+
+pub fn routes() -> Router<SharedStore> {
+    Router::new()
+        .route("/api/users/{user_id}/inventory", get(list_items).post(create_item))
+        .route("/api/users/{user_id}/inventory/{id}", put(update_item).delete(delete_item))
+}
+
+#[derive(serde::Deserialize)]
+struct NewItem {
+    name: String,
+    in_kitchen: bool
+}
+
+impl NewItem {
+    fn into_item(self, user_id: i64, id: i64) -> ApiResult<InventoryItem> {
+        Ok(InventoryItem { id, user_id, name: require_text("name", &self.name)?, in_kitchen: self.in_kitchen })
+    }
+}
+
+async fn list_items(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<InventoryItem>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.inventory(user_id)?))
+    }).await
+}
+
+async fn create_item(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewItem>) -> ApiResult<(StatusCode, Json<InventoryItem>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let item = request.into_item(user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_item(item)?)))
+    }).await
+}
+
+async fn update_item(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewItem>) -> ApiResult<Json<InventoryItem>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let item = request.into_item(user_id, id)?;
+        store.update_item(&item)?.map(Json).ok_or(ApiError::NotFound("item"))
+    }).await
+}
+
+async fn delete_item(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_item(user_id, id)?, "item")
+    }).await
+}
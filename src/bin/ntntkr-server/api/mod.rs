@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use axum::{http::StatusCode, middleware, response::{IntoResponse, Response}, Router};
+use tokio::task::JoinError;
+
+use crate::store::{Store, StoreError};
+
+pub mod auth;
+mod financial;
+mod health;
+mod inventory;
+mod notebooks;
+mod status;
+mod users;
+
+pub type SharedStore = Arc<Store>;
+
+pub enum ApiError {
+    NotFound(&'static str),
+    BadRequest(String),
+    Unauthorized(&'static str),
+    Store(StoreError),
+    /// A store call on the blocking pool panicked
+    Task(JoinError)
+}
+
+impl From<StoreError> for ApiError {
+    fn from(error: StoreError) -> Self {
+        ApiError::Store(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::NotFound(what) => (StatusCode::NOT_FOUND, format!("{} not found", what)).into_response(),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message).into_response(),
+            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message).into_response(),
+            ApiError::Store(StoreError::InUse(what)) => (StatusCode::CONFLICT, format!("{} is still in use", what)).into_response(),
+            ApiError::Store(error) => {
+                eprintln!("Store error: {}", error);
+                (StatusCode::INTERNAL_SERVER_ERROR, "storage error").into_response()
+            },
+            ApiError::Task(error) => {
+                eprintln!("Store task failed: {}", error);
+                (StatusCode::INTERNAL_SERVER_ERROR, "storage error").into_response()
+            }
+        }
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// The health check and login stay reachable without credentials, everything else needs them
+/// when the server was started with any.
+pub fn router(store: SharedStore, auth: auth::SharedAuth) -> Router {
+    let protected = Router::new()
+        .merge(users::routes())
+        .merge(notebooks::routes())
+        .merge(inventory::routes())
+        .merge(financial::routes())
+        .merge(health::routes())
+        .route_layer(middleware::from_fn_with_state(auth.clone(), auth::require_auth));
+
+    Router::new()
+        .merge(protected)
+        .merge(status::routes())
+        .merge(auth::routes(auth))
+        .with_state(store)
+}
+
+/// Runs `f` against the store on the blocking thread pool.
+async fn with_store<T, F>(store: SharedStore, f: F) -> ApiResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&Store) -> ApiResult<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&store)).await.map_err(ApiError::Task)?
+}
+
+/// Every per user route answers 404 for users that do not exist rather than returning empty lists.
+fn require_user(store: &Store, user_id: i64) -> ApiResult<()> {
+    store.user(user_id)?.map(|_| ()).ok_or(ApiError::NotFound("user"))
+}
+
+/// Answers a delete with 204, or 404 naming `what` when there was nothing to delete.
+fn deleted(deleted: bool, what: &'static str) -> ApiResult<StatusCode> {
+    match deleted {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(ApiError::NotFound(what)),
+    }
+}
+
+fn require_text(name: &str, text: &str) -> ApiResult<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ApiError::BadRequest(format!("{} is required", name)));
+    }
+    Ok(text.to_string())
+}
@@ -0,0 +1,72 @@
+use axum::{extract::{Path, State}, http::StatusCode, routing::{get, post, put}, Json, Router};
+use chrono::Utc;
+
+use crate::models::Notebook;
+
+use super::{deleted, require_text, require_user, with_store, ApiError, ApiResult, SharedStore};
+
+pub fn routes() -> Router<SharedStore> {
+    Router::new()
+        .route("/api/users/{user_id}/notebooks", get(list_notebooks).post(create_notebook))
+        .route("/api/users/{user_id}/notebooks/{id}", put(update_notebook).delete(delete_notebook))
+        .route("/api/users/{user_id}/notebooks/{id}/used", post(mark_used))
+}
+
+#[derive(serde::Deserialize)]
+struct NewNotebook {
+    name: String,
+    #[serde(default)]
+    description: String
+}
+
+impl NewNotebook {
+    /// Both timestamps are set to now, updates keep the stored ones.
+    fn into_notebook(self, user_id: i64, id: i64) -> ApiResult<Notebook> {
+        let now = Utc::now();
+        Ok(Notebook {
+            id,
+            user_id,
+            name: require_text("name", &self.name)?,
+            description: self.description.trim().to_string(),
+            created: now,
+            last_used: now,
+        })
+    }
+}
+
+async fn list_notebooks(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<Json<Vec<Notebook>>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        Ok(Json(store.notebooks(user_id)?))
+    }).await
+}
+
+async fn create_notebook(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewNotebook>) -> ApiResult<(StatusCode, Json<Notebook>)> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let notebook = request.into_notebook(user_id, 0)?;
+        Ok((StatusCode::CREATED, Json(store.insert_notebook(notebook)?)))
+    }).await
+}
+
+async fn update_notebook(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>, Json(request): Json<NewNotebook>) -> ApiResult<Json<Notebook>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        let notebook = request.into_notebook(user_id, id)?;
+        store.update_notebook(&notebook)?.map(Json).ok_or(ApiError::NotFound("notebook"))
+    }).await
+}
+
+async fn delete_notebook(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        deleted(store.delete_notebook(user_id, id)?, "notebook")
+    }).await
+}
+
+async fn mark_used(State(store): State<SharedStore>, Path((user_id, id)): Path<(i64, i64)>) -> ApiResult<Json<Notebook>> {
+    with_store(store, move |store| {
+        require_user(store, user_id)?;
+        store.touch_notebook(user_id, id, Utc::now())?.map(Json).ok_or(ApiError::NotFound("notebook"))
+    }).await
+}
@@ -0,0 +1,20 @@
+use axum::{extract::State, routing::get, Json, Router};
+
+use super::{with_store, ApiResult, SharedStore};
+
+pub fn routes() -> Router<SharedStore> {
+    Router::new()
+        .route("/api/health", get(health))
+}
+
+#[derive(serde::Serialize)]
+struct Health {
+    version: &'static str,
+    schema_version: usize
+}
+
+/// Cheap to answer, clients use it to check whether the server is up.
+async fn health(State(store): State<SharedStore>) -> ApiResult<Json<Health>> {
+    let schema_version = with_store(store, |store| Ok(store.schema_version()?)).await?;
+    Ok(Json(Health { version: env!("CARGO_PKG_VERSION"), schema_version }))
+}
@@ -0,0 +1,34 @@
+use axum::{extract::{Path, State}, http::StatusCode, routing::{get, put}, Json, Router};
+
+use crate::models::User;
+
+use super::{deleted, require_text, with_store, ApiError, ApiResult, SharedStore};
+
+pub fn routes() -> Router<SharedStore> {
+    Router::new()
+        .route("/api/users", get(list_users).post(create_user))
+        .route("/api/users/{user_id}", put(rename_user).delete(delete_user))
+}
+
+#[derive(serde::Deserialize)]
+struct NewUser {
+    name: String
+}
+
+async fn list_users(State(store): State<SharedStore>) -> ApiResult<Json<Vec<User>>> {
+    with_store(store, |store| Ok(Json(store.users()?))).await
+}
+
+async fn create_user(State(store): State<SharedStore>, Json(request): Json<NewUser>) -> ApiResult<(StatusCode, Json<User>)> {
+    let user = User { id: 0, name: require_text("name", &request.name)? };
+    with_store(store, |store| Ok((StatusCode::CREATED, Json(store.insert_user(user)?)))).await
+}
+
+async fn rename_user(State(store): State<SharedStore>, Path(user_id): Path<i64>, Json(request): Json<NewUser>) -> ApiResult<Json<User>> {
+    let name = require_text("name", &request.name)?;
+    with_store(store, move |store| store.rename_user(user_id, &name)?.map(Json).ok_or(ApiError::NotFound("user"))).await
+}
+
+async fn delete_user(State(store): State<SharedStore>, Path(user_id): Path<i64>) -> ApiResult<StatusCode> {
+    with_store(store, move |store| deleted(store.delete_user(user_id)?, "user")).await
+}
@@ -0,0 +1,95 @@
+mod api;
+mod models;
+mod store;
+
+use std::{path::PathBuf, sync::Arc};
+
+use color_eyre::{eyre::{bail, WrapErr}, Result};
+use api::auth::Auth;
+use store::Store;
+
+const USAGE: &str = "Usage: ntntkr-server [--address ADDRESS] [--port PORT] [--data FILE] [--token TOKEN] [--login USER:PASSWORD]
+       ntntkr-server [--data FILE] --import JSON_FILE
+
+Serves the ntntkr tracker API.
+
+Options:
+  --address ADDRESS      Address to listen on (default 127.0.0.1)
+  --port PORT            Port to listen on (default 8000)
+  --data FILE            SQLite database the tracker data is kept in (default ntntkr.db)
+  --token TOKEN          Require this API token as a bearer token, read from NTNTKR_TOKEN when
+                         not given
+  --login USER:PASSWORD  Let clients log in with this username and password, read from
+                         NTNTKR_LOGIN when not given
+  --import JSON_FILE     Copy the records from a ntntkr-data.json file written by older versions
+                         into the database, which must not have any users yet, and exit
+
+Without a token or login every request is accepted. Command line arguments can be seen by
+other users on the machine, so prefer the environment variables for real secrets.";
+
+struct Args {
+    address: String,
+    port: u16,
+    data: PathBuf,
+    import: Option<PathBuf>,
+    auth: Auth
+}
+
+impl Args {
+    fn parse() -> Result<Option<Self>> {
+        let mut args = Args { address: "127.0.0.1".to_string(), port: 8000, data: PathBuf::from("ntntkr.db"), import: None, auth: Auth::default() };
+
+        let mut input = std::env::args().skip(1);
+        while let Some(arg) = input.next() {
+            let mut value = || input.next().ok_or_else(|| color_eyre::eyre::eyre!("{} needs a value\n\n{}", arg, USAGE));
+            match arg.as_str() {
+                "--address" => args.address = value()?,
+                "--port" => args.port = value()?.parse().wrap_err("--port must be a number between 0 and 65535")?,
+                "--data" => args.data = PathBuf::from(value()?),
+                "--import" => args.import = Some(PathBuf::from(value()?)),
+                "--token" => args.auth.token = Some(value()?),
+                "--login" => args.auth.login = Some(parse_login(&value()?).wrap_err("--login must look like USER:PASSWORD")?),
+                "-h" | "--help" => return Ok(None),
+                _ => bail!("unknown argument {}\n\n{}", arg, USAGE),
+            }
+        }
+
+        if args.auth.token.is_none() {
+            args.auth.token = std::env::var("NTNTKR_TOKEN").ok().filter(|token| !token.is_empty());
+        }
+        if args.auth.login.is_none() {
+            if let Some(login) = std::env::var("NTNTKR_LOGIN").ok().filter(|login| !login.is_empty()) {
+                args.auth.login = Some(parse_login(&login).wrap_err("NTNTKR_LOGIN must look like USER:PASSWORD")?);
+            }
+        }
+        Ok(Some(args))
+    }
+}
+
+fn parse_login(login: &str) -> Result<(String, String)> {
+    let Some((username, password)) = login.split_once(':') else { bail!("no ':' between the username and password") };
+    Ok((username.to_string(), password.to_string()))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    let Some(args) = Args::parse()? else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let store = Store::open(&args.data).wrap_err_with(|| format!("could not open {}", args.data.display()))?;
+    if let Some(import) = &args.import {
+        let imported = store.import_json(import).wrap_err_with(|| format!("could not import {} into {}", import.display(), args.data.display()))?;
+        println!("Imported {} records from {} into {}", imported, import.display(), args.data.display());
+        return Ok(());
+    }
+    let store_version = store.schema_version()?;
+    let app = api::router(Arc::new(store), Arc::new(args.auth));
+
+    let listener = tokio::net::TcpListener::bind((args.address.as_str(), args.port)).await.wrap_err_with(|| format!("could not listen on {}:{}", args.address, args.port))?;
+    println!("ntntkr-server listening on {}, data in {} (schema version {})", listener.local_addr()?, args.data.display(), store_version);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
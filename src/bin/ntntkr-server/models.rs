@@ -0,0 +1,118 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct User {
+    pub id: i64,
+    pub name: String
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Notebook {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub description: String,
+    pub created: DateTime<Utc>,
+    pub last_used: DateTime<Utc>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct InventoryItem {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub in_kitchen: bool
+}
+
+/// Money is stored in cents throughout the financial records.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Account {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub account_type: String,
+    pub starting_balance: i64
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Purchase {
+    pub id: i64,
+    pub user_id: i64,
+    pub account_id: i64,
+    pub date: NaiveDate,
+    pub amount: i64,
+    pub payee: String,
+    pub category: String,
+    /// Set when the purchase was materialized from a recurring purchase
+    pub recurring_id: Option<i64>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct RecurringPurchase {
+    pub id: i64,
+    pub user_id: i64,
+    pub account_id: i64,
+    pub amount: i64,
+    pub payee: String,
+    pub category: String,
+    /// Schedule in the client's text form, e.g. `monthly:15`
+    pub schedule: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub last_materialized: Option<NaiveDate>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Contribution {
+    pub date: NaiveDate,
+    pub amount: i64
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PlannedPurchase {
+    pub id: i64,
+    pub user_id: i64,
+    pub account_id: i64,
+    pub name: String,
+    pub target_amount: i64,
+    pub target_date: NaiveDate,
+    pub contributions: Vec<Contribution>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct WeightLog {
+    pub id: i64,
+    pub user_id: i64,
+    pub date: NaiveDate,
+    pub weight: f64,
+    /// Either `kg` or `lb`
+    pub unit: String,
+    pub fitbit_log_id: Option<i64>
+}
+
+/// Calories burned over a day, there is at most one per user and date.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct CaloriesLog {
+    pub user_id: i64,
+    pub date: NaiveDate,
+    pub calories: u32
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct SleepLog {
+    pub id: i64,
+    pub user_id: i64,
+    pub bed_time: NaiveDateTime,
+    pub wake_time: NaiveDateTime,
+    pub quality: Option<u8>,
+    pub fitbit_log_id: Option<i64>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct FitbitAccount {
+    pub user_id: i64,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+    pub last_synced: Option<NaiveDate>
+}
@@ -0,0 +1,106 @@
+use std::{fs, path::Path};
+
+use rusqlite::{params, Connection};
+
+use crate::models::{Account, CaloriesLog, FitbitAccount, InventoryItem, Notebook, PlannedPurchase, Purchase, RecurringPurchase, SleepLog, User, WeightLog};
+
+use super::{Result, StoreError};
+
+/// The single JSON document servers kept everything in before the SQLite store.
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct LegacyData {
+    users: Vec<User>,
+    notebooks: Vec<Notebook>,
+    inventory: Vec<InventoryItem>,
+    accounts: Vec<Account>,
+    purchases: Vec<Purchase>,
+    recurring: Vec<RecurringPurchase>,
+    planned: Vec<PlannedPurchase>,
+    weights: Vec<WeightLog>,
+    calories: Vec<CaloriesLog>,
+    sleeps: Vec<SleepLog>,
+    fitbit_accounts: Vec<FitbitAccount>
+}
+
+/// Inserts every record from the JSON file at `path` in one transaction. The ids are kept so
+/// the references between records stay intact, which is why the database has to be empty.
+pub fn import(connection: &mut Connection, path: &Path) -> Result<usize> {
+    let contents = fs::read_to_string(path).map_err(|e| StoreError::Import(format!("could not read {}: {}", path.display(), e)))?;
+    let data: LegacyData = serde_json::from_str(&contents).map_err(|e| StoreError::Import(format!("{} is not a ntntkr data file: {}", path.display(), e)))?;
+
+    let transaction = connection.transaction()?;
+    let existing_users: i64 = transaction.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+    if existing_users > 0 {
+        return Err(StoreError::Import("the database already has users, import into a new database instead".to_string()));
+    }
+
+    let mut imported = 0;
+    for user in &data.users {
+        imported += transaction.execute("INSERT INTO users (id, name) VALUES (?1, ?2)", params![user.id, user.name])?;
+    }
+    for notebook in &data.notebooks {
+        imported += transaction.execute(
+            "INSERT INTO notebooks (id, user_id, name, description, created, last_used) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![notebook.id, notebook.user_id, notebook.name, notebook.description, notebook.created, notebook.last_used],
+        )?;
+    }
+    for item in &data.inventory {
+        imported += transaction.execute("INSERT INTO inventory (id, user_id, name, in_kitchen) VALUES (?1, ?2, ?3, ?4)", params![item.id, item.user_id, item.name, item.in_kitchen])?;
+    }
+    for account in &data.accounts {
+        imported += transaction.execute(
+            "INSERT INTO accounts (id, user_id, name, account_type, starting_balance) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![account.id, account.user_id, account.name, account.account_type, account.starting_balance],
+        )?;
+    }
+    // Purchases point at the recurring purchase they were materialized from
+    for recurring in &data.recurring {
+        imported += transaction.execute(
+            "INSERT INTO recurring (id, user_id, account_id, amount, payee, category, schedule, start_date, end_date, last_materialized)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![recurring.id, recurring.user_id, recurring.account_id, recurring.amount, recurring.payee, recurring.category, recurring.schedule, recurring.start_date, recurring.end_date, recurring.last_materialized],
+        )?;
+    }
+    for purchase in &data.purchases {
+        imported += transaction.execute(
+            "INSERT INTO purchases (id, user_id, account_id, date, amount, payee, category, recurring_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![purchase.id, purchase.user_id, purchase.account_id, purchase.date, purchase.amount, purchase.payee, purchase.category, purchase.recurring_id],
+        )?;
+    }
+    for planned in &data.planned {
+        imported += transaction.execute(
+            "INSERT INTO planned (id, user_id, account_id, name, target_amount, target_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![planned.id, planned.user_id, planned.account_id, planned.name, planned.target_amount, planned.target_date],
+        )?;
+        for contribution in &planned.contributions {
+            transaction.execute("INSERT INTO contributions (planned_id, date, amount) VALUES (?1, ?2, ?3)", params![planned.id, contribution.date, contribution.amount])?;
+        }
+    }
+    // The JSON store could hold a Fitbit log more than once, only the first copy is kept
+    for weight in &data.weights {
+        imported += transaction.execute(
+            "INSERT INTO weights (id, user_id, date, weight, unit, fitbit_log_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (user_id, fitbit_log_id) DO NOTHING",
+            params![weight.id, weight.user_id, weight.date, weight.weight, weight.unit, weight.fitbit_log_id],
+        )?;
+    }
+    for calories in &data.calories {
+        imported += transaction.execute("INSERT INTO calories (user_id, date, calories) VALUES (?1, ?2, ?3)", params![calories.user_id, calories.date, calories.calories])?;
+    }
+    for sleep in &data.sleeps {
+        imported += transaction.execute(
+            "INSERT INTO sleeps (id, user_id, bed_time, wake_time, quality, fitbit_log_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (user_id, fitbit_log_id) DO NOTHING",
+            params![sleep.id, sleep.user_id, sleep.bed_time, sleep.wake_time, sleep.quality, sleep.fitbit_log_id],
+        )?;
+    }
+    for account in &data.fitbit_accounts {
+        imported += transaction.execute(
+            "INSERT INTO fitbit_accounts (user_id, access_token, refresh_token, expires_at, last_synced) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![account.user_id, account.access_token, account.refresh_token, account.expires_at, account.last_synced],
+        )?;
+    }
+    transaction.commit()?;
+    Ok(imported)
+}
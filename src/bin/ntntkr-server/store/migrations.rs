@@ -0,0 +1,189 @@
+use rusqlite::Connection;
+
+use super::{Result, StoreError};
+
+/// Schema changes in the order they were introduced. The database's `user_version` records how
+/// many have been applied, so entries must never be edited or reordered, only appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE users (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE notebooks (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        name TEXT NOT NULL,
+        description TEXT NOT NULL,
+        created TEXT NOT NULL,
+        last_used TEXT NOT NULL
+    );
+    CREATE TABLE inventory (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        name TEXT NOT NULL,
+        in_kitchen INTEGER NOT NULL
+    );
+    CREATE TABLE accounts (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        name TEXT NOT NULL,
+        account_type TEXT NOT NULL,
+        starting_balance INTEGER NOT NULL
+    );
+    CREATE TABLE recurring (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        account_id INTEGER NOT NULL REFERENCES accounts(id),
+        amount INTEGER NOT NULL,
+        payee TEXT NOT NULL,
+        category TEXT NOT NULL,
+        schedule TEXT NOT NULL,
+        start_date TEXT NOT NULL,
+        end_date TEXT,
+        last_materialized TEXT
+    );
+    CREATE TABLE purchases (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        account_id INTEGER NOT NULL REFERENCES accounts(id),
+        date TEXT NOT NULL,
+        amount INTEGER NOT NULL,
+        payee TEXT NOT NULL,
+        category TEXT NOT NULL,
+        recurring_id INTEGER REFERENCES recurring(id),
+        UNIQUE (recurring_id, date)
+    );
+    CREATE TABLE planned (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        account_id INTEGER NOT NULL REFERENCES accounts(id),
+        name TEXT NOT NULL,
+        target_amount INTEGER NOT NULL,
+        target_date TEXT NOT NULL
+    );
+    CREATE TABLE contributions (
+        id INTEGER PRIMARY KEY,
+        planned_id INTEGER NOT NULL REFERENCES planned(id),
+        date TEXT NOT NULL,
+        amount INTEGER NOT NULL
+    );
+    CREATE TABLE weights (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        date TEXT NOT NULL,
+        weight REAL NOT NULL,
+        unit TEXT NOT NULL,
+        fitbit_log_id INTEGER
+    );
+    CREATE TABLE calories (
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        date TEXT NOT NULL,
+        calories INTEGER NOT NULL,
+        PRIMARY KEY (user_id, date)
+    );
+    CREATE TABLE sleeps (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users(id),
+        bed_time TEXT NOT NULL,
+        wake_time TEXT NOT NULL,
+        quality INTEGER,
+        fitbit_log_id INTEGER
+    );
+    CREATE TABLE fitbit_accounts (
+        user_id INTEGER PRIMARY KEY REFERENCES users(id),
+        access_token TEXT NOT NULL,
+        refresh_token TEXT NOT NULL,
+        expires_at TEXT NOT NULL,
+        last_synced TEXT
+    );",
+    // 2: a Fitbit log is only stored once per user, earlier duplicates are dropped
+    "DELETE FROM weights WHERE fitbit_log_id IS NOT NULL
+        AND id NOT IN (SELECT MIN(id) FROM weights WHERE fitbit_log_id IS NOT NULL GROUP BY user_id, fitbit_log_id);
+    DELETE FROM sleeps WHERE fitbit_log_id IS NOT NULL
+        AND id NOT IN (SELECT MIN(id) FROM sleeps WHERE fitbit_log_id IS NOT NULL GROUP BY user_id, fitbit_log_id);
+    CREATE UNIQUE INDEX weights_fitbit_log_id ON weights (user_id, fitbit_log_id);
+    CREATE UNIQUE INDEX sleeps_fitbit_log_id ON sleeps (user_id, fitbit_log_id);",
+];
+
+pub fn schema_version(connection: &Connection) -> Result<usize> {
+    Ok(connection.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Brings the database up to the latest schema, applying each pending migration in its own
+/// transaction so a failure leaves the database at the last good version.
+pub fn migrate(connection: &mut Connection) -> Result<()> {
+    let current = schema_version(connection)?;
+    if current > MIGRATIONS.len() {
+        return Err(StoreError::NewerSchema(current));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_names(connection: &Connection) -> Vec<String> {
+        let mut statement = connection.prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name").unwrap();
+        statement.query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn fresh_database_is_migrated_to_the_latest_version() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&connection).unwrap(), 0);
+
+        migrate(&mut connection).unwrap();
+        assert_eq!(schema_version(&connection).unwrap(), MIGRATIONS.len());
+        let tables = table_names(&connection);
+        for table in ["users", "notebooks", "inventory", "accounts", "recurring", "purchases", "planned", "contributions", "weights", "calories", "sleeps", "fitbit_accounts"] {
+            assert!(tables.iter().any(|t| t == table), "missing table {}", table);
+        }
+
+        // Migrating an up to date database changes nothing
+        migrate(&mut connection).unwrap();
+        assert_eq!(schema_version(&connection).unwrap(), MIGRATIONS.len());
+        assert_eq!(table_names(&connection), tables);
+    }
+
+    #[test]
+    fn duplicate_fitbit_logs_are_dropped() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(MIGRATIONS[0]).unwrap();
+        connection.pragma_update(None, "user_version", 1).unwrap();
+        connection.execute_batch(
+            "INSERT INTO users (id, name) VALUES (1, 'Sam'), (2, 'Alex');
+            INSERT INTO weights (user_id, date, weight, unit, fitbit_log_id) VALUES
+                (1, '2024-03-01', 80.0, 'kg', 7), (1, '2024-03-01', 80.0, 'kg', 7), (2, '2024-03-01', 70.0, 'kg', 7),
+                (1, '2024-03-02', 79.5, 'kg', NULL), (1, '2024-03-02', 79.5, 'kg', NULL);
+            INSERT INTO sleeps (user_id, bed_time, wake_time, fitbit_log_id) VALUES
+                (1, '2024-03-01T23:00:00', '2024-03-02T07:00:00', 9), (1, '2024-03-01T23:00:00', '2024-03-02T07:00:00', 9);",
+        ).unwrap();
+
+        migrate(&mut connection).unwrap();
+        let ids = |sql: &str| -> Vec<i64> {
+            let mut statement = connection.prepare(sql).unwrap();
+            statement.query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap()
+        };
+        // Entries logged by hand have no log id and are all kept
+        assert_eq!(ids("SELECT id FROM weights ORDER BY id"), vec![1, 3, 4, 5]);
+        assert_eq!(ids("SELECT id FROM sleeps ORDER BY id"), vec![1]);
+        assert!(connection.execute("INSERT INTO weights (user_id, date, weight, unit, fitbit_log_id) VALUES (1, '2024-03-03', 80.0, 'kg', 7)", []).is_err());
+    }
+
+    #[test]
+    fn newer_schema_is_refused() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection.pragma_update(None, "user_version", MIGRATIONS.len() + 1).unwrap();
+        assert!(matches!(migrate(&mut connection), Err(StoreError::NewerSchema(version)) if version == MIGRATIONS.len() + 1));
+        assert!(table_names(&connection).is_empty());
+    }
+}
@@ -0,0 +1,546 @@
+use std::{fmt::Display, path::Path, sync::Mutex};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::models::{Account, CaloriesLog, Contribution, FitbitAccount, InventoryItem, Notebook, PlannedPurchase, Purchase, RecurringPurchase, SleepLog, User, WeightLog};
+
+mod legacy;
+mod migrations;
+
+#[derive(Debug)]
+pub enum StoreError {
+    Sqlite(rusqlite::Error),
+    /// The database has migrations applied that this build does not know about
+    NewerSchema(usize),
+    /// The record can't be deleted while other records still refer to it
+    InUse(&'static str),
+    /// A JSON data file from before the SQLite store could not be imported
+    Import(String)
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Sqlite(error) => write!(f, "database error: {}", error),
+            StoreError::NewerSchema(version) => write!(f, "the database is at schema version {}, which is newer than this server supports", version),
+            StoreError::InUse(what) => write!(f, "the {} is still in use", what),
+            StoreError::Import(message) => write!(f, "could not import the data file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        StoreError::Sqlite(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, StoreError>;
+
+/// SQLite backed store, the schema is migrated to the latest version when it is opened.
+pub struct Store {
+    connection: Mutex<Connection>
+}
+
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    Ok(User { id: row.get("id")?, name: row.get("name")? })
+}
+
+fn notebook_from_row(row: &Row) -> rusqlite::Result<Notebook> {
+    Ok(Notebook {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        name: row.get("name")?,
+        description: row.get("description")?,
+        created: row.get("created")?,
+        last_used: row.get("last_used")?,
+    })
+}
+
+fn item_from_row(row: &Row) -> rusqlite::Result<InventoryItem> {
+    Ok(InventoryItem { id: row.get("id")?, user_id: row.get("user_id")?, name: row.get("name")?, in_kitchen: row.get("in_kitchen")? })
+}
+
+fn account_from_row(row: &Row) -> rusqlite::Result<Account> {
+    Ok(Account {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        name: row.get("name")?,
+        account_type: row.get("account_type")?,
+        starting_balance: row.get("starting_balance")?,
+    })
+}
+
+fn purchase_from_row(row: &Row) -> rusqlite::Result<Purchase> {
+    Ok(Purchase {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        account_id: row.get("account_id")?,
+        date: row.get("date")?,
+        amount: row.get("amount")?,
+        payee: row.get("payee")?,
+        category: row.get("category")?,
+        recurring_id: row.get("recurring_id")?,
+    })
+}
+
+fn recurring_from_row(row: &Row) -> rusqlite::Result<RecurringPurchase> {
+    Ok(RecurringPurchase {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        account_id: row.get("account_id")?,
+        amount: row.get("amount")?,
+        payee: row.get("payee")?,
+        category: row.get("category")?,
+        schedule: row.get("schedule")?,
+        start_date: row.get("start_date")?,
+        end_date: row.get("end_date")?,
+        last_materialized: row.get("last_materialized")?,
+    })
+}
+
+fn planned_from_row(row: &Row) -> rusqlite::Result<PlannedPurchase> {
+    Ok(PlannedPurchase {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        account_id: row.get("account_id")?,
+        name: row.get("name")?,
+        target_amount: row.get("target_amount")?,
+        target_date: row.get("target_date")?,
+        contributions: Vec::new(),
+    })
+}
+
+fn weight_from_row(row: &Row) -> rusqlite::Result<WeightLog> {
+    Ok(WeightLog {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        date: row.get("date")?,
+        weight: row.get("weight")?,
+        unit: row.get("unit")?,
+        fitbit_log_id: row.get("fitbit_log_id")?,
+    })
+}
+
+fn calories_from_row(row: &Row) -> rusqlite::Result<CaloriesLog> {
+    Ok(CaloriesLog { user_id: row.get("user_id")?, date: row.get("date")?, calories: row.get("calories")? })
+}
+
+fn sleep_from_row(row: &Row) -> rusqlite::Result<SleepLog> {
+    Ok(SleepLog {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        bed_time: row.get("bed_time")?,
+        wake_time: row.get("wake_time")?,
+        quality: row.get("quality")?,
+        fitbit_log_id: row.get("fitbit_log_id")?,
+    })
+}
+
+fn fitbit_account_from_row(row: &Row) -> rusqlite::Result<FitbitAccount> {
+    Ok(FitbitAccount {
+        user_id: row.get("user_id")?,
+        access_token: row.get("access_token")?,
+        refresh_token: row.get("refresh_token")?,
+        expires_at: row.get("expires_at")?,
+        last_synced: row.get("last_synced")?,
+    })
+}
+
+/// Whether `error` is SQLite refusing to break a foreign key.
+fn is_foreign_key_error(error: &rusqlite::Error) -> bool {
+    matches!(error, rusqlite::Error::SqliteFailure(error, _) if error.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY)
+}
+
+/// Deletes the row with `id` from `table` if it belongs to the user.
+fn delete_owned(connection: &Connection, table: &str, user_id: i64, id: i64) -> Result<bool> {
+    Ok(connection.execute(&format!("DELETE FROM {} WHERE user_id = ?1 AND id = ?2", table), [user_id, id])? > 0)
+}
+
+/// Runs a query and collects every row it returns.
+fn query_all<T>(connection: &Connection, sql: &str, params: impl rusqlite::Params, map: impl FnMut(&Row) -> rusqlite::Result<T>) -> Result<Vec<T>> {
+    let mut statement = connection.prepare(sql)?;
+    let rows = statement.query_map(params, map)?.collect::<rusqlite::Result<Vec<T>>>()?;
+    Ok(rows)
+}
+
+fn load_contributions(connection: &Connection, planned: &mut PlannedPurchase) -> Result<()> {
+    planned.contributions = query_all(connection, "SELECT date, amount FROM contributions WHERE planned_id = ?1 ORDER BY date, id", [planned.id], |row| {
+        Ok(Contribution { date: row.get("date")?, amount: row.get("amount")? })
+    })?;
+    Ok(())
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        Store::new(Connection::open(path)?)
+    }
+
+    fn new(mut connection: Connection) -> Result<Self> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut connection)?;
+        Ok(Store { connection: Mutex::new(connection) })
+    }
+
+    /// Copies the records from a `ntntkr-data.json` file written before the SQLite store into
+    /// this database, which has to be empty. Returns how many records were imported.
+    pub fn import_json(&self, path: &Path) -> Result<usize> {
+        legacy::import(&mut self.connection.lock().unwrap(), path)
+    }
+
+    pub fn schema_version(&self) -> Result<usize> {
+        migrations::schema_version(&self.connection.lock().unwrap())
+    }
+
+    pub fn users(&self) -> Result<Vec<User>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM users ORDER BY id", [], user_from_row)
+    }
+
+    pub fn user(&self, user_id: i64) -> Result<Option<User>> {
+        let connection = self.connection.lock().unwrap();
+        Ok(connection.query_row("SELECT * FROM users WHERE id = ?1", [user_id], user_from_row).optional()?)
+    }
+
+    pub fn insert_user(&self, mut user: User) -> Result<User> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("INSERT INTO users (name) VALUES (?1)", [&user.name])?;
+        user.id = connection.last_insert_rowid();
+        Ok(user)
+    }
+
+    pub fn rename_user(&self, user_id: i64, name: &str) -> Result<Option<User>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("UPDATE users SET name = ?1 WHERE id = ?2", params![name, user_id])?;
+        Ok(connection.query_row("SELECT * FROM users WHERE id = ?1", [user_id], user_from_row).optional()?)
+    }
+
+    /// Deletes a user and every record they own.
+    pub fn delete_user(&self, user_id: i64) -> Result<bool> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM contributions WHERE planned_id IN (SELECT id FROM planned WHERE user_id = ?1)", [user_id])?;
+        for table in ["notebooks", "inventory", "purchases", "recurring", "planned", "accounts", "weights", "calories", "sleeps", "fitbit_accounts"] {
+            transaction.execute(&format!("DELETE FROM {} WHERE user_id = ?1", table), [user_id])?;
+        }
+        let deleted = transaction.execute("DELETE FROM users WHERE id = ?1", [user_id])? > 0;
+        transaction.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn notebooks(&self, user_id: i64) -> Result<Vec<Notebook>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM notebooks WHERE user_id = ?1 ORDER BY id", [user_id], notebook_from_row)
+    }
+
+    pub fn insert_notebook(&self, mut notebook: Notebook) -> Result<Notebook> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO notebooks (user_id, name, description, created, last_used) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![notebook.user_id, notebook.name, notebook.description, notebook.created, notebook.last_used],
+        )?;
+        notebook.id = connection.last_insert_rowid();
+        Ok(notebook)
+    }
+
+    pub fn touch_notebook(&self, user_id: i64, id: i64, used: DateTime<Utc>) -> Result<Option<Notebook>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("UPDATE notebooks SET last_used = ?1 WHERE user_id = ?2 AND id = ?3", params![used, user_id, id])?;
+        Ok(connection.query_row("SELECT * FROM notebooks WHERE user_id = ?1 AND id = ?2", [user_id, id], notebook_from_row).optional()?)
+    }
+
+    /// Changes the name and description, the timestamps are kept.
+    pub fn update_notebook(&self, notebook: &Notebook) -> Result<Option<Notebook>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE notebooks SET name = ?1, description = ?2 WHERE user_id = ?3 AND id = ?4",
+            params![notebook.name, notebook.description, notebook.user_id, notebook.id],
+        )?;
+        Ok(connection.query_row("SELECT * FROM notebooks WHERE user_id = ?1 AND id = ?2", [notebook.user_id, notebook.id], notebook_from_row).optional()?)
+    }
+
+    pub fn delete_notebook(&self, user_id: i64, id: i64) -> Result<bool> {
+        delete_owned(&self.connection.lock().unwrap(), "notebooks", user_id, id)
+    }
+
+    pub fn inventory(&self, user_id: i64) -> Result<Vec<InventoryItem>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM inventory WHERE user_id = ?1 ORDER BY id", [user_id], item_from_row)
+    }
+
+    pub fn insert_item(&self, mut item: InventoryItem) -> Result<InventoryItem> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("INSERT INTO inventory (user_id, name, in_kitchen) VALUES (?1, ?2, ?3)", params![item.user_id, item.name, item.in_kitchen])?;
+        item.id = connection.last_insert_rowid();
+        Ok(item)
+    }
+
+    pub fn update_item(&self, item: &InventoryItem) -> Result<Option<InventoryItem>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("UPDATE inventory SET name = ?1, in_kitchen = ?2 WHERE user_id = ?3 AND id = ?4", params![item.name, item.in_kitchen, item.user_id, item.id])?;
+        Ok(connection.query_row("SELECT * FROM inventory WHERE user_id = ?1 AND id = ?2", [item.user_id, item.id], item_from_row).optional()?)
+    }
+
+    pub fn delete_item(&self, user_id: i64, id: i64) -> Result<bool> {
+        delete_owned(&self.connection.lock().unwrap(), "inventory", user_id, id)
+    }
+
+    pub fn accounts(&self, user_id: i64) -> Result<Vec<Account>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM accounts WHERE user_id = ?1 ORDER BY id", [user_id], account_from_row)
+    }
+
+    pub fn insert_account(&self, mut account: Account) -> Result<Account> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO accounts (user_id, name, account_type, starting_balance) VALUES (?1, ?2, ?3, ?4)",
+            params![account.user_id, account.name, account.account_type, account.starting_balance],
+        )?;
+        account.id = connection.last_insert_rowid();
+        Ok(account)
+    }
+
+    pub fn update_account(&self, account: &Account) -> Result<Option<Account>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE accounts SET name = ?1, account_type = ?2, starting_balance = ?3 WHERE user_id = ?4 AND id = ?5",
+            params![account.name, account.account_type, account.starting_balance, account.user_id, account.id],
+        )?;
+        Ok(connection.query_row("SELECT * FROM accounts WHERE user_id = ?1 AND id = ?2", [account.user_id, account.id], account_from_row).optional()?)
+    }
+
+    /// Accounts that purchases, recurring or planned purchases still use are kept.
+    pub fn delete_account(&self, user_id: i64, id: i64) -> Result<bool> {
+        match delete_owned(&self.connection.lock().unwrap(), "accounts", user_id, id) {
+            Err(StoreError::Sqlite(error)) if is_foreign_key_error(&error) => Err(StoreError::InUse("account")),
+            result => result,
+        }
+    }
+
+    pub fn purchases(&self, user_id: i64) -> Result<Vec<Purchase>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM purchases WHERE user_id = ?1 ORDER BY date, id", [user_id], purchase_from_row)
+    }
+
+    /// Stores a purchase, a recurring purchase is only ever materialized once for each date.
+    pub fn insert_purchase(&self, mut purchase: Purchase) -> Result<Purchase> {
+        let connection = self.connection.lock().unwrap();
+        let inserted = connection.execute(
+            "INSERT INTO purchases (user_id, account_id, date, amount, payee, category, recurring_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT (recurring_id, date) DO NOTHING",
+            params![purchase.user_id, purchase.account_id, purchase.date, purchase.amount, purchase.payee, purchase.category, purchase.recurring_id],
+        )?;
+        if inserted == 0 {
+            return Ok(connection.query_row("SELECT * FROM purchases WHERE user_id = ?1 AND recurring_id = ?2 AND date = ?3", params![purchase.user_id, purchase.recurring_id, purchase.date], purchase_from_row)?);
+        }
+        purchase.id = connection.last_insert_rowid();
+        Ok(purchase)
+    }
+
+    pub fn update_purchase(&self, purchase: &Purchase) -> Result<Option<Purchase>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE purchases SET account_id = ?1, date = ?2, amount = ?3, payee = ?4, category = ?5, recurring_id = ?6 WHERE user_id = ?7 AND id = ?8",
+            params![purchase.account_id, purchase.date, purchase.amount, purchase.payee, purchase.category, purchase.recurring_id, purchase.user_id, purchase.id],
+        )?;
+        Ok(connection.query_row("SELECT * FROM purchases WHERE user_id = ?1 AND id = ?2", [purchase.user_id, purchase.id], purchase_from_row).optional()?)
+    }
+
+    pub fn delete_purchase(&self, user_id: i64, id: i64) -> Result<bool> {
+        delete_owned(&self.connection.lock().unwrap(), "purchases", user_id, id)
+    }
+
+    pub fn recurring(&self, user_id: i64) -> Result<Vec<RecurringPurchase>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM recurring WHERE user_id = ?1 ORDER BY id", [user_id], recurring_from_row)
+    }
+
+    pub fn insert_recurring(&self, mut recurring: RecurringPurchase) -> Result<RecurringPurchase> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO recurring (user_id, account_id, amount, payee, category, schedule, start_date, end_date, last_materialized)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![recurring.user_id, recurring.account_id, recurring.amount, recurring.payee, recurring.category, recurring.schedule, recurring.start_date, recurring.end_date, recurring.last_materialized],
+        )?;
+        recurring.id = connection.last_insert_rowid();
+        Ok(recurring)
+    }
+
+    pub fn set_last_materialized(&self, user_id: i64, id: i64, date: NaiveDate) -> Result<Option<RecurringPurchase>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("UPDATE recurring SET last_materialized = ?1 WHERE user_id = ?2 AND id = ?3", params![date, user_id, id])?;
+        Ok(connection.query_row("SELECT * FROM recurring WHERE user_id = ?1 AND id = ?2", [user_id, id], recurring_from_row).optional()?)
+    }
+
+    /// Replaces everything but `last_materialized`, purchases that were already materialized stay as they are.
+    pub fn update_recurring(&self, recurring: &RecurringPurchase) -> Result<Option<RecurringPurchase>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE recurring SET account_id = ?1, amount = ?2, payee = ?3, category = ?4, schedule = ?5, start_date = ?6, end_date = ?7
+            WHERE user_id = ?8 AND id = ?9",
+            params![recurring.account_id, recurring.amount, recurring.payee, recurring.category, recurring.schedule, recurring.start_date, recurring.end_date, recurring.user_id, recurring.id],
+        )?;
+        Ok(connection.query_row("SELECT * FROM recurring WHERE user_id = ?1 AND id = ?2", [recurring.user_id, recurring.id], recurring_from_row).optional()?)
+    }
+
+    /// The purchases it materialized are kept as ordinary purchases.
+    pub fn delete_recurring(&self, user_id: i64, id: i64) -> Result<bool> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute("UPDATE purchases SET recurring_id = NULL WHERE user_id = ?1 AND recurring_id = ?2", [user_id, id])?;
+        let deleted = delete_owned(&transaction, "recurring", user_id, id)?;
+        transaction.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn planned(&self, user_id: i64) -> Result<Vec<PlannedPurchase>> {
+        let connection = self.connection.lock().unwrap();
+        let mut planned = query_all(&connection, "SELECT * FROM planned WHERE user_id = ?1 ORDER BY id", [user_id], planned_from_row)?;
+        for item in &mut planned {
+            load_contributions(&connection, item)?;
+        }
+        Ok(planned)
+    }
+
+    pub fn insert_planned(&self, mut planned: PlannedPurchase) -> Result<PlannedPurchase> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO planned (user_id, account_id, name, target_amount, target_date) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![planned.user_id, planned.account_id, planned.name, planned.target_amount, planned.target_date],
+        )?;
+        planned.id = connection.last_insert_rowid();
+        planned.contributions.clear();
+        Ok(planned)
+    }
+
+    pub fn add_contribution(&self, user_id: i64, id: i64, contribution: Contribution) -> Result<Option<PlannedPurchase>> {
+        let connection = self.connection.lock().unwrap();
+        let Some(mut planned) = connection.query_row("SELECT * FROM planned WHERE user_id = ?1 AND id = ?2", [user_id, id], planned_from_row).optional()? else {
+            return Ok(None);
+        };
+        connection.execute("INSERT INTO contributions (planned_id, date, amount) VALUES (?1, ?2, ?3)", params![planned.id, contribution.date, contribution.amount])?;
+        load_contributions(&connection, &mut planned)?;
+        Ok(Some(planned))
+    }
+
+    /// Replaces everything but the contributions.
+    pub fn update_planned(&self, planned: &PlannedPurchase) -> Result<Option<PlannedPurchase>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE planned SET account_id = ?1, name = ?2, target_amount = ?3, target_date = ?4 WHERE user_id = ?5 AND id = ?6",
+            params![planned.account_id, planned.name, planned.target_amount, planned.target_date, planned.user_id, planned.id],
+        )?;
+        let Some(mut planned) = connection.query_row("SELECT * FROM planned WHERE user_id = ?1 AND id = ?2", [planned.user_id, planned.id], planned_from_row).optional()? else {
+            return Ok(None);
+        };
+        load_contributions(&connection, &mut planned)?;
+        Ok(Some(planned))
+    }
+
+    /// Deletes a planned purchase along with its contributions.
+    pub fn delete_planned(&self, user_id: i64, id: i64) -> Result<bool> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM contributions WHERE planned_id IN (SELECT id FROM planned WHERE user_id = ?1 AND id = ?2)", [user_id, id])?;
+        let deleted = delete_owned(&transaction, "planned", user_id, id)?;
+        transaction.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn weights(&self, user_id: i64) -> Result<Vec<WeightLog>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM weights WHERE user_id = ?1 ORDER BY date, id", [user_id], weight_from_row)
+    }
+
+    /// Stores a weight, a Fitbit log is only ever stored once for each user.
+    pub fn insert_weight(&self, mut weight: WeightLog) -> Result<WeightLog> {
+        let connection = self.connection.lock().unwrap();
+        let inserted = connection.execute(
+            "INSERT INTO weights (user_id, date, weight, unit, fitbit_log_id) VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (user_id, fitbit_log_id) DO NOTHING",
+            params![weight.user_id, weight.date, weight.weight, weight.unit, weight.fitbit_log_id],
+        )?;
+        if inserted == 0 {
+            return Ok(connection.query_row("SELECT * FROM weights WHERE user_id = ?1 AND fitbit_log_id = ?2", params![weight.user_id, weight.fitbit_log_id], weight_from_row)?);
+        }
+        weight.id = connection.last_insert_rowid();
+        Ok(weight)
+    }
+
+    pub fn update_weight(&self, weight: &WeightLog) -> Result<Option<WeightLog>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE weights SET date = ?1, weight = ?2, unit = ?3, fitbit_log_id = ?4 WHERE user_id = ?5 AND id = ?6",
+            params![weight.date, weight.weight, weight.unit, weight.fitbit_log_id, weight.user_id, weight.id],
+        )?;
+        Ok(connection.query_row("SELECT * FROM weights WHERE user_id = ?1 AND id = ?2", [weight.user_id, weight.id], weight_from_row).optional()?)
+    }
+
+    pub fn delete_weight(&self, user_id: i64, id: i64) -> Result<bool> {
+        delete_owned(&self.connection.lock().unwrap(), "weights", user_id, id)
+    }
+
+    pub fn calories(&self, user_id: i64) -> Result<Vec<CaloriesLog>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM calories WHERE user_id = ?1 ORDER BY date", [user_id], calories_from_row)
+    }
+
+    /// Sets the calories for a day, replacing anything logged for that day before.
+    pub fn set_calories(&self, calories: CaloriesLog) -> Result<CaloriesLog> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO calories (user_id, date, calories) VALUES (?1, ?2, ?3)
+            ON CONFLICT (user_id, date) DO UPDATE SET calories = excluded.calories",
+            params![calories.user_id, calories.date, calories.calories],
+        )?;
+        Ok(calories)
+    }
+
+    pub fn delete_calories(&self, user_id: i64, date: NaiveDate) -> Result<bool> {
+        let connection = self.connection.lock().unwrap();
+        Ok(connection.execute("DELETE FROM calories WHERE user_id = ?1 AND date = ?2", params![user_id, date])? > 0)
+    }
+
+    pub fn sleeps(&self, user_id: i64) -> Result<Vec<SleepLog>> {
+        query_all(&self.connection.lock().unwrap(), "SELECT * FROM sleeps WHERE user_id = ?1 ORDER BY bed_time, id", [user_id], sleep_from_row)
+    }
+
+    /// Stores a sleep log, a Fitbit log is only ever stored once for each user.
+    pub fn insert_sleep(&self, mut sleep: SleepLog) -> Result<SleepLog> {
+        let connection = self.connection.lock().unwrap();
+        let inserted = connection.execute(
+            "INSERT INTO sleeps (user_id, bed_time, wake_time, quality, fitbit_log_id) VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (user_id, fitbit_log_id) DO NOTHING",
+            params![sleep.user_id, sleep.bed_time, sleep.wake_time, sleep.quality, sleep.fitbit_log_id],
+        )?;
+        if inserted == 0 {
+            return Ok(connection.query_row("SELECT * FROM sleeps WHERE user_id = ?1 AND fitbit_log_id = ?2", params![sleep.user_id, sleep.fitbit_log_id], sleep_from_row)?);
+        }
+        sleep.id = connection.last_insert_rowid();
+        Ok(sleep)
+    }
+
+    pub fn update_sleep(&self, sleep: &SleepLog) -> Result<Option<SleepLog>> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "UPDATE sleeps SET bed_time = ?1, wake_time = ?2, quality = ?3, fitbit_log_id = ?4 WHERE user_id = ?5 AND id = ?6",
+            params![sleep.bed_time, sleep.wake_time, sleep.quality, sleep.fitbit_log_id, sleep.user_id, sleep.id],
+        )?;
+        Ok(connection.query_row("SELECT * FROM sleeps WHERE user_id = ?1 AND id = ?2", [sleep.user_id, sleep.id], sleep_from_row).optional()?)
+    }
+
+    pub fn delete_sleep(&self, user_id: i64, id: i64) -> Result<bool> {
+        delete_owned(&self.connection.lock().unwrap(), "sleeps", user_id, id)
+    }
+
+    pub fn fitbit_account(&self, user_id: i64) -> Result<Option<FitbitAccount>> {
+        let connection = self.connection.lock().unwrap();
+        Ok(connection.query_row("SELECT * FROM fitbit_accounts WHERE user_id = ?1", [user_id], fitbit_account_from_row).optional()?)
+    }
+
+    pub fn set_fitbit_account(&self, account: FitbitAccount) -> Result<FitbitAccount> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO fitbit_accounts (user_id, access_token, refresh_token, expires_at, last_synced) VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (user_id) DO UPDATE SET access_token = excluded.access_token, refresh_token = excluded.refresh_token,
+                expires_at = excluded.expires_at, last_synced = excluded.last_synced",
+            params![account.user_id, account.access_token, account.refresh_token, account.expires_at, account.last_synced],
+        )?;
+        Ok(account)
+    }
+}
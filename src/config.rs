@@ -1,47 +1,411 @@
+#[allow(clippy::module_inception)]
 pub mod config {
-    use std::{fs::{self}, path::Path};
+    use std::{ffi::OsString, fs::{self, OpenOptions}, io::Write, path::{Path, PathBuf}};
 
-    #[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+    use color_eyre::{eyre::{bail, eyre, WrapErr}, Result};
+    use toml::{Table, Value};
+
+    /// Version written by this build. When the format changes, bump it and add a step to
+    /// `MIGRATIONS` that turns the previous version into the new one.
+    const CONFIG_VERSION: u32 = 2;
+
+    /// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`, the version field itself is
+    /// set by `Config::migrate`.
+    const MIGRATIONS: [fn(&mut Table); CONFIG_VERSION as usize] = [
+        // Version 0 is every file written before the version field existed, the fields are the same
+        |_| {},
+        // Version 1 servers had no scheme, certificate or auth, their defaults are plain http
+        // without auth, which is how those servers were reached
+        |_| {},
+    ];
+
+    #[derive(serde::Serialize, serde::Deserialize, Default, Debug, Clone)]
     pub struct Server {
         pub name: String,
         pub address: String,
-        pub port: String
+        pub port: String,
+        #[serde(default)]
+        pub scheme: Scheme,
+        /// PEM file with the CA that signed the server's certificate, for servers whose
+        /// certificate is not trusted by the system
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub ca_certificate: Option<PathBuf>,
+        #[serde(default)]
+        pub auth: ServerAuth
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Scheme {
+        #[default]
+        Http,
+        Https
+    }
+
+    impl Scheme {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Scheme::Http => "http",
+                Scheme::Https => "https",
+            }
+        }
+    }
+
+    /// How the TUI identifies itself to a server. The secrets are kept in the config file, which
+    /// is only readable by its owner.
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+    #[serde(tag = "method", rename_all = "lowercase")]
+    pub enum ServerAuth {
+        #[default]
+        None,
+        /// Sent as a bearer token with every request
+        Token { token: String },
+        /// Exchanged for a session token when the first request is made
+        Password { username: String, password: String }
     }
 
-    #[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+    /// Settings for pulling data from the Fitbit Web API, the URLs can be pointed at a local mock.
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    #[serde(default)]
+    pub struct FitbitConfig {
+        pub client_id: String,
+        pub authorize_url: String,
+        pub api_url: String,
+        pub redirect_uri: String
+    }
+
+    impl Default for FitbitConfig {
+        fn default() -> Self {
+            FitbitConfig {
+                client_id: String::new(),
+                authorize_url: "https://www.fitbit.com/oauth2/authorize".to_string(),
+                api_url: "https://api.fitbit.com".to_string(),
+                redirect_uri: "http://127.0.0.1:8080/".to_string(),
+            }
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
     pub struct Config {
-        pub servers: Vec<Server>
+        version: u32,
+        pub servers: Vec<Server>,
+        #[serde(default)]
+        pub fitbit: FitbitConfig,
+        /// File the config was loaded from and is saved back to
+        #[serde(skip)]
+        path: PathBuf
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Config {
+                version: CONFIG_VERSION,
+                servers: Vec::new(),
+                fitbit: FitbitConfig::default(),
+                path: PathBuf::new(),
+            }
+        }
+    }
+
+    /// `path` with `suffix` added to the end of its file name.
+    fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+        name.push(suffix);
+        path.with_file_name(name)
     }
 
     impl Config {
 
-        pub fn initialize(&mut self) {
-            // Check if a file exists
-            let path = Path::new("Config.toml");
+        /// `$XDG_CONFIG_HOME/ntntkr/config.toml`, falling back to `~/.config` when the variable
+        /// is unset.
+        pub fn default_path() -> Result<PathBuf> {
+            let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+                Some(dir) if Path::new(&dir).is_absolute() => PathBuf::from(dir),
+                _ => {
+                    let home = std::env::var_os("HOME").ok_or_else(|| eyre!("neither XDG_CONFIG_HOME nor HOME is set, pass --config to say where the config lives"))?;
+                    PathBuf::from(home).join(".config")
+                }
+            };
+            Ok(config_home.join("ntntkr").join("config.toml"))
+        }
+
+        /// Reads the config at `path`, creating it on first run and upgrading it when it was
+        /// written by an older version. A file that does not parse is left untouched and copied
+        /// aside, the app then starts with an empty config and the returned message says what went
+        /// wrong and where the copy is.
+        pub fn load(path: &Path) -> Result<(Config, Option<String>)> {
             if !path.exists() {
-                self.servers = Vec::new();
-                self.write_config_file(path);
-            } else {
-                // if a file exists then make sure that it is valid
-                let contents = fs::read_to_string(path).unwrap();
-                if toml::from_str::<Config>(&contents).is_err() {
-                    self.servers = Vec::new();
-                    self.write_config_file(path);
+                let config = Config { path: path.to_path_buf(), ..Config::default() };
+                if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                    fs::create_dir_all(dir).wrap_err_with(|| format!("could not create {}", dir.display()))?;
                 }
+                config.save()?;
+                return Ok((config, None));
+            }
+
+            let contents = fs::read_to_string(path).wrap_err_with(|| format!("could not read {}", path.display()))?;
+            let mut table = match toml::from_str::<Table>(&contents) {
+                Ok(table) => table,
+                Err(error) => {
+                    let line = error.span().map(|span| contents[..span.start].matches('\n').count() + 1).unwrap_or(1);
+                    return Config::recover(path, format!("line {}: {}", line, error.message().replace('\n', ", ")));
+                }
+            };
+
+            // Files from before the version field are version 0
+            let version = match table.get("version") {
+                None => 0,
+                Some(value) => match value.as_integer().and_then(|version| u32::try_from(version).ok()) {
+                    Some(version) => version,
+                    None => return Config::recover(path, "version must be a whole number".to_string()),
+                },
+            };
+            if version > CONFIG_VERSION {
+                bail!("{} is config version {}, but this ntntkr only understands up to version {}, please update it", path.display(), version, CONFIG_VERSION);
+            }
+            Config::migrate(&mut table, version);
+
+            let config = match Value::Table(table).try_into::<Config>() {
+                Ok(config) => Config { path: path.to_path_buf(), ..config },
+                Err(error) => return Config::recover(path, error.message().replace('\n', ", ")),
+            };
+
+            if version < CONFIG_VERSION {
+                // Keep the old file around in case the upgrade lost something
+                let backup = with_suffix(path, &format!(".v{}.bak", version));
+                fs::copy(path, &backup).wrap_err_with(|| format!("could not back up the config to {} before upgrading it", backup.display()))?;
+                config.save()?;
             }
-            
-            let read_obj: Config = toml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
-            self.servers = read_obj.servers;
+            Ok((config, None))
+        }
+
+        /// Runs every migration after `version`, leaving `table` in the current format.
+        fn migrate(table: &mut Table, version: u32) {
+            for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+                migration(table);
+                table.insert("version".to_string(), Value::Integer(from as i64 + 1));
+            }
+        }
+
+        /// Copies an unreadable config aside and starts over with an empty one.
+        fn recover(path: &Path, reason: String) -> Result<(Config, Option<String>)> {
+            let backup = with_suffix(path, &format!(".{}.bak", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+            fs::copy(path, &backup).wrap_err_with(|| format!("could not back up the invalid config to {}", backup.display()))?;
+            let message = format!("{} could not be read ({}). It was copied to {} and will be replaced when a server is saved.", path.display(), reason, backup.display());
+            Ok((Config { path: path.to_path_buf(), ..Config::default() }, Some(message)))
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        /// Writes next to the config first and renames over it, so an interrupted write never
+        /// leaves a half written file behind. The file can hold server passwords, so only its
+        /// owner may read it.
+        fn save(&self) -> Result<()> {
+            let temp_path = with_suffix(&self.path, ".tmp");
+            let contents = toml::to_string(&self).wrap_err("could not serialize the config")?;
+
+            let mut options = OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let mut file = options.open(&temp_path).wrap_err_with(|| format!("could not create {}", temp_path.display()))?;
+            file.write_all(contents.as_bytes()).and_then(|_| file.sync_all()).wrap_err_with(|| format!("could not write {}", temp_path.display()))?;
+            fs::rename(&temp_path, &self.path).wrap_err_with(|| format!("could not replace {}", self.path.display()))?;
+            Ok(())
         }
 
-        fn write_config_file(&self, path: &Path) {
-            fs::write(path, toml::to_string(&self).unwrap()).unwrap();
+        /// Saves the config with `servers` as its server list, which only replaces the current
+        /// list once the file was written.
+        fn save_servers(&mut self, servers: Vec<Server>) -> Result<()> {
+            let config = Config { version: self.version, servers, fitbit: self.fitbit.clone(), path: self.path.clone() };
+            config.save()?;
+            self.servers = config.servers;
+            Ok(())
         }
 
-        pub fn add_new_server(&mut self, new_server: Server) {
-            self.servers.push(new_server);
-            self.write_config_file(Path::new("Config.toml"));
+        pub fn add_new_server(&mut self, new_server: Server) -> Result<()> {
+            let mut servers = self.servers.clone();
+            servers.push(new_server);
+            self.save_servers(servers)
         }
 
+        pub fn update_server(&mut self, index: usize, server: Server) -> Result<()> {
+            let mut servers = self.servers.clone();
+            *servers.get_mut(index).ok_or_else(|| eyre!("there is no server {} to update", index))? = server;
+            self.save_servers(servers)
+        }
+
+        pub fn remove_server(&mut self, index: usize) -> Result<()> {
+            let mut servers = self.servers.clone();
+            servers.get(index).ok_or_else(|| eyre!("there is no server {} to remove", index))?;
+            servers.remove(index);
+            self.save_servers(servers)
+        }
+
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// An empty directory of its own for each test, so they can run in parallel.
+        fn scratch_dir(name: &str) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!("ntntkr-config-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+
+        fn saved_version(path: &Path) -> Option<i64> {
+            toml::from_str::<Table>(&fs::read_to_string(path).unwrap()).unwrap().get("version").and_then(Value::as_integer)
+        }
+
+        fn backups(dir: &Path) -> Vec<PathBuf> {
+            let mut backups: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).filter(|path| path.extension().is_some_and(|e| e == "bak")).collect();
+            backups.sort();
+            backups
+        }
+
+        #[test]
+        fn version_0_file_is_upgraded() {
+            let dir = scratch_dir("v0");
+            let path = dir.join("config.toml");
+            let old = "[[servers]]\nname = \"Home\"\naddress = \"192.168.1.20\"\nport = \"8000\"\n";
+            fs::write(&path, old).unwrap();
+
+            let (config, warning) = Config::load(&path).unwrap();
+            assert!(warning.is_none());
+            assert_eq!(config.version, CONFIG_VERSION);
+            assert_eq!(config.servers.len(), 1);
+            let server = &config.servers[0];
+            assert_eq!((server.name.as_str(), server.address.as_str(), server.port.as_str()), ("Home", "192.168.1.20", "8000"));
+            assert_eq!(server.scheme, Scheme::Http);
+            assert!(matches!(server.auth, ServerAuth::None));
+
+            assert_eq!(backups(&dir), vec![dir.join("config.toml.v0.bak")]);
+            assert_eq!(fs::read_to_string(dir.join("config.toml.v0.bak")).unwrap(), old);
+            assert_eq!(saved_version(&path), Some(CONFIG_VERSION as i64));
+
+            // The upgraded file loads as it is
+            let (config, _) = Config::load(&path).unwrap();
+            assert_eq!(config.servers.len(), 1);
+            assert_eq!(backups(&dir).len(), 1);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn version_1_file_is_upgraded() {
+            let dir = scratch_dir("v1");
+            let path = dir.join("config.toml");
+            let old = "version = 1\n\n[[servers]]\nname = \"Home\"\naddress = \"tracker.lan\"\nport = \"8000\"\n\n[[servers]]\nname = \"Office\"\naddress = \"10.0.0.5\"\nport = \"9000\"\n\n[fitbit]\nclient_id = \"ABC123\"\n";
+            fs::write(&path, old).unwrap();
+
+            let (config, warning) = Config::load(&path).unwrap();
+            assert!(warning.is_none());
+            let servers: Vec<(&str, &str, &str)> = config.servers.iter().map(|s| (s.name.as_str(), s.address.as_str(), s.port.as_str())).collect();
+            assert_eq!(servers, vec![("Home", "tracker.lan", "8000"), ("Office", "10.0.0.5", "9000")]);
+            assert!(config.servers.iter().all(|s| s.scheme == Scheme::Http && matches!(s.auth, ServerAuth::None) && s.ca_certificate.is_none()));
+            assert_eq!(config.fitbit.client_id, "ABC123");
+
+            assert_eq!(backups(&dir), vec![dir.join("config.toml.v1.bak")]);
+            assert_eq!(fs::read_to_string(dir.join("config.toml.v1.bak")).unwrap(), old);
+            assert_eq!(saved_version(&path), Some(CONFIG_VERSION as i64));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn newer_version_is_refused() {
+            let dir = scratch_dir("newer");
+            let path = dir.join("config.toml");
+            let newer = format!("version = {}\nservers = []\n", CONFIG_VERSION + 1);
+            fs::write(&path, &newer).unwrap();
+
+            let error = Config::load(&path).unwrap_err();
+            assert!(error.to_string().contains("please update"), "{}", error);
+            // Nothing is touched, so the newer ntntkr can still read it
+            assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+            assert!(backups(&dir).is_empty());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn invalid_file_is_copied_aside() {
+            let dir = scratch_dir("invalid");
+            let path = dir.join("config.toml");
+            let invalid = "[[servers]]\nname = \"Home\"\naddress = \n";
+            fs::write(&path, invalid).unwrap();
+
+            let (config, warning) = Config::load(&path).unwrap();
+            assert!(config.servers.is_empty());
+            let backups = backups(&dir);
+            assert_eq!(backups.len(), 1);
+            assert_eq!(fs::read_to_string(&backups[0]).unwrap(), invalid);
+            let warning = warning.unwrap();
+            assert!(warning.contains("line 3"), "{}", warning);
+            assert!(warning.contains(&backups[0].display().to_string()), "{}", warning);
+
+            // The original is only replaced once a server is saved
+            assert_eq!(fs::read_to_string(&path).unwrap(), invalid);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        fn server(name: &str) -> Server {
+            Server { name: name.to_string(), address: "127.0.0.1".to_string(), port: "8000".to_string(), scheme: Scheme::Http, ca_certificate: None, auth: ServerAuth::None }
+        }
+
+        fn server_names(config: &Config) -> Vec<&str> {
+            config.servers.iter().map(|s| s.name.as_str()).collect()
+        }
+
+        #[test]
+        fn servers_are_saved_as_they_change() {
+            let dir = scratch_dir("servers");
+            let path = dir.join("config.toml");
+            let (mut config, _) = Config::load(&path).unwrap();
+
+            config.add_new_server(server("Home")).unwrap();
+            config.add_new_server(server("Office")).unwrap();
+            config.update_server(1, server("Work")).unwrap();
+            config.remove_server(0).unwrap();
+            assert_eq!(server_names(&config), vec!["Work"]);
+            assert_eq!(server_names(&Config::load(&path).unwrap().0), vec!["Work"]);
+
+            assert!(config.update_server(1, server("Nowhere")).is_err());
+            assert!(config.remove_server(1).is_err());
+            assert_eq!(server_names(&config), vec!["Work"]);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn failed_save_keeps_the_servers() {
+            let dir = scratch_dir("failed-save");
+            let path = dir.join("config.toml");
+            let (mut config, _) = Config::load(&path).unwrap();
+            config.add_new_server(server("Home")).unwrap();
+
+            // Nothing can be written once the config's directory is gone
+            fs::remove_dir_all(&dir).unwrap();
+            assert!(config.add_new_server(server("Office")).is_err());
+            assert!(config.update_server(0, server("Work")).is_err());
+            assert!(config.remove_server(0).is_err());
+            assert_eq!(server_names(&config), vec!["Home"]);
+        }
+
+        #[test]
+        fn file_with_wrong_types_is_copied_aside() {
+            let dir = scratch_dir("types");
+            let path = dir.join("config.toml");
+            fs::write(&path, "version = \"two\"\nservers = []\n").unwrap();
+            let (_, warning) = Config::load(&path).unwrap();
+            assert!(warning.unwrap().contains("version must be a whole number"));
+
+            fs::write(&path, "version = 2\nservers = \"none\"\n").unwrap();
+            let (config, warning) = Config::load(&path).unwrap();
+            assert!(config.servers.is_empty());
+            assert!(warning.is_some());
+            fs::remove_dir_all(&dir).unwrap();
+        }
     }
 }
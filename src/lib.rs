@@ -0,0 +1,5 @@
+pub mod api;
+pub mod app;
+pub mod config;
+pub mod modules;
+pub mod tasks;
@@ -1,17 +1,58 @@
-mod modules;
-mod app;
-mod config;
+use std::{io::stdout, path::PathBuf};
 
-use app::App;
+use color_eyre::{eyre::{bail, eyre}, Result};
+use crossterm::{event::{DisableBracketedPaste, EnableBracketedPaste}, execute};
+use ntntkr::{app::App, config::config::Config};
 
-use color_eyre::Result;
+const USAGE: &str = "Usage: ntntkr [--config FILE]
+
+Terminal client for the ntntkr tracker.
+
+Options:
+  --config FILE  Config file with the server list (default $XDG_CONFIG_HOME/ntntkr/config.toml)";
+
+struct Args {
+    config: Option<PathBuf>
+}
+
+impl Args {
+    fn parse() -> Result<Option<Self>> {
+        let mut args = Args { config: None };
+
+        let mut input = std::env::args().skip(1);
+        while let Some(arg) = input.next() {
+            let mut value = || input.next().ok_or_else(|| eyre!("{} needs a value\n\n{}", arg, USAGE));
+            match arg.as_str() {
+                "--config" => args.config = Some(PathBuf::from(value()?)),
+                "-h" | "--help" => return Ok(None),
+                _ => bail!("unknown argument {}\n\n{}", arg, USAGE),
+            }
+        }
+        Ok(Some(args))
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+    let Some(args) = Args::parse()? else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let config_path = match args.config {
+        Some(path) => path,
+        None => Config::default_path()?,
+    };
+    let (config, warning) = Config::load(&config_path)?;
+
     let terminal = ratatui::init();
-    let app = App::default();
+    // Without bracketed paste, pasted text arrives as key presses and would trigger keybinds
+    execute!(stdout(), EnableBracketedPaste)?;
+    let app = App::new(config, warning);
     let app_result = app.run(terminal).await;
+    // Like ratatui::restore, a terminal that cannot be reset is not worth failing over
+    let _ = execute!(stdout(), DisableBracketedPaste);
     ratatui::restore();
     app_result
 }
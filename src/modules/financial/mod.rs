@@ -0,0 +1,1194 @@
+use chrono::{Local, NaiveDate};
+use crossterm::event::KeyCode;
+use planned::{Pace, PlannedPurchase};
+use ratatui::{layout::{Constraint, Layout, Rect}, style::{Color, Style, Stylize}, text, widgets::{Block, Gauge, Paragraph, Row, Table, TableState, Tabs}};
+use recurring::{RecurringPurchase, Schedule};
+use regex::Regex;
+
+use crate::{api::{ApiClient, ApiResult, NewAccount, NewContribution, NewPlanned, NewPurchase, NewRecurring}, app::App, modules::TextInput};
+
+use super::{input_functions, rendering::{self, single_line_input}, selection::SelectionModules, RenderableModule, SanitizedForm, SelectedModule};
+
+pub mod planned;
+pub mod recurring;
+
+pub struct FinancialModule {}
+
+pub enum FinancialModules {
+    AccountList,
+    AccountEditing(AccountInput),
+    PurchaseList,
+    PurchaseEditing(PurchaseInput),
+    RecurringList,
+    RecurringEditing(RecurringInput),
+    PlannedList,
+    PlannedEditing(PlannedInput),
+    ContributionEditing(ContributionInput)
+}
+
+#[derive(Clone, Default)]
+pub enum AccountInput {
+    #[default]
+    Name,
+    AccountType,
+    StartingBalance
+}
+
+impl AccountInput {
+    fn next(self) -> Self {
+        match self {
+            AccountInput::Name => AccountInput::AccountType,
+            AccountInput::AccountType => AccountInput::StartingBalance,
+            AccountInput::StartingBalance => AccountInput::Name,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            AccountInput::Name => AccountInput::StartingBalance,
+            AccountInput::AccountType => AccountInput::Name,
+            AccountInput::StartingBalance => AccountInput::AccountType,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum PurchaseInput {
+    #[default]
+    Date,
+    Amount,
+    Payee,
+    Category
+}
+
+impl PurchaseInput {
+    fn next(self) -> Self {
+        match self {
+            PurchaseInput::Date => PurchaseInput::Amount,
+            PurchaseInput::Amount => PurchaseInput::Payee,
+            PurchaseInput::Payee => PurchaseInput::Category,
+            PurchaseInput::Category => PurchaseInput::Date,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            PurchaseInput::Date => PurchaseInput::Category,
+            PurchaseInput::Amount => PurchaseInput::Date,
+            PurchaseInput::Payee => PurchaseInput::Amount,
+            PurchaseInput::Category => PurchaseInput::Payee,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum RecurringInput {
+    #[default]
+    Account,
+    Amount,
+    Payee,
+    Category,
+    Schedule,
+    StartDate,
+    EndDate
+}
+
+impl RecurringInput {
+    fn next(self) -> Self {
+        match self {
+            RecurringInput::Account => RecurringInput::Amount,
+            RecurringInput::Amount => RecurringInput::Payee,
+            RecurringInput::Payee => RecurringInput::Category,
+            RecurringInput::Category => RecurringInput::Schedule,
+            RecurringInput::Schedule => RecurringInput::StartDate,
+            RecurringInput::StartDate => RecurringInput::EndDate,
+            RecurringInput::EndDate => RecurringInput::Account,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            RecurringInput::Account => RecurringInput::EndDate,
+            RecurringInput::Amount => RecurringInput::Account,
+            RecurringInput::Payee => RecurringInput::Amount,
+            RecurringInput::Category => RecurringInput::Payee,
+            RecurringInput::Schedule => RecurringInput::Category,
+            RecurringInput::StartDate => RecurringInput::Schedule,
+            RecurringInput::EndDate => RecurringInput::StartDate,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum PlannedInput {
+    #[default]
+    Name,
+    TargetAmount,
+    TargetDate,
+    Account
+}
+
+impl PlannedInput {
+    fn next(self) -> Self {
+        match self {
+            PlannedInput::Name => PlannedInput::TargetAmount,
+            PlannedInput::TargetAmount => PlannedInput::TargetDate,
+            PlannedInput::TargetDate => PlannedInput::Account,
+            PlannedInput::Account => PlannedInput::Name,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            PlannedInput::Name => PlannedInput::Account,
+            PlannedInput::TargetAmount => PlannedInput::Name,
+            PlannedInput::TargetDate => PlannedInput::TargetAmount,
+            PlannedInput::Account => PlannedInput::TargetDate,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum ContributionInput {
+    Date,
+    #[default]
+    Amount
+}
+
+impl ContributionInput {
+    fn next(self) -> Self {
+        match self {
+            ContributionInput::Date => ContributionInput::Amount,
+            ContributionInput::Amount => ContributionInput::Date,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            ContributionInput::Date => ContributionInput::Amount,
+            ContributionInput::Amount => ContributionInput::Date,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FinancialModuleData {
+    account_list: Vec<Account>,
+    purchase_list: Vec<Purchase>,
+    recurring_list: Vec<RecurringPurchase>,
+    planned_list: Vec<PlannedPurchase>,
+    account_table_state: TableState,
+    purchase_table_state: TableState,
+    recurring_table_state: TableState,
+    selected_planned: usize,
+    selected_account: Option<Account>,
+    editable_account_data: AccountEntry,
+    editable_purchase_data: PurchaseEntry,
+    editable_recurring_data: RecurringEntry,
+    editable_planned_data: PlannedEntry,
+    editable_contribution_data: ContributionEntry
+}
+
+/// Every list the financial screens show, loaded in one go so they never disagree.
+struct Ledger {
+    accounts: Vec<Account>,
+    purchases: Vec<Purchase>,
+    recurring: Vec<RecurringPurchase>,
+    planned: Vec<PlannedPurchase>
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct Account {
+    pub id: i64,
+    pub name: String,
+    pub account_type: String,
+    /// Balance in cents before any recorded purchases
+    pub starting_balance: i64
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct Purchase {
+    pub id: i64,
+    pub account_id: i64,
+    pub date: NaiveDate,
+    /// Amount in cents, positive values are money leaving the account
+    pub amount: i64,
+    pub payee: String,
+    pub category: String,
+    /// Recurring purchase this purchase was generated from, if any
+    #[serde(default)]
+    pub recurring_id: Option<i64>
+}
+
+#[derive(Default)]
+struct AccountEntry {
+    name: TextInput,
+    account_type: TextInput,
+    starting_balance: TextInput
+}
+
+impl AccountEntry {
+    fn request(&self) -> Option<NewAccount> {
+        Some(NewAccount {
+            name: self.name.text.trim().to_string(),
+            account_type: self.account_type.text.trim().to_string(),
+            starting_balance: parse_amount(&self.starting_balance.text)?,
+        })
+    }
+}
+
+impl SanitizedForm for AccountEntry {
+    fn verify_input(&self) -> bool {
+        self.name.valid && self.account_type.valid && self.starting_balance.valid
+    }
+
+    fn update_validity(&mut self) {
+        let text_regex = Regex::new(r"\S").unwrap();
+        self.name.valid = text_regex.is_match(&self.name.text);
+        self.account_type.valid = text_regex.is_match(&self.account_type.text);
+        self.starting_balance.valid = parse_amount(&self.starting_balance.text).is_some();
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(Default)]
+struct PurchaseEntry {
+    date: TextInput,
+    amount: TextInput,
+    payee: TextInput,
+    category: TextInput
+}
+
+impl PurchaseEntry {
+    fn new() -> Self {
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+        let mut entry = Self {
+            date: TextInput::new(today),
+            ..Default::default()
+        };
+        entry.date.cursor_to_end();
+        entry
+    }
+
+    fn request(&self, account_id: i64) -> Option<NewPurchase> {
+        Some(NewPurchase {
+            account_id,
+            date: NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").ok()?,
+            amount: parse_amount(&self.amount.text)?,
+            payee: self.payee.text.trim().to_string(),
+            category: self.category.text.trim().to_string(),
+            recurring_id: None,
+        })
+    }
+}
+
+impl SanitizedForm for PurchaseEntry {
+    fn verify_input(&self) -> bool {
+        self.date.valid && self.amount.valid && self.payee.valid && self.category.valid
+    }
+
+    fn update_validity(&mut self) {
+        let text_regex = Regex::new(r"\S").unwrap();
+        self.date.valid = NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").is_ok();
+        self.amount.valid = parse_amount(&self.amount.text).is_some();
+        self.payee.valid = text_regex.is_match(&self.payee.text);
+        self.category.valid = text_regex.is_match(&self.category.text);
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[derive(Default)]
+struct RecurringEntry {
+    account: TextInput,
+    amount: TextInput,
+    payee: TextInput,
+    category: TextInput,
+    schedule: TextInput,
+    start_date: TextInput,
+    end_date: TextInput,
+    /// Names of the user's accounts, used to validate the account field
+    account_names: Vec<String>
+}
+
+impl RecurringEntry {
+    fn new(account_names: Vec<String>) -> Self {
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+        let mut entry = Self {
+            start_date: TextInput::new(today),
+            account_names,
+            ..Default::default()
+        };
+        entry.start_date.cursor_to_end();
+        entry
+    }
+
+    fn request(&self, account_id: i64) -> Option<NewRecurring> {
+        let (start_date, end_date) = self.date_range()?;
+        Some(NewRecurring {
+            account_id,
+            amount: parse_amount(&self.amount.text)?,
+            payee: self.payee.text.trim().to_string(),
+            category: self.category.text.trim().to_string(),
+            schedule: self.schedule.text.parse::<Schedule>().ok()?.to_string(),
+            start_date,
+            end_date,
+        })
+    }
+
+    fn date_range(&self) -> Option<(NaiveDate, Option<NaiveDate>)> {
+        let start = NaiveDate::parse_from_str(&self.start_date.text, "%Y-%m-%d").ok()?;
+        if self.end_date.text.trim().is_empty() {
+            return Some((start, None));
+        }
+        let end = NaiveDate::parse_from_str(&self.end_date.text, "%Y-%m-%d").ok()?;
+        (end >= start).then_some((start, Some(end)))
+    }
+}
+
+impl SanitizedForm for RecurringEntry {
+    fn verify_input(&self) -> bool {
+        self.account.valid && self.amount.valid && self.payee.valid && self.category.valid && self.schedule.valid && self.start_date.valid && self.end_date.valid
+    }
+
+    fn update_validity(&mut self) {
+        let text_regex = Regex::new(r"\S").unwrap();
+        self.account.valid = self.account_names.iter().any(|n| n == self.account.text.trim());
+        self.amount.valid = parse_amount(&self.amount.text).is_some();
+        self.payee.valid = text_regex.is_match(&self.payee.text);
+        self.category.valid = text_regex.is_match(&self.category.text);
+        self.schedule.valid = self.schedule.text.parse::<Schedule>().is_ok();
+        self.start_date.valid = NaiveDate::parse_from_str(&self.start_date.text, "%Y-%m-%d").is_ok();
+        // The end date is optional but must not come before the start date
+        self.end_date.valid = !self.start_date.valid || self.date_range().is_some();
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::new(std::mem::take(&mut self.account_names));
+    }
+}
+
+#[derive(Default)]
+struct PlannedEntry {
+    name: TextInput,
+    target_amount: TextInput,
+    target_date: TextInput,
+    account: TextInput,
+    /// Names of the user's accounts, used to validate the account field
+    account_names: Vec<String>
+}
+
+impl PlannedEntry {
+    fn new(account_names: Vec<String>) -> Self {
+        Self {
+            account_names,
+            ..Default::default()
+        }
+    }
+
+    fn request(&self, account_id: i64) -> Option<NewPlanned> {
+        Some(NewPlanned {
+            account_id,
+            name: self.name.text.trim().to_string(),
+            target_amount: parse_amount(&self.target_amount.text)?,
+            target_date: NaiveDate::parse_from_str(&self.target_date.text, "%Y-%m-%d").ok()?,
+        })
+    }
+}
+
+impl SanitizedForm for PlannedEntry {
+    fn verify_input(&self) -> bool {
+        self.name.valid && self.target_amount.valid && self.target_date.valid && self.account.valid
+    }
+
+    fn update_validity(&mut self) {
+        let text_regex = Regex::new(r"\S").unwrap();
+        self.name.valid = text_regex.is_match(&self.name.text);
+        self.target_amount.valid = parse_amount(&self.target_amount.text).is_some_and(|a| a > 0);
+        self.target_date.valid = NaiveDate::parse_from_str(&self.target_date.text, "%Y-%m-%d").is_ok();
+        self.account.valid = self.account_names.iter().any(|n| n == self.account.text.trim());
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::new(std::mem::take(&mut self.account_names));
+    }
+}
+
+#[derive(Default)]
+struct ContributionEntry {
+    date: TextInput,
+    amount: TextInput
+}
+
+impl ContributionEntry {
+    fn new() -> Self {
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+        let mut entry = Self {
+            date: TextInput::new(today),
+            ..Default::default()
+        };
+        entry.date.cursor_to_end();
+        entry
+    }
+
+    fn request(&self) -> Option<NewContribution> {
+        Some(NewContribution {
+            date: NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").ok()?,
+            amount: parse_amount(&self.amount.text)?,
+        })
+    }
+}
+
+impl SanitizedForm for ContributionEntry {
+    fn verify_input(&self) -> bool {
+        self.date.valid && self.amount.valid
+    }
+
+    fn update_validity(&mut self) {
+        self.date.valid = NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").is_ok();
+        self.amount.valid = parse_amount(&self.amount.text).is_some();
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Parses a decimal currency amount such as `12.5` or `-3.99` into cents.
+pub fn parse_amount(text: &str) -> Option<i64> {
+    let amount_regex = Regex::new(r"^(-?)([0-9]+)(?:\.([0-9]{1,2}))?$").unwrap();
+    let captures = amount_regex.captures(text.trim())?;
+    let whole: i64 = captures[2].parse().ok()?;
+    let fraction = match captures.get(3) {
+        Some(f) if f.as_str().len() == 1 => f.as_str().parse::<i64>().ok()? * 10,
+        Some(f) => f.as_str().parse().ok()?,
+        None => 0,
+    };
+    let cents = whole.checked_mul(100)?.checked_add(fraction)?;
+    Some(if &captures[1] == "-" { -cents } else { cents })
+}
+
+pub fn format_amount(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+impl FinancialModuleData {
+    fn set_ledger(&mut self, ledger: Ledger) {
+        self.account_list = ledger.accounts;
+        self.purchase_list = ledger.purchases;
+        self.purchase_list.sort_by(|a, b| b.date.cmp(&a.date).then(b.id.cmp(&a.id)));
+        self.recurring_list = ledger.recurring;
+        self.planned_list = ledger.planned;
+        self.planned_list.sort_by_key(|p| p.target_date);
+        self.selected_planned = self.selected_planned.min(self.planned_list.len().saturating_sub(1));
+
+        if self.recurring_list.is_empty() {
+            self.recurring_table_state.select(None);
+        } else if self.recurring_table_state.selected().is_some_and(|i| i >= self.recurring_list.len()) {
+            self.recurring_table_state.select_last();
+        }
+
+        // Keep the selection inside the bounds of the new list
+        if self.account_list.is_empty() {
+            self.account_table_state.select(None);
+        } else if self.account_table_state.selected().is_some_and(|i| i >= self.account_list.len()) {
+            self.account_table_state.select_last();
+        }
+    }
+
+    fn account_balance(&self, account: &Account) -> i64 {
+        let spent: i64 = self.purchase_list.iter().filter(|p| p.account_id == account.id).map(|p| p.amount).sum();
+        account.starting_balance - spent
+    }
+
+    fn account_name(&self, account_id: i64) -> String {
+        self.account_list.iter().find(|a| a.id == account_id).map(|a| a.name.clone()).unwrap_or_default()
+    }
+
+    fn account_purchases(&self) -> Vec<&Purchase> {
+        match &self.selected_account {
+            Some(account) => self.purchase_list.iter().filter(|p| p.account_id == account.id).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl RenderableModule for FinancialModule {
+    fn input_handling(app: &mut App, key: crossterm::event::KeyEvent) {
+        if let SelectedModule::FinancialModule(module_status) = &app.selected_module {
+            match module_status {
+                FinancialModules::AccountList => {
+                    let financial_module = &mut app.module_data.financial_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Char('c') => app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountEditing(AccountInput::default())),
+                        KeyCode::Char('r') => FinancialModule::refresh_ledger(app),
+                        KeyCode::Tab => app.selected_module = SelectedModule::FinancialModule(FinancialModules::RecurringList),
+                        KeyCode::Enter => {
+                            if let Some(account) = financial_module.account_table_state.selected().and_then(|index| financial_module.account_list.get(index)) {
+                                financial_module.selected_account = Some(account.clone());
+                                financial_module.purchase_table_state.select(None);
+                                app.selected_module = SelectedModule::FinancialModule(FinancialModules::PurchaseList);
+                            }
+                        },
+                        KeyCode::Up => financial_module.account_table_state.select_previous(),
+                        KeyCode::Down => financial_module.account_table_state.select_next(),
+                        _ => {}
+                    }
+                },
+                FinancialModules::AccountEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.financial_module.editable_account_data;
+                    let input = match selected_input {
+                        AccountInput::Name => &mut editable_data.name,
+                        AccountInput::AccountType => &mut editable_data.account_type,
+                        AccountInput::StartingBalance => &mut editable_data.starting_balance,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            let Some(account) = editable_data.request() else { return };
+                            app.request("Saving account", move |client| {
+                                let account = account.clone();
+                                async move { client.create_account(user_id, &account).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.financial_module.editable_account_data.clear_form();
+                                // Refresh Ledger
+                                FinancialModule::refresh_ledger(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                FinancialModules::PurchaseList => {
+                    let financial_module = &mut app.module_data.financial_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountList),
+                        KeyCode::Char('c') => {
+                            financial_module.editable_purchase_data.clear_form();
+                            financial_module.editable_purchase_data.update_validity();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::PurchaseEditing(PurchaseInput::default()));
+                        },
+                        KeyCode::Char('r') => FinancialModule::refresh_ledger(app),
+                        KeyCode::Up => financial_module.purchase_table_state.select_previous(),
+                        KeyCode::Down => financial_module.purchase_table_state.select_next(),
+                        _ => {}
+                    }
+                },
+                FinancialModules::PurchaseEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.financial_module.editable_purchase_data;
+                    let input = match selected_input {
+                        PurchaseInput::Date => &mut editable_data.date,
+                        PurchaseInput::Amount => &mut editable_data.amount,
+                        PurchaseInput::Payee => &mut editable_data.payee,
+                        PurchaseInput::Category => &mut editable_data.category,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::FinancialModule(FinancialModules::PurchaseList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::PurchaseEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::PurchaseEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            let financial_module = &app.module_data.financial_module;
+                            let Some(purchase) = financial_module.selected_account.as_ref().and_then(|a| financial_module.editable_purchase_data.request(a.id)) else { return };
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            app.request("Saving purchase", move |client| {
+                                let purchase = purchase.clone();
+                                async move { client.create_purchase(user_id, &purchase).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.financial_module.editable_purchase_data.clear_form();
+                                // Refresh Ledger
+                                FinancialModule::refresh_ledger(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::FinancialModule(FinancialModules::PurchaseList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                FinancialModules::RecurringList => {
+                    let financial_module = &mut app.module_data.financial_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Tab => app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedList),
+                        KeyCode::Char('c') => {
+                            let account_names = financial_module.account_list.iter().map(|a| a.name.clone()).collect();
+                            financial_module.editable_recurring_data = RecurringEntry::new(account_names);
+                            financial_module.editable_recurring_data.update_validity();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::RecurringEditing(RecurringInput::default()));
+                        },
+                        KeyCode::Char('r') => FinancialModule::refresh_ledger(app),
+                        KeyCode::Up => financial_module.recurring_table_state.select_previous(),
+                        KeyCode::Down => financial_module.recurring_table_state.select_next(),
+                        _ => {}
+                    }
+                },
+                FinancialModules::RecurringEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.financial_module.editable_recurring_data;
+                    let input = match selected_input {
+                        RecurringInput::Account => &mut editable_data.account,
+                        RecurringInput::Amount => &mut editable_data.amount,
+                        RecurringInput::Payee => &mut editable_data.payee,
+                        RecurringInput::Category => &mut editable_data.category,
+                        RecurringInput::Schedule => &mut editable_data.schedule,
+                        RecurringInput::StartDate => &mut editable_data.start_date,
+                        RecurringInput::EndDate => &mut editable_data.end_date,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::FinancialModule(FinancialModules::RecurringList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::RecurringEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::RecurringEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            let financial_module = &app.module_data.financial_module;
+                            let account = financial_module.account_list.iter().find(|a| a.name == financial_module.editable_recurring_data.account.text.trim());
+                            let Some(recurring) = account.and_then(|a| financial_module.editable_recurring_data.request(a.id)) else { return };
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            app.request("Saving recurring purchase", move |client| {
+                                let recurring = recurring.clone();
+                                async move { client.create_recurring(user_id, &recurring).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.financial_module.editable_recurring_data.clear_form();
+                                // Refresh Ledger, picking up anything that is already due
+                                FinancialModule::open_ledger(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::FinancialModule(FinancialModules::RecurringList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                FinancialModules::PlannedList => {
+                    let financial_module = &mut app.module_data.financial_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Tab => app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountList),
+                        KeyCode::Char('c') => {
+                            let account_names = financial_module.account_list.iter().map(|a| a.name.clone()).collect();
+                            financial_module.editable_planned_data = PlannedEntry::new(account_names);
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedEditing(PlannedInput::default()));
+                        },
+                        KeyCode::Char('a') if !financial_module.planned_list.is_empty() => {
+                            financial_module.editable_contribution_data.clear_form();
+                            financial_module.editable_contribution_data.update_validity();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::ContributionEditing(ContributionInput::default()));
+                        },
+                        KeyCode::Char('r') => FinancialModule::refresh_ledger(app),
+                        KeyCode::Up => financial_module.selected_planned = financial_module.selected_planned.saturating_sub(1),
+                        KeyCode::Down => financial_module.selected_planned = (financial_module.selected_planned + 1).min(financial_module.planned_list.len().saturating_sub(1)),
+                        _ => {}
+                    }
+                },
+                FinancialModules::PlannedEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.financial_module.editable_planned_data;
+                    let input = match selected_input {
+                        PlannedInput::Name => &mut editable_data.name,
+                        PlannedInput::TargetAmount => &mut editable_data.target_amount,
+                        PlannedInput::TargetDate => &mut editable_data.target_date,
+                        PlannedInput::Account => &mut editable_data.account,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            let financial_module = &app.module_data.financial_module;
+                            let account = financial_module.account_list.iter().find(|a| a.name == financial_module.editable_planned_data.account.text.trim());
+                            let Some(planned) = account.and_then(|a| financial_module.editable_planned_data.request(a.id)) else { return };
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            app.request("Saving planned purchase", move |client| {
+                                let planned = planned.clone();
+                                async move { client.create_planned(user_id, &planned).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.financial_module.editable_planned_data.clear_form();
+                                // Refresh Ledger
+                                FinancialModule::refresh_ledger(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                FinancialModules::ContributionEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.financial_module.editable_contribution_data;
+                    let input = match selected_input {
+                        ContributionInput::Date => &mut editable_data.date,
+                        ContributionInput::Amount => &mut editable_data.amount,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::ContributionEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::FinancialModule(FinancialModules::ContributionEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            let financial_module = &app.module_data.financial_module;
+                            let Some(planned_id) = financial_module.planned_list.get(financial_module.selected_planned).map(|p| p.id) else { return };
+                            let Some(contribution) = financial_module.editable_contribution_data.request() else { return };
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            app.request("Saving contribution", move |client| {
+                                let contribution = contribution.clone();
+                                async move { client.create_contribution(user_id, planned_id, &contribution).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.financial_module.editable_contribution_data.clear_form();
+                                // Refresh Ledger
+                                FinancialModule::refresh_ledger(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::FinancialModule(FinancialModules::PlannedList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+            }
+        }
+    }
+
+    fn render(app: &mut App, frame: &mut ratatui::Frame) {
+        let rects = Layout::vertical([Constraint::Length(3), Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
+
+        let (selected_tab, footer_contents) = match &app.selected_module {
+            SelectedModule::FinancialModule(FinancialModules::PurchaseList | FinancialModules::PurchaseEditing(_)) => {
+                FinancialModule::render_purchases(app, frame, rects[1]);
+                (0, text::Line::from("Esc: Back to Accounts | Arrow Keys: Navigation | c: Record New Purchase | r: Refresh"))
+            },
+            SelectedModule::FinancialModule(FinancialModules::RecurringList | FinancialModules::RecurringEditing(_)) => {
+                FinancialModule::render_recurring(app, frame, rects[1]);
+                (1, text::Line::from("Esc: Back | Tab: Switch View | Arrow Keys: Navigation | c: Create Recurring Purchase | r: Refresh"))
+            },
+            SelectedModule::FinancialModule(FinancialModules::PlannedList | FinancialModules::PlannedEditing(_) | FinancialModules::ContributionEditing(_)) => {
+                FinancialModule::render_planned(app, frame, rects[1]);
+                (2, text::Line::from("Esc: Back | Tab: Switch View | Arrow Keys: Navigation | c: Create Planned Purchase | a: Add Contribution | r: Refresh"))
+            },
+            _ => {
+                FinancialModule::render_accounts(app, frame, rects[1]);
+                (0, text::Line::from("Esc: Back | Tab: Switch View | Enter: View Purchases | Arrow Keys: Navigation | c: Create New Account | r: Refresh"))
+            },
+        };
+
+        let tabs = Tabs::new(vec!["Accounts", "Recurring", "Planned"]).block(Block::bordered().title("Financial")).highlight_style(Style::new().reversed()).select(selected_tab);
+        frame.render_widget(tabs, rects[0]);
+
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[2]);
+
+        match &app.selected_module {
+            SelectedModule::FinancialModule(FinancialModules::AccountEditing(selected_input)) => {
+                let area = rendering::popup_area_lengths(frame.area(), 40, 12);
+
+                let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3), Constraint::Length(3), Constraint::Length(3)]).split(area);
+
+                let editable_data = &app.module_data.financial_module.editable_account_data;
+
+                let input_name = single_line_input(&editable_data.name, "Account Name");
+                frame.render_widget(input_name, rects[0]);
+
+                let input_type = single_line_input(&editable_data.account_type, "Account Type");
+                frame.render_widget(input_type, rects[1]);
+
+                let input_balance = single_line_input(&editable_data.starting_balance, "Starting Balance");
+                frame.render_widget(input_balance, rects[2]);
+
+                let (rect, input) = match selected_input {
+                    AccountInput::Name => (0, &editable_data.name),
+                    AccountInput::AccountType => (1, &editable_data.account_type),
+                    AccountInput::StartingBalance => (2, &editable_data.starting_balance),
+                };
+                frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+                let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Account");
+                let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+                frame.render_widget(keybinds, rects[3]);
+            },
+            SelectedModule::FinancialModule(FinancialModules::PurchaseEditing(selected_input)) => {
+                let area = rendering::popup_area_lengths(frame.area(), 40, 15);
+
+                let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3), Constraint::Length(3), Constraint::Length(3), Constraint::Length(3)]).split(area);
+
+                let editable_data = &app.module_data.financial_module.editable_purchase_data;
+
+                let input_date = single_line_input(&editable_data.date, "Date (YYYY-MM-DD)");
+                frame.render_widget(input_date, rects[0]);
+
+                let input_amount = single_line_input(&editable_data.amount, "Amount");
+                frame.render_widget(input_amount, rects[1]);
+
+                let input_payee = single_line_input(&editable_data.payee, "Payee");
+                frame.render_widget(input_payee, rects[2]);
+
+                let input_category = single_line_input(&editable_data.category, "Category");
+                frame.render_widget(input_category, rects[3]);
+
+                let (rect, input) = match selected_input {
+                    PurchaseInput::Date => (0, &editable_data.date),
+                    PurchaseInput::Amount => (1, &editable_data.amount),
+                    PurchaseInput::Payee => (2, &editable_data.payee),
+                    PurchaseInput::Category => (3, &editable_data.category),
+                };
+                frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+                let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Purchase");
+                let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+                frame.render_widget(keybinds, rects[4]);
+            },
+            SelectedModule::FinancialModule(FinancialModules::RecurringEditing(selected_input)) => {
+                let area = rendering::popup_area_lengths(frame.area(), 44, 24);
+
+                let rects = Layout::vertical([Constraint::Length(3); 8]).split(area);
+
+                let editable_data = &app.module_data.financial_module.editable_recurring_data;
+
+                let inputs = [
+                    (&editable_data.account, "Account Name"),
+                    (&editable_data.amount, "Amount"),
+                    (&editable_data.payee, "Payee"),
+                    (&editable_data.category, "Category"),
+                    (&editable_data.schedule, "Schedule (daily, weekly:mon, monthly:15, nth:2:tue, last:fri)"),
+                    (&editable_data.start_date, "Start Date (YYYY-MM-DD)"),
+                    (&editable_data.end_date, "End Date (optional)"),
+                ];
+                for (rect, (input, title)) in inputs.iter().enumerate() {
+                    frame.render_widget(single_line_input(input, title), rects[rect]);
+                }
+
+                let rect = match selected_input {
+                    RecurringInput::Account => 0,
+                    RecurringInput::Amount => 1,
+                    RecurringInput::Payee => 2,
+                    RecurringInput::Category => 3,
+                    RecurringInput::Schedule => 4,
+                    RecurringInput::StartDate => 5,
+                    RecurringInput::EndDate => 6,
+                };
+                frame.set_cursor_position(rendering::cursor_position(inputs[rect].0, rects[rect]));
+
+                let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Recurring Purchase");
+                let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+                frame.render_widget(keybinds, rects[7]);
+            },
+            SelectedModule::FinancialModule(FinancialModules::PlannedEditing(selected_input)) => {
+                let area = rendering::popup_area_lengths(frame.area(), 40, 15);
+
+                let rects = Layout::vertical([Constraint::Length(3); 5]).split(area);
+
+                let editable_data = &app.module_data.financial_module.editable_planned_data;
+
+                let inputs = [
+                    (&editable_data.name, "Planned Purchase"),
+                    (&editable_data.target_amount, "Target Amount"),
+                    (&editable_data.target_date, "Target Date (YYYY-MM-DD)"),
+                    (&editable_data.account, "Funding Account Name"),
+                ];
+                for (rect, (input, title)) in inputs.iter().enumerate() {
+                    frame.render_widget(single_line_input(input, title), rects[rect]);
+                }
+
+                let rect = match selected_input {
+                    PlannedInput::Name => 0,
+                    PlannedInput::TargetAmount => 1,
+                    PlannedInput::TargetDate => 2,
+                    PlannedInput::Account => 3,
+                };
+                frame.set_cursor_position(rendering::cursor_position(inputs[rect].0, rects[rect]));
+
+                let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Planned Purchase");
+                let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+                frame.render_widget(keybinds, rects[4]);
+            },
+            SelectedModule::FinancialModule(FinancialModules::ContributionEditing(selected_input)) => {
+                let area = rendering::popup_area_lengths(frame.area(), 40, 9);
+
+                let rects = Layout::vertical([Constraint::Length(3); 3]).split(area);
+
+                let editable_data = &app.module_data.financial_module.editable_contribution_data;
+
+                let input_date = single_line_input(&editable_data.date, "Date (YYYY-MM-DD)");
+                frame.render_widget(input_date, rects[0]);
+
+                let input_amount = single_line_input(&editable_data.amount, "Contribution Amount");
+                frame.render_widget(input_amount, rects[1]);
+
+                let (rect, input) = match selected_input {
+                    ContributionInput::Date => (0, &editable_data.date),
+                    ContributionInput::Amount => (1, &editable_data.amount),
+                };
+                frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+                let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Contribution");
+                let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+                frame.render_widget(keybinds, rects[2]);
+            },
+            _ => {}
+        }
+    }
+}
+
+impl FinancialModule {
+    /// Loads the ledger and materializes any recurring purchases that have come due since the last visit.
+    pub fn open_ledger(app: &mut App) {
+        let user_id = app.module_data.user_module.selected_user.id;
+        app.request("Updating ledger", move |client| async move {
+            let ledger = FinancialModule::load_ledger(&client, user_id).await?;
+            if FinancialModule::materialize_recurring(&client, user_id, &ledger).await? {
+                return FinancialModule::load_ledger(&client, user_id).await;
+            }
+            Ok(ledger)
+        }, |app, ledger| app.module_data.financial_module.set_ledger(ledger));
+    }
+
+    pub fn refresh_ledger(app: &mut App) {
+        let user_id = app.module_data.user_module.selected_user.id;
+        app.request("Loading ledger", move |client| async move { FinancialModule::load_ledger(&client, user_id).await }, |app, ledger| app.module_data.financial_module.set_ledger(ledger));
+    }
+
+    async fn load_ledger(client: &ApiClient, user_id: i64) -> ApiResult<Ledger> {
+        Ok(Ledger {
+            accounts: client.accounts(user_id).await?,
+            purchases: client.purchases(user_id).await?,
+            recurring: client.recurring(user_id).await?,
+            planned: client.planned(user_id).await?,
+        })
+    }
+
+    /// Posts a purchase for every due date of every recurring purchase, returning whether anything was posted.
+    ///
+    /// Each recurring purchase records the last date it was materialized for, and dates that already
+    /// have a matching purchase are skipped so an interrupted run never produces duplicates.
+    async fn materialize_recurring(client: &ApiClient, user_id: i64, ledger: &Ledger) -> ApiResult<bool> {
+        let today = Local::now().date_naive();
+
+        let mut materialized = false;
+        for recurring in &ledger.recurring {
+            let due_dates = recurring.due_dates(today);
+            let Some(last_due) = due_dates.last().copied() else { continue };
+
+            for date in due_dates {
+                let already_posted = ledger.purchases.iter().any(|p| p.recurring_id == Some(recurring.id) && p.date == date);
+                if !already_posted {
+                    let purchase = NewPurchase {
+                        account_id: recurring.account_id,
+                        date,
+                        amount: recurring.amount,
+                        payee: recurring.payee.clone(),
+                        category: recurring.category.clone(),
+                        recurring_id: Some(recurring.id),
+                    };
+                    client.create_purchase(user_id, &purchase).await?;
+                }
+            }
+
+            // Every due date is on the server by now, an error above leaves the marker where it was
+            client.set_last_materialized(user_id, recurring.id, last_due).await?;
+            materialized = true;
+        }
+        Ok(materialized)
+    }
+
+    fn render_accounts(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let financial_module = &mut app.module_data.financial_module;
+
+        let header = Row::new(vec!["Name", "Type", "Balance"]).bold();
+        let rows: Vec<Row> = financial_module.account_list.iter().map(|a| Row::new(vec![
+            a.name.clone(),
+            a.account_type.clone(),
+            format_amount(financial_module.account_balance(a)),
+        ])).collect();
+        let widths = [Constraint::Fill(1), Constraint::Length(15), Constraint::Length(15)];
+
+        let accounts_table = Table::new(rows, widths).header(header).block(Block::bordered().title("Accounts")).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(accounts_table, area, &mut financial_module.account_table_state);
+
+        if !financial_module.account_list.is_empty() && financial_module.account_table_state.selected().is_none() {
+            financial_module.account_table_state.select_next();
+        }
+    }
+
+    fn render_purchases(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let financial_module = &mut app.module_data.financial_module;
+
+        let title = match &financial_module.selected_account {
+            Some(account) => format!("Purchases - {} (Balance: {})", account.name, format_amount(financial_module.account_balance(account))),
+            None => "Purchases".to_string(),
+        };
+
+        let header = Row::new(vec!["Date", "Amount", "Payee", "Category"]).bold();
+        let purchases = financial_module.account_purchases();
+        let purchase_count = purchases.len();
+        let rows: Vec<Row> = purchases.iter().map(|p| Row::new(vec![
+            p.date.format("%Y-%m-%d").to_string(),
+            format_amount(p.amount),
+            p.payee.clone(),
+            p.category.clone(),
+        ])).collect();
+        let widths = [Constraint::Length(12), Constraint::Length(12), Constraint::Fill(1), Constraint::Length(20)];
+
+        let purchases_table = Table::new(rows, widths).header(header).block(Block::bordered().title(title)).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(purchases_table, area, &mut financial_module.purchase_table_state);
+
+        if purchase_count > 0 && financial_module.purchase_table_state.selected().is_none() {
+            financial_module.purchase_table_state.select_next();
+        }
+    }
+
+    fn render_recurring(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let financial_module = &mut app.module_data.financial_module;
+        let today = Local::now().date_naive();
+
+        let header = Row::new(vec!["Account", "Payee", "Amount", "Schedule", "Start", "End", "Next Due"]).bold();
+        let rows: Vec<Row> = financial_module.recurring_list.iter().map(|r| Row::new(vec![
+            financial_module.account_name(r.account_id),
+            r.payee.clone(),
+            format_amount(r.amount),
+            r.schedule.to_string(),
+            r.start_date.format("%Y-%m-%d").to_string(),
+            r.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+            r.next_due(today).map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or("-".to_string()),
+        ])).collect();
+        let widths = [Constraint::Length(15), Constraint::Fill(1), Constraint::Length(12), Constraint::Length(12), Constraint::Length(12), Constraint::Length(12), Constraint::Length(12)];
+
+        let recurring_table = Table::new(rows, widths).header(header).block(Block::bordered().title("Recurring Purchases")).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(recurring_table, area, &mut financial_module.recurring_table_state);
+
+        if !financial_module.recurring_list.is_empty() && financial_module.recurring_table_state.selected().is_none() {
+            financial_module.recurring_table_state.select_next();
+        }
+    }
+
+    fn render_planned(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let financial_module = &app.module_data.financial_module;
+        let today = Local::now().date_naive();
+
+        let block = Block::bordered().title("Planned Purchases");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if financial_module.planned_list.is_empty() {
+            frame.render_widget(Paragraph::new("No planned purchases yet, press c to create one").centered(), inner);
+            return;
+        }
+
+        // Each planned purchase takes three rows, scroll so the selected one stays visible
+        let visible = (inner.height / 3).max(1) as usize;
+        let offset = financial_module.selected_planned.saturating_sub(visible - 1);
+
+        let rects = Layout::vertical(vec![Constraint::Length(3); visible]).split(inner);
+        for (rect, (index, planned)) in rects.iter().zip(financial_module.planned_list.iter().enumerate().skip(offset)) {
+            let (projection, color) = match planned.pace(today) {
+                Pace::Funded => ("Funded".to_string(), Color::Green),
+                Pace::OnTrack(date) => (format!("Projected {}", date.format("%Y-%m-%d")), Color::Green),
+                Pace::Behind(date) => (format!("Projected {} - will miss target!", date.format("%Y-%m-%d")), Color::Red),
+                Pace::NoHistory if planned.target_date < today => ("No contributions - target date passed!".to_string(), Color::Red),
+                Pace::NoHistory => ("No contributions yet".to_string(), Color::Yellow),
+            };
+
+            let title = format!("{} ({}) - Target {} | {}", planned.name, financial_module.account_name(planned.account_id), planned.target_date.format("%Y-%m-%d"), projection);
+            let label = format!("{} / {} ({:.0}%)", format_amount(planned.saved()), format_amount(planned.target_amount), planned.progress() * 100.0);
+
+            let border_style = if index == financial_module.selected_planned { Style::new().reversed() } else { Style::new() };
+            let gauge = Gauge::default().block(Block::bordered().title(title).border_style(border_style)).gauge_style(Style::new().fg(color)).ratio(planned.progress()).label(label);
+            frame.render_widget(gauge, *rect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_are_parsed_to_cents() {
+        assert_eq!(parse_amount("12"), Some(1_200));
+        assert_eq!(parse_amount(" 12.5 "), Some(1_250));
+        assert_eq!(parse_amount("12.05"), Some(1_205));
+        assert_eq!(parse_amount("-12.5"), Some(-1_250));
+        assert_eq!(parse_amount("-0.05"), Some(-5));
+        // Fractions of a cent are refused rather than rounded
+        assert_eq!(parse_amount("1.005"), None);
+        assert_eq!(parse_amount("0.001"), None);
+        assert_eq!(parse_amount("1."), None);
+        assert_eq!(parse_amount(".5"), None);
+        assert_eq!(parse_amount("--1"), None);
+        assert_eq!(parse_amount("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn amounts_are_formatted_from_cents() {
+        assert_eq!(format_amount(1_205), "12.05");
+        assert_eq!(format_amount(0), "0.00");
+        assert_eq!(format_amount(-5), "-0.05");
+        assert_eq!(format_amount(-1_250), "-12.50");
+        for cents in [-100_001, -99, -1, 7, 100_000] {
+            assert_eq!(parse_amount(&format_amount(cents)), Some(cents));
+        }
+    }
+}
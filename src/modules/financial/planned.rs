@@ -0,0 +1,140 @@
+use chrono::{Days, NaiveDate};
+
+#[derive(serde::Deserialize, Clone)]
+pub struct Contribution {
+    pub date: NaiveDate,
+    /// Amount in cents set aside towards the planned purchase
+    pub amount: i64
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct PlannedPurchase {
+    pub id: i64,
+    pub account_id: i64,
+    pub name: String,
+    /// Target amount in cents
+    pub target_amount: i64,
+    pub target_date: NaiveDate,
+    #[serde(default)]
+    pub contributions: Vec<Contribution>
+}
+
+/// Whether a planned purchase is on course to be funded by its target date.
+pub enum Pace {
+    Funded,
+    OnTrack(NaiveDate),
+    Behind(NaiveDate),
+    /// Nothing has been saved yet, so there is no history to project from
+    NoHistory
+}
+
+impl PlannedPurchase {
+    pub fn saved(&self) -> i64 {
+        self.contributions.iter().map(|c| c.amount).sum()
+    }
+
+    /// Fraction of the target that has been saved, clamped to `0.0..=1.0`.
+    pub fn progress(&self) -> f64 {
+        if self.target_amount <= 0 {
+            return 1.0;
+        }
+        (self.saved() as f64 / self.target_amount as f64).clamp(0.0, 1.0)
+    }
+
+    /// Projects when the target will be reached if saving continues at the average daily rate
+    /// seen since the first contribution.
+    pub fn projected_completion(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let saved = self.saved();
+        let remaining = self.target_amount - saved;
+        if remaining <= 0 {
+            return Some(today);
+        }
+
+        let first = self.contributions.iter().map(|c| c.date).min()?;
+        // Count the first day so a single contribution made today still gives a rate
+        let days_saving = (today.signed_duration_since(first).num_days() + 1).max(1);
+        let daily_rate = saved as f64 / days_saving as f64;
+        if daily_rate <= 0.0 {
+            return None;
+        }
+
+        let days_left = (remaining as f64 / daily_rate).ceil() as u64;
+        today.checked_add_days(Days::new(days_left))
+    }
+
+    pub fn pace(&self, today: NaiveDate) -> Pace {
+        if self.saved() >= self.target_amount {
+            return Pace::Funded;
+        }
+        match self.projected_completion(today) {
+            Some(date) if date <= self.target_date => Pace::OnTrack(date),
+            Some(date) => Pace::Behind(date),
+            None => Pace::NoHistory,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn planned(target_date: NaiveDate, contributions: &[(NaiveDate, i64)]) -> PlannedPurchase {
+        PlannedPurchase {
+            id: 1,
+            account_id: 1,
+            name: "Bike".to_string(),
+            target_amount: 10_000,
+            target_date,
+            contributions: contributions.iter().map(|&(date, amount)| Contribution { date, amount }).collect(),
+        }
+    }
+
+    #[test]
+    fn nothing_saved_has_no_projection() {
+        let today = date(2024, 3, 10);
+        let empty = planned(date(2024, 6, 1), &[]);
+        assert_eq!(empty.projected_completion(today), None);
+        assert!(matches!(empty.pace(today), Pace::NoHistory));
+        assert_eq!(empty.progress(), 0.0);
+
+        // Contributions that add up to nothing give no rate either
+        let cancelled = planned(date(2024, 6, 1), &[(date(2024, 3, 1), 500), (date(2024, 3, 2), -500)]);
+        assert_eq!(cancelled.projected_completion(today), None);
+        assert!(matches!(cancelled.pace(today), Pace::NoHistory));
+    }
+
+    #[test]
+    fn reached_targets_are_funded() {
+        let today = date(2024, 3, 10);
+        let funded = planned(date(2024, 3, 1), &[(date(2024, 2, 1), 6_000), (date(2024, 2, 15), 5_000)]);
+        assert_eq!(funded.projected_completion(today), Some(today));
+        assert!(matches!(funded.pace(today), Pace::Funded));
+        assert_eq!(funded.progress(), 1.0);
+    }
+
+    #[test]
+    fn projection_follows_the_daily_rate() {
+        // 3000 over the ten days since the 1st is 300 a day, so the other 7000 take 24 more days
+        let today = date(2024, 3, 10);
+        let contributions = [(date(2024, 3, 1), 1_000), (date(2024, 3, 6), 2_000)];
+        let on_track = planned(date(2024, 4, 30), &contributions);
+        assert_eq!(on_track.projected_completion(today), Some(date(2024, 4, 3)));
+        assert!(matches!(on_track.pace(today), Pace::OnTrack(d) if d == date(2024, 4, 3)));
+
+        let tight = planned(date(2024, 4, 2), &contributions);
+        assert!(matches!(tight.pace(today), Pace::Behind(d) if d == date(2024, 4, 3)));
+    }
+
+    #[test]
+    fn past_deadlines_fall_behind() {
+        let today = date(2024, 3, 10);
+        let late = planned(date(2024, 3, 5), &[(date(2024, 3, 10), 5_000)]);
+        // A single contribution made today already gives a rate
+        assert_eq!(late.projected_completion(today), Some(date(2024, 3, 11)));
+        assert!(matches!(late.pace(today), Pace::Behind(d) if d > late.target_date));
+    }
+}
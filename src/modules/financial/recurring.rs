@@ -0,0 +1,237 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+
+/// How often a recurring purchase comes due.
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub enum Schedule {
+    Daily,
+    Weekly(Weekday),
+    /// Day of the month, clamped to the last day for shorter months
+    Monthly(u32),
+    /// The nth (1 to 4) occurrence of a weekday within the month
+    NthWeekday(u32, Weekday),
+    LastWeekday(Weekday)
+}
+
+impl Schedule {
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        match self {
+            Schedule::Daily => true,
+            Schedule::Weekly(weekday) => date.weekday() == *weekday,
+            Schedule::Monthly(day) => date.day() == (*day).min(days_in_month(date)),
+            Schedule::NthWeekday(n, weekday) => date.weekday() == *weekday && (date.day() - 1) / 7 + 1 == *n,
+            Schedule::LastWeekday(weekday) => date.weekday() == *weekday && date.day() + 7 > days_in_month(date),
+        }
+    }
+
+    /// Every date in `from..=to` on which the schedule comes due.
+    pub fn occurrences(&self, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        from.iter_days().take_while(|d| *d <= to).filter(|d| self.occurs_on(*d)).collect()
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap();
+    let next_first = first.checked_add_months(Months::new(1)).unwrap();
+    next_first.signed_duration_since(first).num_days() as u32
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "mon",
+        Weekday::Tue => "tue",
+        Weekday::Wed => "wed",
+        Weekday::Thu => "thu",
+        Weekday::Fri => "fri",
+        Weekday::Sat => "sat",
+        Weekday::Sun => "sun",
+    }
+}
+
+/// Schedules are written as `daily`, `weekly:mon`, `monthly:15`, `nth:2:tue` or `last:fri`.
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowered = s.trim().to_lowercase();
+        let parts: Vec<&str> = lowered.split(':').map(str::trim).collect();
+        let weekday = |text: &str| text.parse::<Weekday>().map_err(|_| format!("invalid weekday '{}'", text));
+        match parts.as_slice() {
+            ["daily"] => Ok(Schedule::Daily),
+            ["weekly", day] => Ok(Schedule::Weekly(weekday(day)?)),
+            ["monthly", day] => match day.parse::<u32>() {
+                Ok(day) if (1..=31).contains(&day) => Ok(Schedule::Monthly(day)),
+                _ => Err(format!("invalid day of month '{}'", day)),
+            },
+            ["nth", n, day] => match n.parse::<u32>() {
+                Ok(n) if (1..=4).contains(&n) => Ok(Schedule::NthWeekday(n, weekday(day)?)),
+                _ => Err(format!("invalid week of month '{}'", n)),
+            },
+            ["last", day] => Ok(Schedule::LastWeekday(weekday(day)?)),
+            _ => Err(format!("unknown schedule '{}'", s)),
+        }
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::Daily => write!(f, "daily"),
+            Schedule::Weekly(weekday) => write!(f, "weekly:{}", weekday_name(*weekday)),
+            Schedule::Monthly(day) => write!(f, "monthly:{}", day),
+            Schedule::NthWeekday(n, weekday) => write!(f, "nth:{}:{}", n, weekday_name(*weekday)),
+            Schedule::LastWeekday(weekday) => write!(f, "last:{}", weekday_name(*weekday)),
+        }
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct RecurringPurchase {
+    pub id: i64,
+    pub account_id: i64,
+    /// Amount in cents, positive values are money leaving the account
+    pub amount: i64,
+    pub payee: String,
+    pub category: String,
+    pub schedule: Schedule,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    /// Latest date that has already been turned into a purchase
+    pub last_materialized: Option<NaiveDate>
+}
+
+impl RecurringPurchase {
+    /// Dates that are due up to and including `today` and have not yet been materialized.
+    pub fn due_dates(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        let from = match self.last_materialized {
+            Some(last) => self.start_date.max(last.succ_opt().unwrap()),
+            None => self.start_date,
+        };
+        let to = match self.end_date {
+            Some(end) => end.min(today),
+            None => today,
+        };
+        if from > to {
+            return Vec::new();
+        }
+        self.schedule.occurrences(from, to)
+    }
+
+    /// The first date after `today` on which this purchase comes due, looking up to a year ahead.
+    pub fn next_due(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let from = self.start_date.max(today.succ_opt()?);
+        let to = from.checked_add_months(Months::new(12))?;
+        let to = self.end_date.map_or(to, |end| end.min(to));
+        self.schedule.occurrences(from, to).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn purchase(schedule: &str, start_date: NaiveDate) -> RecurringPurchase {
+        RecurringPurchase {
+            id: 1,
+            account_id: 1,
+            amount: 1000,
+            payee: "Rent".to_string(),
+            category: "Housing".to_string(),
+            schedule: schedule.parse().unwrap(),
+            start_date,
+            end_date: None,
+            last_materialized: None,
+        }
+    }
+
+    #[test]
+    fn monthly_clamps_to_the_end_of_short_months() {
+        let schedule = Schedule::Monthly(31);
+        assert_eq!(schedule.occurrences(date(2023, 1, 1), date(2023, 4, 30)), vec![date(2023, 1, 31), date(2023, 2, 28), date(2023, 3, 31), date(2023, 4, 30)]);
+        assert_eq!(schedule.occurrences(date(2024, 2, 1), date(2024, 2, 29)), vec![date(2024, 2, 29)]);
+        assert!(!schedule.occurs_on(date(2024, 2, 28)));
+    }
+
+    #[test]
+    fn nth_weekday() {
+        // March 2024 has five Fridays, the 1st, 8th, 15th, 22nd and 29th
+        let schedule = Schedule::NthWeekday(4, Weekday::Fri);
+        assert_eq!(schedule.occurrences(date(2024, 3, 1), date(2024, 3, 31)), vec![date(2024, 3, 22)]);
+        assert_eq!(Schedule::NthWeekday(1, Weekday::Fri).occurrences(date(2024, 3, 1), date(2024, 3, 31)), vec![date(2024, 3, 1)]);
+    }
+
+    #[test]
+    fn last_weekday() {
+        let schedule = Schedule::LastWeekday(Weekday::Fri);
+        assert_eq!(schedule.occurrences(date(2024, 3, 1), date(2024, 3, 31)), vec![date(2024, 3, 29)]);
+        // February 2024 has four Fridays, the last one is also the fourth
+        assert_eq!(schedule.occurrences(date(2024, 2, 1), date(2024, 2, 29)), vec![date(2024, 2, 23)]);
+        assert!(Schedule::NthWeekday(4, Weekday::Fri).occurs_on(date(2024, 2, 23)));
+    }
+
+    #[test]
+    fn weekly_and_daily() {
+        assert_eq!(Schedule::Weekly(Weekday::Mon).occurrences(date(2024, 3, 1), date(2024, 3, 14)), vec![date(2024, 3, 4), date(2024, 3, 11)]);
+        assert_eq!(Schedule::Daily.occurrences(date(2024, 2, 28), date(2024, 3, 1)).len(), 3);
+    }
+
+    #[test]
+    fn parsing_round_trips_through_display() {
+        for text in ["daily", "weekly:mon", "monthly:31", "nth:4:tue", "last:sun"] {
+            let schedule: Schedule = text.parse().unwrap();
+            assert_eq!(schedule.to_string(), text);
+            assert_eq!(schedule.to_string().parse::<Schedule>().unwrap(), schedule);
+        }
+        assert_eq!(" Weekly : FRI ".parse::<Schedule>().unwrap(), Schedule::Weekly(Weekday::Fri));
+    }
+
+    #[test]
+    fn parsing_rejects_out_of_range_values() {
+        for text in ["monthly:0", "monthly:32", "nth:0:mon", "nth:5:mon", "weekly:someday", "yearly", ""] {
+            assert!(text.parse::<Schedule>().is_err(), "{} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn due_dates_resume_after_the_last_materialized_date() {
+        let mut recurring = purchase("monthly:15", date(2024, 1, 1));
+        recurring.last_materialized = Some(date(2024, 2, 15));
+        assert_eq!(recurring.due_dates(date(2024, 4, 20)), vec![date(2024, 3, 15), date(2024, 4, 15)]);
+
+        // Materializing those dates leaves nothing for the next run
+        recurring.last_materialized = Some(date(2024, 4, 15));
+        assert!(recurring.due_dates(date(2024, 4, 20)).is_empty());
+    }
+
+    #[test]
+    fn due_dates_stop_at_the_end_date() {
+        let mut recurring = purchase("weekly:mon", date(2024, 3, 1));
+        recurring.end_date = Some(date(2024, 3, 18));
+        assert_eq!(recurring.due_dates(date(2024, 4, 30)), vec![date(2024, 3, 4), date(2024, 3, 11), date(2024, 3, 18)]);
+
+        recurring.last_materialized = Some(date(2024, 3, 18));
+        assert!(recurring.due_dates(date(2024, 4, 30)).is_empty());
+        assert_eq!(recurring.next_due(date(2024, 4, 30)), None);
+    }
+
+    #[test]
+    fn nothing_is_due_before_the_start_date() {
+        let recurring = purchase("daily", date(2024, 5, 1));
+        assert!(recurring.due_dates(date(2024, 4, 30)).is_empty());
+        assert_eq!(recurring.next_due(date(2024, 4, 20)), Some(date(2024, 5, 1)));
+    }
+}
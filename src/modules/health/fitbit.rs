@@ -0,0 +1,315 @@
+use std::{fs, path::Path};
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::api::{NewSleep, NewWeight};
+
+use super::{sleep::{night_of, SleepLog}, WeightLog, WeightUnit};
+
+/// A weight reading as exported by Fitbit.
+#[derive(Clone)]
+pub struct FitbitWeight {
+    pub log_id: i64,
+    pub date: NaiveDate,
+    pub weight: f64,
+    pub unit: WeightUnit
+}
+
+/// A sleep log as exported by Fitbit.
+#[derive(Clone)]
+pub struct FitbitSleep {
+    pub log_id: i64,
+    pub bed_time: NaiveDateTime,
+    pub wake_time: NaiveDateTime,
+    /// Fitbit's sleep efficiency, used as the quality score
+    pub quality: Option<u8>
+}
+
+impl FitbitWeight {
+    pub fn request(&self) -> NewWeight {
+        NewWeight { date: self.date, weight: self.weight, unit: self.unit, fitbit_log_id: Some(self.log_id) }
+    }
+}
+
+impl FitbitSleep {
+    pub fn request(&self) -> NewSleep {
+        NewSleep { bed_time: self.bed_time, wake_time: self.wake_time, quality: self.quality, fitbit_log_id: Some(self.log_id) }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FitbitImport {
+    pub weights: Vec<FitbitWeight>,
+    pub sleeps: Vec<FitbitSleep>,
+    /// Files that could not be read, shown alongside the preview
+    pub errors: Vec<String>
+}
+
+/// Weight entry as it appears in both the export files and the Web API.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct WeightRecord {
+    log_id: i64,
+    weight: f64,
+    date: String
+}
+
+/// Sleep entry as it appears in both the export files and the Web API.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct SleepRecord {
+    log_id: i64,
+    start_time: String,
+    end_time: String,
+    efficiency: Option<u8>
+}
+
+impl FitbitImport {
+    /// Reads every `weight-*.json/csv` and `sleep-*.json/csv` file in `directory`.
+    ///
+    /// Fitbit exports weights in the unit set on the account, so that unit has to be supplied.
+    pub fn from_directory(directory: &Path, unit: WeightUnit) -> Result<Self, String> {
+        let entries = fs::read_dir(directory).map_err(|e| format!("Could not read {}: {}", directory.display(), e))?;
+
+        let mut import = FitbitImport::default();
+        let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_file()).collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            let contents = || fs::read_to_string(&path).map_err(|e| e.to_string());
+
+            let result = match (file_name.starts_with("weight-"), file_name.starts_with("sleep-"), extension) {
+                (true, _, "json") => contents().and_then(|c| parse_weight_json(&c, unit)).map(|w| import.weights.extend(w)),
+                (true, _, "csv") => contents().and_then(|c| parse_weight_csv(&c, unit)).map(|w| import.weights.extend(w)),
+                (_, true, "json") => contents().and_then(|c| parse_sleep_json(&c)).map(|s| import.sleeps.extend(s)),
+                (_, true, "csv") => contents().and_then(|c| parse_sleep_csv(&c)).map(|s| import.sleeps.extend(s)),
+                _ => continue,
+            };
+            if let Err(error) = result {
+                import.errors.push(format!("{}: {}", file_name, error));
+            }
+        }
+
+        import.weights.sort_by_key(|w| (w.date, w.log_id));
+        import.weights.dedup_by_key(|w| w.log_id);
+        import.sleeps.sort_by_key(|s| (s.bed_time, s.log_id));
+        import.sleeps.dedup_by_key(|s| s.log_id);
+        Ok(import)
+    }
+
+    /// Drops anything already stored on the server, matching on the Fitbit log id or, for entries
+    /// logged by hand, on the day.
+    pub fn without_existing(mut self, weights: &[WeightLog], sleeps: &[SleepLog]) -> Self {
+        self.weights.retain(|w| !weights.iter().any(|e| e.fitbit_log_id == Some(w.log_id) || e.date == w.date));
+        self.sleeps.retain(|s| !sleeps.iter().any(|e| e.fitbit_log_id == Some(s.log_id) || e.night() == night_of(s.bed_time)));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty() && self.sleeps.is_empty()
+    }
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text, "%m/%d/%y")
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d"))
+        .map_err(|_| format!("invalid date '{}'", text))
+}
+
+fn parse_date_time(text: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S"))
+        .map_err(|_| format!("invalid time '{}'", text))
+}
+
+pub(super) fn weight_from_record(record: WeightRecord, unit: WeightUnit) -> Result<FitbitWeight, String> {
+    Ok(FitbitWeight { log_id: record.log_id, date: parse_date(&record.date)?, weight: record.weight, unit })
+}
+
+pub(super) fn sleep_from_record(record: SleepRecord) -> Result<FitbitSleep, String> {
+    Ok(FitbitSleep {
+        log_id: record.log_id,
+        bed_time: parse_date_time(&record.start_time)?,
+        wake_time: parse_date_time(&record.end_time)?,
+        quality: record.efficiency.map(|e| e.min(100)),
+    })
+}
+
+fn parse_weight_json(contents: &str, unit: WeightUnit) -> Result<Vec<FitbitWeight>, String> {
+    let records: Vec<WeightRecord> = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    records.into_iter().map(|r| weight_from_record(r, unit)).collect()
+}
+
+fn parse_sleep_json(contents: &str) -> Result<Vec<FitbitSleep>, String> {
+    let records: Vec<SleepRecord> = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    records.into_iter().map(sleep_from_record).collect()
+}
+
+/// Splits a CSV line into its fields. Fields can be quoted to hold commas, with `""` standing for
+/// a quote; line breaks inside a field are not supported.
+fn csv_fields(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| *c == ' ').is_some() {}
+        let mut field = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quote".to_string()),
+                }
+            }
+            while chars.next_if(|c| *c == ' ').is_some() {}
+            if chars.peek().is_some_and(|c| *c != ',') {
+                return Err("unexpected text after a quoted field".to_string());
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                if c == '"' {
+                    return Err("unexpected quote".to_string());
+                }
+                field.push(c);
+            }
+            field.truncate(field.trim_end().len());
+        }
+        fields.push(field);
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+/// Splits a CSV file into rows keyed by its header.
+fn csv_rows(contents: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let mut lines = contents.trim_start_matches('\u{feff}').lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    let (header_index, header) = lines.next().ok_or("empty file")?;
+    let header = csv_fields(header).map_err(|e| format!("line {}: {}", header_index + 1, e))?;
+    lines.map(|(index, line)| {
+        let fields = csv_fields(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        if fields.len() != header.len() {
+            return Err(format!("line {}: {} fields where the header has {}", index + 1, fields.len(), header.len()));
+        }
+        Ok(header.iter().cloned().zip(fields).collect())
+    }).collect()
+}
+
+fn csv_field<'a>(row: &'a [(String, String)], name: &str) -> Result<&'a str, String> {
+    row.iter().find(|(h, _)| h == name).map(|(_, v)| v.as_str()).ok_or(format!("missing column '{}'", name))
+}
+
+fn parse_weight_csv(contents: &str, unit: WeightUnit) -> Result<Vec<FitbitWeight>, String> {
+    csv_rows(contents)?.iter().map(|row| {
+        let record = WeightRecord {
+            log_id: csv_field(row, "logId")?.parse().map_err(|_| "invalid logId".to_string())?,
+            weight: csv_field(row, "weight")?.parse().map_err(|_| "invalid weight".to_string())?,
+            date: csv_field(row, "date")?.to_string(),
+        };
+        weight_from_record(record, unit)
+    }).collect()
+}
+
+fn parse_sleep_csv(contents: &str) -> Result<Vec<FitbitSleep>, String> {
+    csv_rows(contents)?.iter().map(|row| {
+        let record = SleepRecord {
+            log_id: csv_field(row, "logId")?.parse().map_err(|_| "invalid logId".to_string())?,
+            start_time: csv_field(row, "startTime")?.to_string(),
+            end_time: csv_field(row, "endTime")?.to_string(),
+            efficiency: csv_field(row, "efficiency").ok().and_then(|e| e.parse().ok()),
+        };
+        sleep_from_record(record)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fitbit-export")
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn export_directory_is_read() {
+        let import = FitbitImport::from_directory(&fixtures(), WeightUnit::Lb).unwrap();
+
+        // The weight logged in both the JSON and the CSV file is only imported once
+        let weights: Vec<(i64, NaiveDate, f64)> = import.weights.iter().map(|w| (w.log_id, w.date, w.weight)).collect();
+        assert_eq!(weights, vec![
+            (1704182400000, date("2024-01-02"), 176.4),
+            (1704441600000, date("2024-01-05"), 175.9),
+            (1706774400000, date("2024-02-01"), 175.2),
+        ]);
+        assert!(import.weights.iter().all(|w| w.unit == WeightUnit::Lb));
+
+        let sleeps: Vec<(i64, NaiveDateTime, Option<u8>)> = import.sleeps.iter().map(|s| (s.log_id, s.bed_time, s.quality)).collect();
+        assert_eq!(sleeps, vec![
+            (40001, time("2024-01-01 23:10") + chrono::Duration::seconds(30), Some(93)),
+            (40002, time("2024-01-03 00:45"), Some(100)),
+            (40003, time("2024-02-01 22:30"), None),
+        ]);
+        assert_eq!(import.sleeps[2].wake_time, time("2024-02-02 06:45"));
+
+        // Files that can't be read are reported and the rest is still imported
+        assert_eq!(import.errors.len(), 2, "{:?}", import.errors);
+        assert!(import.errors[0].starts_with("sleep-2024-03-01.csv: invalid time"), "{}", import.errors[0]);
+        assert!(import.errors[1].starts_with("weight-2024-03-01.json: "), "{}", import.errors[1]);
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        assert!(FitbitImport::from_directory(&fixtures().join("missing"), WeightUnit::Kg).is_err());
+    }
+
+    #[test]
+    fn csv_rows_are_keyed_by_the_header() {
+        let rows = csv_rows("\u{feff}logId,source\r\n\r\n1, Aria \r\n2,\"Aria, kitchen\"\n3,\"The \"\"old\"\" scale\"\n4,\n").unwrap();
+        let sources: Vec<(&str, &str)> = rows.iter().map(|row| (csv_field(row, "logId").unwrap(), csv_field(row, "source").unwrap())).collect();
+        assert_eq!(sources, vec![("1", "Aria"), ("2", "Aria, kitchen"), ("3", "The \"old\" scale"), ("4", "")]);
+        assert_eq!(csv_field(&rows[0], "weight"), Err("missing column 'weight'".to_string()));
+    }
+
+    #[test]
+    fn malformed_csv_is_rejected() {
+        assert_eq!(csv_rows(""), Err("empty file".to_string()));
+        assert_eq!(csv_rows("logId,source\n1,\"Aria\n"), Err("line 2: unterminated quote".to_string()));
+        assert_eq!(csv_rows("logId,source\n1,\"Aria\" scale\n"), Err("line 2: unexpected text after a quoted field".to_string()));
+        assert_eq!(csv_rows("logId,source\n1,Aria \"2\"\n"), Err("line 2: unexpected quote".to_string()));
+        assert_eq!(csv_rows("logId,source\n1,Aria,kitchen\n"), Err("line 2: 3 fields where the header has 2".to_string()));
+    }
+
+    #[test]
+    fn existing_entries_are_left_out() {
+        let import = FitbitImport::from_directory(&fixtures(), WeightUnit::Kg).unwrap();
+        let weights = [
+            // Synced before, since moved to another day
+            WeightLog { id: 1, date: date("2023-12-31"), weight: 80.0, unit: WeightUnit::Kg, fitbit_log_id: Some(1704182400000) },
+            // Logged by hand on a day the export also has
+            WeightLog { id: 2, date: date("2024-02-01"), weight: 79.0, unit: WeightUnit::Kg, fitbit_log_id: None },
+        ];
+        let sleeps = [
+            SleepLog { id: 1, bed_time: time("2024-01-03 01:00"), wake_time: time("2024-01-03 06:00"), quality: None, fitbit_log_id: Some(40002) },
+            // After midnight, so it still counts as the night of the 1st
+            SleepLog { id: 2, bed_time: time("2024-02-02 01:00"), wake_time: time("2024-02-02 07:00"), quality: None, fitbit_log_id: None },
+        ];
+
+        let import = import.without_existing(&weights, &sleeps);
+        assert_eq!(import.weights.iter().map(|w| w.log_id).collect::<Vec<_>>(), vec![1704441600000]);
+        assert_eq!(import.sleeps.iter().map(|s| s.log_id).collect::<Vec<_>>(), vec![40001]);
+        assert!(!import.is_empty());
+    }
+}
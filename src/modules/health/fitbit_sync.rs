@@ -0,0 +1,398 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+use crate::config::config::FitbitConfig;
+
+use super::{fitbit::{sleep_from_record, weight_from_record, FitbitImport, SleepRecord, WeightRecord}, WeightUnit};
+
+/// Longest date range the weight log endpoint accepts in one request.
+const MAX_RANGE_DAYS: u64 = 31;
+/// How far back the first sync reaches when nothing has been pulled before.
+const INITIAL_SYNC_DAYS: u64 = 90;
+
+/// A user's Fitbit tokens, stored on the server so every client shares the same login.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct FitbitAccount {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Last day pulled from Fitbit, the next sync starts again from this day
+    pub last_synced: Option<NaiveDate>
+}
+
+impl FitbitAccount {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now() + TimeDelta::minutes(1)
+    }
+
+    pub fn sync_start(&self, today: NaiveDate) -> NaiveDate {
+        self.last_synced.unwrap_or(today.checked_sub_days(Days::new(INITIAL_SYNC_DAYS)).unwrap_or(today))
+    }
+}
+
+/// An authorization in progress, kept until the user pastes back the code Fitbit redirected with.
+#[derive(Clone)]
+pub struct FitbitLogin {
+    pub authorization_url: String,
+    verifier: String,
+    state: String
+}
+
+impl FitbitLogin {
+    /// Accepts either the bare code or the whole URL the browser was redirected to.
+    pub fn code_from_redirect(&self, pasted: &str) -> Result<String, String> {
+        let pasted = pasted.trim();
+        let Ok(url) = Url::parse(pasted) else {
+            return Ok(pasted.to_string());
+        };
+
+        let mut code = None;
+        let mut state = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "code" => code = Some(value.to_string()),
+                "state" => state = Some(value.to_string()),
+                "error" => return Err(format!("Fitbit refused the authorization: {}", value)),
+                _ => {}
+            }
+        }
+        // The state ties the redirect to this login attempt, without it the code could come from
+        // a login someone else started
+        match state {
+            Some(state) if state == self.state => {},
+            Some(_) => return Err("The pasted URL belongs to a different login attempt".to_string()),
+            None => return Err("The pasted URL does not contain a state, paste the whole URL Fitbit redirected to or just the code".to_string()),
+        }
+        code.ok_or("The pasted URL does not contain a code".to_string())
+    }
+}
+
+/// Why pulling logs from Fitbit failed.
+#[derive(Debug, PartialEq)]
+pub enum FetchError {
+    /// Fitbit turned the access token down, so the user has to log in again
+    Unauthorized,
+    Failed(String)
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::Unauthorized => write!(f, "Fitbit no longer accepts the saved login"),
+            FetchError::Failed(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    /// Seconds until the access token expires
+    expires_in: i64
+}
+
+#[derive(serde::Deserialize)]
+struct WeightResponse {
+    weight: Vec<WeightRecord>
+}
+
+#[derive(serde::Deserialize)]
+struct SleepResponse {
+    sleep: Vec<SleepRecord>
+}
+
+#[derive(Clone)]
+pub struct FitbitClient {
+    config: FitbitConfig,
+    http_client: reqwest::Client
+}
+
+impl FitbitClient {
+    pub fn new(config: &FitbitConfig, http_client: &reqwest::Client) -> Self {
+        FitbitClient { config: config.clone(), http_client: http_client.clone() }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.config.client_id.trim().is_empty()
+    }
+
+    /// Starts an authorization code flow with a fresh PKCE verifier.
+    pub fn begin_login(&self) -> Result<FitbitLogin, String> {
+        let verifier = random_string(64);
+        let state = random_string(16);
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        let authorization_url = Url::parse_with_params(&self.config.authorize_url, [
+            ("response_type", "code"),
+            ("client_id", self.config.client_id.trim()),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("scope", "weight sleep"),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+            ("state", state.as_str()),
+        ]).map_err(|e| format!("Invalid Fitbit authorize_url: {}", e))?;
+
+        Ok(FitbitLogin { authorization_url: authorization_url.to_string(), verifier, state })
+    }
+
+    pub async fn exchange_code(&self, login: &FitbitLogin, code: &str) -> Result<FitbitAccount, String> {
+        let params = [
+            ("client_id", self.config.client_id.trim()),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("code_verifier", login.verifier.as_str()),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+        ];
+        let token = self.request_token(&params).await?;
+        Ok(account_from_token(token, None))
+    }
+
+    pub async fn refresh(&self, account: &FitbitAccount) -> Result<FitbitAccount, String> {
+        let params = [
+            ("client_id", self.config.client_id.trim()),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", account.refresh_token.as_str()),
+        ];
+        let token = self.request_token(&params).await?;
+        Ok(account_from_token(token, account.last_synced))
+    }
+
+    /// Pulls every weight and sleep log between `from` and `to`, splitting the range into the
+    /// largest windows the API allows. Weights come back in kilograms as no locale is requested.
+    pub async fn fetch(&self, account: &FitbitAccount, from: NaiveDate, to: NaiveDate) -> Result<FitbitImport, FetchError> {
+        let mut import = FitbitImport::default();
+
+        let mut start = from;
+        while start <= to {
+            let end = start.checked_add_days(Days::new(MAX_RANGE_DAYS - 1)).unwrap_or(to).min(to);
+
+            let weights: WeightResponse = self.get(account, &format!("1/user/-/body/log/weight/date/{}/{}.json", start, end)).await?;
+            for record in weights.weight {
+                match weight_from_record(record, WeightUnit::Kg) {
+                    Ok(weight) => import.weights.push(weight),
+                    Err(error) => import.errors.push(format!("Weight {}: {}", start, error)),
+                }
+            }
+
+            let sleeps: SleepResponse = self.get(account, &format!("1.2/user/-/sleep/date/{}/{}.json", start, end)).await?;
+            for record in sleeps.sleep {
+                match sleep_from_record(record) {
+                    Ok(sleep) => import.sleeps.push(sleep),
+                    Err(error) => import.errors.push(format!("Sleep {}: {}", start, error)),
+                }
+            }
+
+            start = match end.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        import.weights.sort_by_key(|w| (w.date, w.log_id));
+        import.weights.dedup_by_key(|w| w.log_id);
+        import.sleeps.sort_by_key(|s| (s.bed_time, s.log_id));
+        import.sleeps.dedup_by_key(|s| s.log_id);
+        Ok(import)
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.api_url.trim_end_matches('/'), path)
+    }
+
+    async fn request_token(&self, params: &[(&str, &str)]) -> Result<TokenResponse, String> {
+        let response = self.http_client.post(self.api_url("oauth2/token")).form(params).send().await.map_err(|e| format!("Could not reach Fitbit: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Fitbit rejected the token request ({})", response.status()));
+        }
+        response.json().await.map_err(|e| format!("Unexpected token response: {}", e))
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, account: &FitbitAccount, path: &str) -> Result<T, FetchError> {
+        let response = self.http_client.get(self.api_url(path)).bearer_auth(&account.access_token).send().await.map_err(|e| FetchError::Failed(format!("Could not reach Fitbit: {}", e)))?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(FetchError::Unauthorized);
+        }
+        if !response.status().is_success() {
+            return Err(FetchError::Failed(format!("Fitbit returned {} for {}", response.status(), path)));
+        }
+        response.json().await.map_err(|e| FetchError::Failed(format!("Unexpected response for {}: {}", path, e)))
+    }
+}
+
+fn account_from_token(token: TokenResponse, last_synced: Option<NaiveDate>) -> FitbitAccount {
+    FitbitAccount {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at: Utc::now() + TimeDelta::seconds(token.expires_in),
+        last_synced,
+    }
+}
+
+fn random_string(length: usize) -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(length).map(char::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+    use axum::{extract::{Path, State}, http::{HeaderMap, StatusCode}, routing::{get, post}, Form, Json, Router};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// What the mock Fitbit saw, and the PKCE challenge it expects the verifier to match.
+    #[derive(Default)]
+    struct MockState {
+        challenge: String,
+        weight_ranges: Vec<(String, String)>,
+        sleep_ranges: Vec<(String, String)>
+    }
+
+    type Mock = Arc<Mutex<MockState>>;
+
+    async fn token(State(mock): State<Mock>, Form(params): Form<HashMap<String, String>>) -> Result<Json<Value>, StatusCode> {
+        let param = |name: &str| params.get(name).map(String::as_str);
+        if param("client_id") != Some("client") {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        match param("grant_type") {
+            Some("authorization_code") => {
+                let verifier = param("code_verifier").ok_or(StatusCode::BAD_REQUEST)?;
+                let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+                if param("code") != Some("the-code") || challenge != mock.lock().unwrap().challenge {
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+                Ok(Json(json!({ "access_token": "access-1", "refresh_token": "refresh-1", "expires_in": 28800 })))
+            },
+            Some("refresh_token") if param("refresh_token") == Some("refresh-1") => {
+                Ok(Json(json!({ "access_token": "access-2", "refresh_token": "refresh-2", "expires_in": 28800 })))
+            },
+            _ => Err(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    fn authorized(headers: &HeaderMap) -> Result<(), StatusCode> {
+        match headers.get("authorization").and_then(|value| value.to_str().ok()) {
+            Some("Bearer access-1") => Ok(()),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+
+    async fn weights(State(mock): State<Mock>, headers: HeaderMap, Path((start, end)): Path<(String, String)>) -> Result<Json<Value>, StatusCode> {
+        authorized(&headers)?;
+        let end = end.trim_end_matches(".json").to_string();
+        let mut mock = mock.lock().unwrap();
+        mock.weight_ranges.push((start.clone(), end));
+        // One reading at the start of every window
+        Ok(Json(json!({ "weight": [{ "logId": mock.weight_ranges.len(), "weight": 80.5, "date": start }] })))
+    }
+
+    async fn sleeps(State(mock): State<Mock>, headers: HeaderMap, Path((start, end)): Path<(String, String)>) -> Result<Json<Value>, StatusCode> {
+        authorized(&headers)?;
+        mock.lock().unwrap().sleep_ranges.push((start, end.trim_end_matches(".json").to_string()));
+        // The same night in every window, as Fitbit returns sleeps that cross a window's edge twice
+        Ok(Json(json!({ "sleep": [{ "logId": 7, "startTime": "2024-01-31T23:30:00.000", "endTime": "2024-02-01T07:00:00.000", "efficiency": 91 }] })))
+    }
+
+    /// Starts a mock Fitbit on a free port and returns a client pointed at it.
+    async fn mock_fitbit() -> (FitbitClient, Mock) {
+        let mock = Mock::default();
+        let app = Router::new()
+            .route("/oauth2/token", post(token))
+            .route("/1/user/-/body/log/weight/date/{start}/{end}", get(weights))
+            .route("/1.2/user/-/sleep/date/{start}/{end}", get(sleeps))
+            .with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = FitbitConfig { client_id: "client".to_string(), api_url: format!("http://{}", address), ..FitbitConfig::default() };
+        (FitbitClient::new(&config, &reqwest::Client::new()), mock)
+    }
+
+    fn account(expires_at: DateTime<Utc>) -> FitbitAccount {
+        FitbitAccount { access_token: "access-1".to_string(), refresh_token: "refresh-1".to_string(), expires_at, last_synced: NaiveDate::from_ymd_opt(2024, 3, 1) }
+    }
+
+    fn query(url: &str, name: &str) -> String {
+        Url::parse(url).unwrap().query_pairs().find(|(key, _)| key == name).unwrap().1.to_string()
+    }
+
+    #[tokio::test]
+    async fn login_exchanges_the_code_with_the_pkce_verifier() {
+        let (client, mock) = mock_fitbit().await;
+        let login = client.begin_login().unwrap();
+        assert_eq!(query(&login.authorization_url, "code_challenge_method"), "S256");
+        mock.lock().unwrap().challenge = query(&login.authorization_url, "code_challenge");
+
+        let redirect = format!("http://127.0.0.1:8080/?code=the-code&state={}", query(&login.authorization_url, "state"));
+        let code = login.code_from_redirect(&redirect).unwrap();
+        let account = client.exchange_code(&login, &code).await.unwrap();
+        assert_eq!(account.access_token, "access-1");
+        assert_eq!(account.refresh_token, "refresh-1");
+        assert!(!account.is_expired());
+
+        // A verifier from another login attempt does not match the challenge
+        let other_login = client.begin_login().unwrap();
+        assert!(client.exchange_code(&other_login, &code).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_are_refreshed() {
+        let (client, _) = mock_fitbit().await;
+        let expired = account(Utc::now() - TimeDelta::hours(1));
+        assert!(expired.is_expired());
+        // Tokens about to run out count as expired too
+        assert!(account(Utc::now() + TimeDelta::seconds(30)).is_expired());
+
+        let refreshed = client.refresh(&expired).await.unwrap();
+        assert_eq!(refreshed.access_token, "access-2");
+        assert_eq!(refreshed.refresh_token, "refresh-2");
+        assert_eq!(refreshed.last_synced, expired.last_synced);
+        assert!(!refreshed.is_expired());
+
+        // The old refresh token is gone once it has been used
+        assert!(client.refresh(&refreshed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_splits_the_range_into_31_day_windows() {
+        let (client, mock) = mock_fitbit().await;
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let import = client.fetch(&account(Utc::now() + TimeDelta::hours(1)), from, to).await.unwrap();
+
+        let expected = [("2024-01-01", "2024-01-31"), ("2024-02-01", "2024-03-02"), ("2024-03-03", "2024-03-15")].map(|(start, end)| (start.to_string(), end.to_string()));
+        let mock = mock.lock().unwrap();
+        assert_eq!(mock.weight_ranges, expected);
+        assert_eq!(mock.sleep_ranges, expected);
+
+        assert_eq!(import.weights.iter().map(|w| w.date.to_string()).collect::<Vec<_>>(), ["2024-01-01", "2024-02-01", "2024-03-03"]);
+        assert_eq!(import.sleeps.len(), 1);
+        assert!(import.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetch_fails_with_a_rejected_token() {
+        let (client, _) = mock_fitbit().await;
+        let mut account = account(Utc::now() + TimeDelta::hours(1));
+        account.access_token = "revoked".to_string();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        assert_eq!(client.fetch(&account, day, day).await.err(), Some(FetchError::Unauthorized));
+    }
+
+    #[test]
+    fn redirect_urls_need_the_login_state() {
+        let login = FitbitLogin { authorization_url: String::new(), verifier: String::new(), state: "expected".to_string() };
+        assert_eq!(login.code_from_redirect(" bare-code "), Ok("bare-code".to_string()));
+        assert_eq!(login.code_from_redirect("http://127.0.0.1:8080/?code=abc&state=expected"), Ok("abc".to_string()));
+        assert!(login.code_from_redirect("http://127.0.0.1:8080/?code=abc").is_err());
+        assert!(login.code_from_redirect("http://127.0.0.1:8080/?code=abc&state=other").is_err());
+        assert!(login.code_from_redirect("http://127.0.0.1:8080/?error=access_denied&state=expected").is_err());
+    }
+}
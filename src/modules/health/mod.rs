@@ -0,0 +1,1399 @@
+use std::path::Path;
+
+use chrono::{Days, Local, NaiveDate, NaiveDateTime};
+use crossterm::event::KeyCode;
+use fitbit::FitbitImport;
+use fitbit_sync::{FetchError, FitbitAccount, FitbitClient, FitbitLogin};
+use ratatui::{layout::{Constraint, Layout, Rect}, style::{Color, Style, Stylize}, symbols, text, widgets::{Axis, Bar, BarChart, BarGroup, Block, Chart, Clear, Dataset, GraphType, Paragraph, Row, Table, TableState, Tabs, Wrap}};
+use sleep::{format_duration, night_of, parse_clock_time, sleep_period, SleepLog, SleepStats};
+
+use crate::{api::{ApiClient, ApiResult, NewCalories, NewSleep, NewWeight}, app::App, modules::TextInput};
+
+use super::{input_functions, rendering::{self, single_line_input}, selection::SelectionModules, RenderableModule, SanitizedForm, SelectedModule};
+
+pub mod fitbit;
+pub mod fitbit_sync;
+pub mod sleep;
+
+pub struct HealthModule {}
+
+/// Where a Fitbit sync ended up, applied once the background request finishes.
+enum FitbitSync {
+    /// The server has no usable tokens, with the reason when refreshing them failed
+    NeedsLogin(Option<String>),
+    /// Fitbit did not accept the pasted code
+    LoginFailed(String),
+    /// The tokens were refreshed but the server did not take them, the old refresh token is spent
+    /// so the new one has to be kept until it is saved
+    Unsaved { user_id: i64, account: FitbitAccount, error: String },
+    Fetched { account: FitbitAccount, import: Result<FitbitImport, String>, today: NaiveDate }
+}
+
+pub enum HealthModules {
+    WeightList,
+    WeightEditing(WeightInput),
+    CaloriesEditing(CaloriesInput),
+    SleepList,
+    SleepEditing(SleepInput),
+    ImportSetup(ImportInput),
+    ImportPreview,
+    FitbitAuthorizing
+}
+
+#[derive(Clone, Default)]
+pub enum WeightInput {
+    Date,
+    #[default]
+    Weight,
+    Unit
+}
+
+impl WeightInput {
+    fn next(self) -> Self {
+        match self {
+            WeightInput::Date => WeightInput::Weight,
+            WeightInput::Weight => WeightInput::Unit,
+            WeightInput::Unit => WeightInput::Date,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            WeightInput::Date => WeightInput::Unit,
+            WeightInput::Weight => WeightInput::Date,
+            WeightInput::Unit => WeightInput::Weight,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum CaloriesInput {
+    Date,
+    #[default]
+    Calories
+}
+
+impl CaloriesInput {
+    fn next(self) -> Self {
+        match self {
+            CaloriesInput::Date => CaloriesInput::Calories,
+            CaloriesInput::Calories => CaloriesInput::Date,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            CaloriesInput::Date => CaloriesInput::Calories,
+            CaloriesInput::Calories => CaloriesInput::Date,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum SleepInput {
+    Night,
+    #[default]
+    BedTime,
+    WakeTime,
+    Quality
+}
+
+impl SleepInput {
+    fn next(self) -> Self {
+        match self {
+            SleepInput::Night => SleepInput::BedTime,
+            SleepInput::BedTime => SleepInput::WakeTime,
+            SleepInput::WakeTime => SleepInput::Quality,
+            SleepInput::Quality => SleepInput::Night,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            SleepInput::Night => SleepInput::Quality,
+            SleepInput::BedTime => SleepInput::Night,
+            SleepInput::WakeTime => SleepInput::BedTime,
+            SleepInput::Quality => SleepInput::WakeTime,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum ImportInput {
+    #[default]
+    Directory,
+    Unit
+}
+
+impl ImportInput {
+    fn next(self) -> Self {
+        match self {
+            ImportInput::Directory => ImportInput::Unit,
+            ImportInput::Unit => ImportInput::Directory,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            ImportInput::Directory => ImportInput::Unit,
+            ImportInput::Unit => ImportInput::Directory,
+        }
+    }
+}
+
+/// Number of days shown in the charts, ending today.
+#[derive(Clone, Copy, Default)]
+pub enum ChartWindow {
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    Year
+}
+
+impl ChartWindow {
+    fn next(self) -> Self {
+        match self {
+            ChartWindow::Week => ChartWindow::Month,
+            ChartWindow::Month => ChartWindow::Quarter,
+            ChartWindow::Quarter => ChartWindow::Year,
+            ChartWindow::Year => ChartWindow::Week,
+        }
+    }
+
+    fn days(&self) -> u64 {
+        match self {
+            ChartWindow::Week => 7,
+            ChartWindow::Month => 30,
+            ChartWindow::Quarter => 90,
+            ChartWindow::Year => 365,
+        }
+    }
+
+    fn start(&self, today: NaiveDate) -> NaiveDate {
+        today.checked_sub_days(Days::new(self.days() - 1)).unwrap_or(today)
+    }
+}
+
+#[derive(Default)]
+pub struct HealthModuleData {
+    weight_list: Vec<WeightLog>,
+    calories_list: Vec<CaloriesLog>,
+    sleep_list: Vec<SleepLog>,
+    day_table_state: TableState,
+    sleep_table_state: TableState,
+    chart_window: ChartWindow,
+    editable_weight_data: WeightEntry,
+    editable_calories_data: CaloriesEntry,
+    editable_sleep_data: SleepEntry,
+    editable_import_data: ImportEntry,
+    fitbit_import: FitbitImport,
+    import_error: Option<String>,
+    fitbit_account: Option<FitbitAccount>,
+    /// Refreshed tokens the server has not stored yet, and the user they belong to
+    unsaved_fitbit_account: Option<(i64, FitbitAccount)>,
+    fitbit_login: Option<FitbitLogin>,
+    fitbit_code: TextInput,
+    /// Day the pending import was synced up to, saved once the entries are uploaded
+    fitbit_synced_through: Option<NaiveDate>
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct WeightLog {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub weight: f64,
+    pub unit: WeightUnit,
+    #[serde(default)]
+    pub fitbit_log_id: Option<i64>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeightUnit {
+    Kg,
+    Lb
+}
+
+impl WeightUnit {
+    const LB_PER_KG: f64 = 2.204_622_621_8;
+
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "kg" | "kgs" => Some(WeightUnit::Kg),
+            "lb" | "lbs" => Some(WeightUnit::Lb),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            WeightUnit::Kg => "kg",
+            WeightUnit::Lb => "lb",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct CaloriesLog {
+    pub date: NaiveDate,
+    /// Total calories burned over the day
+    pub calories: u32
+}
+
+/// A single row of the day-indexed health table.
+struct DayRow<'a> {
+    date: NaiveDate,
+    weight: Option<&'a WeightLog>,
+    calories: Option<u32>
+}
+
+/// Weight trend and calories burned over the chart window.
+struct WindowSummary {
+    /// Change of the 7-day average between the first and last weigh-in, none with fewer than two days
+    weight_change: Option<f64>,
+    total_burned: u64,
+    logged_days: usize,
+    average_burned: u64
+}
+
+impl WeightLog {
+    fn weight_in(&self, unit: WeightUnit) -> f64 {
+        match (self.unit, unit) {
+            (WeightUnit::Kg, WeightUnit::Lb) => self.weight * WeightUnit::LB_PER_KG,
+            (WeightUnit::Lb, WeightUnit::Kg) => self.weight / WeightUnit::LB_PER_KG,
+            _ => self.weight,
+        }
+    }
+}
+
+#[derive(Default)]
+struct WeightEntry {
+    date: TextInput,
+    weight: TextInput,
+    unit: TextInput
+}
+
+impl WeightEntry {
+    fn new(unit: WeightUnit) -> Self {
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+        let mut entry = Self {
+            date: TextInput::new(today),
+            unit: TextInput::new(unit.name().to_string()),
+            ..Default::default()
+        };
+        entry.date.cursor_to_end();
+        entry.unit.cursor_to_end();
+        entry
+    }
+
+    fn request(&self) -> Option<NewWeight> {
+        Some(NewWeight {
+            date: NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").ok()?,
+            weight: self.weight.text.trim().parse().ok()?,
+            unit: WeightUnit::parse(&self.unit.text)?,
+            fitbit_log_id: None,
+        })
+    }
+}
+
+impl SanitizedForm for WeightEntry {
+    fn verify_input(&self) -> bool {
+        self.date.valid && self.weight.valid && self.unit.valid
+    }
+
+    fn update_validity(&mut self) {
+        self.date.valid = NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").is_ok();
+        self.weight.valid = self.weight.text.trim().parse::<f64>().is_ok_and(|w| w > 0.0 && w.is_finite());
+        self.unit.valid = WeightUnit::parse(&self.unit.text).is_some();
+    }
+
+    fn clear_form(&mut self) {
+        let unit = WeightUnit::parse(&self.unit.text).unwrap_or(WeightUnit::Kg);
+        *self = Self::new(unit);
+    }
+}
+
+#[derive(Default)]
+struct CaloriesEntry {
+    date: TextInput,
+    calories: TextInput
+}
+
+impl CaloriesEntry {
+    fn new() -> Self {
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+        let mut entry = Self {
+            date: TextInput::new(today),
+            ..Default::default()
+        };
+        entry.date.cursor_to_end();
+        entry
+    }
+
+    fn request(&self) -> Option<NewCalories> {
+        Some(NewCalories {
+            date: NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").ok()?,
+            calories: self.calories.text.trim().parse().ok()?,
+        })
+    }
+}
+
+impl SanitizedForm for CaloriesEntry {
+    fn verify_input(&self) -> bool {
+        self.date.valid && self.calories.valid
+    }
+
+    fn update_validity(&mut self) {
+        self.date.valid = NaiveDate::parse_from_str(&self.date.text, "%Y-%m-%d").is_ok();
+        self.calories.valid = self.calories.text.trim().parse::<u32>().is_ok();
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[derive(Default)]
+struct SleepEntry {
+    night: TextInput,
+    bed_time: TextInput,
+    wake_time: TextInput,
+    quality: TextInput
+}
+
+impl SleepEntry {
+    fn new() -> Self {
+        // Default to last night
+        let night = Local::now().date_naive().pred_opt().unwrap().format("%Y-%m-%d").to_string();
+        let mut entry = Self {
+            night: TextInput::new(night),
+            ..Default::default()
+        };
+        entry.night.cursor_to_end();
+        entry
+    }
+
+    fn period(&self) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let night = NaiveDate::parse_from_str(&self.night.text, "%Y-%m-%d").ok()?;
+        sleep_period(night, parse_clock_time(&self.bed_time.text)?, parse_clock_time(&self.wake_time.text)?)
+    }
+
+    fn request(&self) -> Option<NewSleep> {
+        let (bed_time, wake_time) = self.period()?;
+        Some(NewSleep { bed_time, wake_time, quality: self.quality.text.trim().parse().ok(), fitbit_log_id: None })
+    }
+}
+
+impl SanitizedForm for SleepEntry {
+    fn verify_input(&self) -> bool {
+        self.night.valid && self.bed_time.valid && self.wake_time.valid && self.quality.valid
+    }
+
+    fn update_validity(&mut self) {
+        self.night.valid = NaiveDate::parse_from_str(&self.night.text, "%Y-%m-%d").is_ok();
+        self.bed_time.valid = parse_clock_time(&self.bed_time.text).is_some();
+        // A wake time may fall on the following day but the whole sleep must be a sensible length
+        self.wake_time.valid = parse_clock_time(&self.wake_time.text).is_some() && (!self.night.valid || !self.bed_time.valid || self.period().is_some());
+        self.quality.valid = self.quality.text.trim().is_empty() || self.quality.text.trim().parse::<u8>().is_ok_and(|q| q <= 100);
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[derive(Default)]
+struct ImportEntry {
+    directory: TextInput,
+    unit: TextInput
+}
+
+impl ImportEntry {
+    fn new(unit: WeightUnit) -> Self {
+        let mut entry = Self {
+            unit: TextInput::new(unit.name().to_string()),
+            ..Default::default()
+        };
+        entry.unit.cursor_to_end();
+        entry
+    }
+}
+
+impl SanitizedForm for ImportEntry {
+    fn verify_input(&self) -> bool {
+        self.directory.valid && self.unit.valid
+    }
+
+    fn update_validity(&mut self) {
+        self.directory.valid = Path::new(self.directory.text.trim()).is_dir();
+        self.unit.valid = WeightUnit::parse(&self.unit.text).is_some();
+    }
+
+    fn clear_form(&mut self) {
+        let unit = WeightUnit::parse(&self.unit.text).unwrap_or(WeightUnit::Kg);
+        *self = Self::new(unit);
+    }
+}
+
+impl HealthModuleData {
+    fn set_logs(&mut self, weights: Vec<WeightLog>, calories: Vec<CaloriesLog>, sleeps: Vec<SleepLog>) {
+        self.weight_list = weights;
+        self.weight_list.sort_by(|a, b| b.date.cmp(&a.date).then(b.id.cmp(&a.id)));
+        self.calories_list = calories;
+        self.sleep_list = sleeps;
+        self.sleep_list.sort_by(|a, b| b.bed_time.cmp(&a.bed_time).then(b.id.cmp(&a.id)));
+
+        // Keep the selection inside the bounds of the new list
+        let day_count = self.day_rows().len();
+        if day_count == 0 {
+            self.day_table_state.select(None);
+        } else if self.day_table_state.selected().is_some_and(|i| i >= day_count) {
+            self.day_table_state.select_last();
+        }
+        if self.sleep_list.is_empty() {
+            self.sleep_table_state.select(None);
+        } else if self.sleep_table_state.selected().is_some_and(|i| i >= self.sleep_list.len()) {
+            self.sleep_table_state.select_last();
+        }
+    }
+
+    /// Unit used for the chart, taken from the most recent entry.
+    fn display_unit(&self) -> WeightUnit {
+        self.weight_list.first().map(|w| w.unit).unwrap_or(WeightUnit::Kg)
+    }
+
+    /// Average of all weights logged in the seven days ending on each entry's date.
+    fn moving_average(&self, unit: WeightUnit) -> Vec<(NaiveDate, f64)> {
+        let mut averages: Vec<(NaiveDate, f64)> = self.weight_list.iter().map(|entry| {
+            let window_start = entry.date.checked_sub_days(Days::new(6)).unwrap_or(entry.date);
+            let window: Vec<f64> = self.weight_list.iter().filter(|w| w.date >= window_start && w.date <= entry.date).map(|w| w.weight_in(unit)).collect();
+            (entry.date, window.iter().sum::<f64>() / window.len() as f64)
+        }).collect();
+        averages.sort_by_key(|(date, _)| *date);
+        averages.dedup_by_key(|(date, _)| *date);
+        averages
+    }
+
+    fn sleep_in_window(&self, today: NaiveDate) -> Vec<&SleepLog> {
+        let window_start = self.chart_window.start(today);
+        self.sleep_list.iter().filter(|s| s.night() >= window_start && s.night() <= today).collect()
+    }
+
+    /// Totals shown under the charts for the chart window ending on `today`.
+    fn window_summary(&self, unit: WeightUnit, today: NaiveDate) -> WindowSummary {
+        let window_start = self.chart_window.start(today);
+
+        let averages: Vec<(NaiveDate, f64)> = self.moving_average(unit).into_iter().filter(|(date, _)| *date >= window_start && *date <= today).collect();
+        let weight_change = match (averages.first(), averages.last()) {
+            (Some(first), Some(last)) if first.0 != last.0 => Some(last.1 - first.1),
+            _ => None,
+        };
+
+        let burned: Vec<u32> = self.calories_list.iter().filter(|c| c.date >= window_start && c.date <= today).map(|c| c.calories).collect();
+        let total_burned: u64 = burned.iter().map(|c| *c as u64).sum();
+        let average_burned = if burned.is_empty() { 0 } else { total_burned / burned.len() as u64 };
+
+        WindowSummary { weight_change, total_burned, logged_days: burned.len(), average_burned }
+    }
+
+    /// Every day with a weight or calorie entry, newest first.
+    fn day_rows(&self) -> Vec<DayRow<'_>> {
+        let mut dates: Vec<NaiveDate> = self.weight_list.iter().map(|w| w.date).chain(self.calories_list.iter().map(|c| c.date)).collect();
+        dates.sort_by(|a, b| b.cmp(a));
+        dates.dedup();
+        dates.into_iter().map(|date| DayRow {
+            date,
+            // Weight list is sorted newest first, so this is the last weigh-in of the day
+            weight: self.weight_list.iter().find(|w| w.date == date),
+            calories: self.calories_list.iter().find(|c| c.date == date).map(|c| c.calories),
+        }).collect()
+    }
+}
+
+impl RenderableModule for HealthModule {
+    fn input_handling(app: &mut App, key: crossterm::event::KeyEvent) {
+        if let SelectedModule::HealthModule(module_status) = &app.selected_module {
+            match module_status {
+                HealthModules::WeightList => {
+                    let health_module = &mut app.module_data.health_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Char('c') => {
+                            health_module.editable_weight_data = WeightEntry::new(health_module.display_unit());
+                            health_module.editable_weight_data.update_validity();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::WeightEditing(WeightInput::default()));
+                        },
+                        KeyCode::Char('k') => {
+                            health_module.editable_calories_data.clear_form();
+                            health_module.editable_calories_data.update_validity();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::CaloriesEditing(CaloriesInput::default()));
+                        },
+                        KeyCode::Char('w') => health_module.chart_window = health_module.chart_window.next(),
+                        KeyCode::Char('r') => HealthModule::refresh_health_data(app),
+                        KeyCode::Char('i') => HealthModule::open_import(app),
+                        KeyCode::Char('f') => HealthModule::start_fitbit_sync(app),
+                        KeyCode::Tab => app.selected_module = SelectedModule::HealthModule(HealthModules::SleepList),
+                        KeyCode::Up => health_module.day_table_state.select_previous(),
+                        KeyCode::Down => health_module.day_table_state.select_next(),
+                        _ => {}
+                    }
+                },
+                HealthModules::SleepList => {
+                    let health_module = &mut app.module_data.health_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Char('c') => {
+                            health_module.editable_sleep_data.clear_form();
+                            health_module.editable_sleep_data.update_validity();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::SleepEditing(SleepInput::default()));
+                        },
+                        KeyCode::Char('w') => health_module.chart_window = health_module.chart_window.next(),
+                        KeyCode::Char('r') => HealthModule::refresh_health_data(app),
+                        KeyCode::Char('i') => HealthModule::open_import(app),
+                        KeyCode::Char('f') => HealthModule::start_fitbit_sync(app),
+                        KeyCode::Tab => app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList),
+                        KeyCode::Up => health_module.sleep_table_state.select_previous(),
+                        KeyCode::Down => health_module.sleep_table_state.select_next(),
+                        _ => {}
+                    }
+                },
+                HealthModules::SleepEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.health_module.editable_sleep_data;
+                    let input = match selected_input {
+                        SleepInput::Night => &mut editable_data.night,
+                        SleepInput::BedTime => &mut editable_data.bed_time,
+                        SleepInput::WakeTime => &mut editable_data.wake_time,
+                        SleepInput::Quality => &mut editable_data.quality,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::HealthModule(HealthModules::SleepList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::SleepEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::SleepEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            let Some(sleep) = editable_data.request() else { return };
+                            app.request("Saving sleep", move |client| {
+                                let sleep = sleep.clone();
+                                async move { client.create_sleep(user_id, &sleep).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.health_module.editable_sleep_data.clear_form();
+                                // Refresh Health Data
+                                HealthModule::refresh_health_data(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::HealthModule(HealthModules::SleepList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                HealthModules::ImportSetup(selected_input) => {
+                    let editable_data = &mut app.module_data.health_module.editable_import_data;
+                    let input = match selected_input {
+                        ImportInput::Directory => &mut editable_data.directory,
+                        ImportInput::Unit => &mut editable_data.unit,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::ImportSetup(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::ImportSetup(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            let health_module = &mut app.module_data.health_module;
+                            let unit = WeightUnit::parse(&health_module.editable_import_data.unit.text).unwrap_or(WeightUnit::Kg);
+                            let directory = Path::new(health_module.editable_import_data.directory.text.trim());
+                            match FitbitImport::from_directory(directory, unit) {
+                                Ok(import) => {
+                                    health_module.fitbit_import = import.without_existing(&health_module.weight_list, &health_module.sleep_list);
+                                    health_module.fitbit_synced_through = None;
+                                    health_module.import_error = None;
+                                    app.selected_module = SelectedModule::HealthModule(HealthModules::ImportPreview);
+                                },
+                                Err(error) => health_module.import_error = Some(error),
+                            }
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                HealthModules::FitbitAuthorizing => {
+                    let input = &mut app.module_data.health_module.fitbit_code;
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList),
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if input.valid => {
+                            HealthModule::finish_fitbit_login(app);
+                            return;
+                        }
+                        _ => {}
+                    }
+                    let input = &mut app.module_data.health_module.fitbit_code;
+                    input.valid = !input.text.trim().is_empty();
+                },
+                HealthModules::ImportPreview => {
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList),
+                        KeyCode::Enter => {
+                            let health_module = &app.module_data.health_module;
+                            let import = health_module.fitbit_import.clone();
+                            // Remember how far Fitbit has been synced so the next sync carries on from there
+                            let account = health_module.fitbit_synced_through.and_then(|synced_through| {
+                                health_module.fitbit_account.clone().map(|account| FitbitAccount { last_synced: Some(synced_through), ..account })
+                            });
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            app.request("Uploading import", move |client| {
+                                let (import, account) = (import.clone(), account.clone());
+                                async move {
+                                    // Upload everything that is not on the server yet, which also skips whatever an interrupted attempt got through
+                                    let import = import.without_existing(&client.weights(user_id).await?, &client.sleeps(user_id).await?);
+                                    for weight in &import.weights {
+                                        client.create_weight(user_id, &weight.request()).await?;
+                                    }
+                                    for sleep in &import.sleeps {
+                                        client.create_sleep(user_id, &sleep.request()).await?;
+                                    }
+                                    if let Some(account) = &account {
+                                        client.set_fitbit_account(user_id, account).await?;
+                                    }
+                                    Ok(account)
+                                }
+                            }, |app, account| {
+                                let health_module = &mut app.module_data.health_module;
+                                health_module.fitbit_import = FitbitImport::default();
+                                health_module.fitbit_synced_through = None;
+                                if account.is_some() {
+                                    health_module.fitbit_account = account;
+                                }
+                                // Refresh Health Data
+                                HealthModule::refresh_health_data(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList);
+                            });
+                        },
+                        _ => {}
+                    }
+                },
+                HealthModules::WeightEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.health_module.editable_weight_data;
+                    let input = match selected_input {
+                        WeightInput::Date => &mut editable_data.date,
+                        WeightInput::Weight => &mut editable_data.weight,
+                        WeightInput::Unit => &mut editable_data.unit,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::WeightEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::WeightEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            let Some(weight) = editable_data.request() else { return };
+                            app.request("Saving weight", move |client| {
+                                let weight = weight.clone();
+                                async move { client.create_weight(user_id, &weight).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.health_module.editable_weight_data.clear_form();
+                                // Refresh Health Data
+                                HealthModule::refresh_health_data(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+                HealthModules::CaloriesEditing(selected_input) => {
+                    let editable_data = &mut app.module_data.health_module.editable_calories_data;
+                    let input = match selected_input {
+                        CaloriesInput::Date => &mut editable_data.date,
+                        CaloriesInput::Calories => &mut editable_data.calories,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::CaloriesEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::HealthModule(HealthModules::CaloriesEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Send to server, replacing any existing total for that day
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            let Some(calories) = editable_data.request() else { return };
+                            app.request("Saving calories", move |client| {
+                                let calories = calories.clone();
+                                async move { client.set_calories(user_id, &calories).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.health_module.editable_calories_data.clear_form();
+                                // Refresh Health Data
+                                HealthModule::refresh_health_data(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+            }
+        }
+    }
+
+    fn render(app: &mut App, frame: &mut ratatui::Frame) {
+        let rects = Layout::vertical([Constraint::Length(3), Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
+
+        let showing_sleep = matches!(app.selected_module, SelectedModule::HealthModule(HealthModules::SleepList | HealthModules::SleepEditing(_)));
+
+        let footer_contents = if showing_sleep {
+            let panes = Layout::horizontal([Constraint::Length(52), Constraint::Fill(1)]).split(rects[1]);
+            let charts = Layout::vertical([Constraint::Length(8), Constraint::Fill(1)]).split(panes[1]);
+
+            HealthModule::render_sleep_table(app, frame, panes[0]);
+            HealthModule::render_sleep_stats(app, frame, charts[0]);
+            HealthModule::render_sleep_week(app, frame, charts[1]);
+
+            text::Line::from("Esc: Back | Tab: Switch View | Arrow Keys: Navigation | c: Log Sleep | w: Change Stats Window | i: Import Fitbit Data | f: Sync Fitbit | r: Refresh")
+        } else {
+            let panes = Layout::horizontal([Constraint::Length(44), Constraint::Fill(1)]).split(rects[1]);
+            let charts = Layout::vertical([Constraint::Fill(3), Constraint::Length(3), Constraint::Fill(2)]).split(panes[1]);
+
+            HealthModule::render_day_table(app, frame, panes[0]);
+            HealthModule::render_weight_chart(app, frame, charts[0]);
+            HealthModule::render_window_summary(app, frame, charts[1]);
+            HealthModule::render_calories_chart(app, frame, charts[2]);
+
+            text::Line::from("Esc: Back | Tab: Switch View | Arrow Keys: Navigation | c: Log Weight | k: Log Calories Burned | w: Change Chart Window | i: Import Fitbit Data | f: Sync Fitbit | r: Refresh")
+        };
+
+        let tabs = Tabs::new(vec!["Weight & Calories", "Sleep"]).block(Block::bordered().title("Health")).highlight_style(Style::new().reversed()).select(if showing_sleep { 1 } else { 0 });
+        frame.render_widget(tabs, rects[0]);
+
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[2]);
+
+        if let SelectedModule::HealthModule(HealthModules::WeightEditing(selected_input)) = &app.selected_module {
+            let area = rendering::popup_area_lengths(frame.area(), 37, 12);
+
+            let rects = Layout::vertical([Constraint::Length(3); 4]).split(area);
+
+            let editable_data = &app.module_data.health_module.editable_weight_data;
+
+            let input_date = single_line_input(&editable_data.date, "Date (YYYY-MM-DD)");
+            frame.render_widget(input_date, rects[0]);
+
+            let input_weight = single_line_input(&editable_data.weight, "Weight");
+            frame.render_widget(input_weight, rects[1]);
+
+            let input_unit = single_line_input(&editable_data.unit, "Unit (kg / lb)");
+            frame.render_widget(input_unit, rects[2]);
+
+            let (rect, input) = match selected_input {
+                WeightInput::Date => (0, &editable_data.date),
+                WeightInput::Weight => (1, &editable_data.weight),
+                WeightInput::Unit => (2, &editable_data.unit),
+            };
+            frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+            let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Weight");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[3]);
+        }
+
+        if let SelectedModule::HealthModule(HealthModules::CaloriesEditing(selected_input)) = &app.selected_module {
+            let area = rendering::popup_area_lengths(frame.area(), 37, 9);
+
+            let rects = Layout::vertical([Constraint::Length(3); 3]).split(area);
+
+            let editable_data = &app.module_data.health_module.editable_calories_data;
+
+            let input_date = single_line_input(&editable_data.date, "Date (YYYY-MM-DD)");
+            frame.render_widget(input_date, rects[0]);
+
+            let input_calories = single_line_input(&editable_data.calories, "Calories Burned (kcal)");
+            frame.render_widget(input_calories, rects[1]);
+
+            let (rect, input) = match selected_input {
+                CaloriesInput::Date => (0, &editable_data.date),
+                CaloriesInput::Calories => (1, &editable_data.calories),
+            };
+            frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+            let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Calories");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[2]);
+        }
+
+        if let SelectedModule::HealthModule(HealthModules::SleepEditing(selected_input)) = &app.selected_module {
+            let area = rendering::popup_area_lengths(frame.area(), 40, 15);
+
+            let rects = Layout::vertical([Constraint::Length(3); 5]).split(area);
+
+            let editable_data = &app.module_data.health_module.editable_sleep_data;
+
+            let inputs = [
+                (&editable_data.night, "Night Of (YYYY-MM-DD)"),
+                (&editable_data.bed_time, "Bed Time (HH:MM)"),
+                (&editable_data.wake_time, "Wake Time (HH:MM)"),
+                (&editable_data.quality, "Quality 0-100 (optional)"),
+            ];
+            for (rect, (input, title)) in inputs.iter().enumerate() {
+                frame.render_widget(single_line_input(input, title), rects[rect]);
+            }
+
+            let rect = match selected_input {
+                SleepInput::Night => 0,
+                SleepInput::BedTime => 1,
+                SleepInput::WakeTime => 2,
+                SleepInput::Quality => 3,
+            };
+            frame.set_cursor_position(rendering::cursor_position(inputs[rect].0, rects[rect]));
+
+            let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Sleep");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[4]);
+        }
+
+        if let SelectedModule::HealthModule(HealthModules::ImportSetup(selected_input)) = &app.selected_module {
+            let area = rendering::popup_area_lengths(frame.area(), 60, 10);
+
+            let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3), Constraint::Length(1), Constraint::Length(3)]).split(area);
+
+            let health_module = &app.module_data.health_module;
+            let editable_data = &health_module.editable_import_data;
+
+            let input_directory = single_line_input(&editable_data.directory, "Fitbit Export Directory");
+            frame.render_widget(input_directory, rects[0]);
+
+            let input_unit = single_line_input(&editable_data.unit, "Weight Unit Used By Fitbit (kg / lb)");
+            frame.render_widget(input_unit, rects[1]);
+
+            let (rect, input) = match selected_input {
+                ImportInput::Directory => (0, &editable_data.directory),
+                ImportInput::Unit => (1, &editable_data.unit),
+            };
+            frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+            if let Some(error) = &health_module.import_error {
+                frame.render_widget(Paragraph::new(error.as_str()).style(Style::new().fg(Color::Red)).on_black(), rects[2]);
+            }
+
+            let footer_contents = text::Line::from("Esc: Back | Enter: Scan Directory");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[3]);
+        }
+
+        if let SelectedModule::HealthModule(HealthModules::ImportPreview) = &app.selected_module {
+            HealthModule::render_import_preview(app, frame);
+        }
+
+        if let SelectedModule::HealthModule(HealthModules::FitbitAuthorizing) = &app.selected_module {
+            HealthModule::render_fitbit_login(app, frame);
+        }
+    }
+}
+
+impl HealthModule {
+    pub fn refresh_health_data(app: &mut App) {
+        let user_id = app.module_data.user_module.selected_user.id;
+        app.request("Loading health data", move |client| async move {
+            Ok((client.weights(user_id).await?, client.calories(user_id).await?, client.sleeps(user_id).await?))
+        }, |app, (weights, calories, sleeps)| app.module_data.health_module.set_logs(weights, calories, sleeps));
+    }
+
+    fn render_day_table(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &mut app.module_data.health_module;
+
+        let header = Row::new(vec!["Date", "Weight", "Burned (kcal)"]).bold();
+        let day_rows = health_module.day_rows();
+        let day_count = day_rows.len();
+        let rows: Vec<Row> = day_rows.iter().map(|d| Row::new(vec![
+            d.date.format("%Y-%m-%d").to_string(),
+            d.weight.map(|w| format!("{:.1} {}", w.weight, w.unit.name())).unwrap_or_default(),
+            d.calories.map(|c| c.to_string()).unwrap_or_default(),
+        ])).collect();
+        let widths = [Constraint::Length(12), Constraint::Length(10), Constraint::Length(13)];
+
+        let day_table = Table::new(rows, widths).header(header).block(Block::bordered().title("Daily Log")).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(day_table, area, &mut health_module.day_table_state);
+
+        if day_count > 0 && health_module.day_table_state.selected().is_none() {
+            health_module.day_table_state.select_next();
+        }
+    }
+
+    fn render_weight_chart(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &app.module_data.health_module;
+        let unit = health_module.display_unit();
+        let today = Local::now().date_naive();
+        let window_start = health_module.chart_window.start(today);
+        let block = Block::bordered().title(format!("Weight Trend ({}, last {} days)", unit.name(), health_module.chart_window.days()));
+
+        let day_offset = |date: NaiveDate| date.signed_duration_since(window_start).num_days() as f64;
+
+        let mut weights: Vec<(f64, f64)> = health_module.weight_list.iter().filter(|w| w.date >= window_start && w.date <= today).map(|w| (day_offset(w.date), w.weight_in(unit))).collect();
+        if weights.is_empty() {
+            frame.render_widget(Paragraph::new("No weight logged in this window, press c to add an entry").centered().block(block), area);
+            return;
+        }
+        weights.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let averages: Vec<(f64, f64)> = health_module.moving_average(unit).into_iter().filter(|(date, _)| *date >= window_start && *date <= today).map(|(date, avg)| (day_offset(date), avg)).collect();
+
+        let min_weight = weights.iter().map(|w| w.1).fold(f64::INFINITY, f64::min);
+        let max_weight = weights.iter().map(|w| w.1).fold(f64::NEG_INFINITY, f64::max);
+        let padding = ((max_weight - min_weight) * 0.1).max(1.0);
+        let y_bounds = [(min_weight - padding).floor(), (max_weight + padding).ceil()];
+        let x_max = day_offset(today).max(1.0);
+
+        let datasets = vec![
+            Dataset::default().name("Weight").marker(symbols::Marker::Dot).graph_type(GraphType::Scatter).style(Style::new().fg(Color::Cyan)).data(&weights),
+            Dataset::default().name("7-day average").marker(symbols::Marker::Braille).graph_type(GraphType::Line).style(Style::new().fg(Color::Yellow)).data(&averages),
+        ];
+
+        let x_labels = vec![window_start.format("%Y-%m-%d").to_string(), today.format("%Y-%m-%d").to_string()];
+        let y_labels = vec![format!("{:.0}", y_bounds[0]), format!("{:.0}", y_bounds[1])];
+
+        let chart = Chart::new(datasets)
+            .block(block)
+            .x_axis(Axis::default().title("Date").bounds([0.0, x_max]).labels(x_labels))
+            .y_axis(Axis::default().title(unit.name()).bounds(y_bounds).labels(y_labels));
+        frame.render_widget(chart, area);
+    }
+
+    /// Compares the weight change across the chart window with the energy burned over the same days.
+    fn render_window_summary(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &app.module_data.health_module;
+        let unit = health_module.display_unit();
+        let window = health_module.window_summary(unit, Local::now().date_naive());
+
+        let weight_change = match window.weight_change {
+            Some(change) => format!("{:+.1} {}", change, unit.name()),
+            None => "-".to_string(),
+        };
+        let summary = format!("Weight change (7-day avg): {} | Burned: {} kcal over {} logged days | Daily average: {} kcal", weight_change, window.total_burned, window.logged_days, window.average_burned);
+        let paragraph = Paragraph::new(summary).block(Block::bordered().title("Window Summary")).centered();
+        frame.render_widget(paragraph, area);
+    }
+
+    fn render_calories_chart(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &app.module_data.health_module;
+        let today = Local::now().date_naive();
+        let window_start = health_module.chart_window.start(today);
+        let block = Block::bordered().title(format!("Calories Burned (last {} days)", health_module.chart_window.days()));
+
+        let bars: Vec<Bar> = window_start.iter_days().take_while(|d| *d <= today).map(|date| {
+            let calories = health_module.calories_list.iter().find(|c| c.date == date).map(|c| c.calories).unwrap_or(0);
+            Bar::default().value(calories as u64).label(date.format("%d").to_string().into()).text_value(String::new())
+        }).collect();
+
+        // Squeeze the bars so the whole window fits inside the chart
+        let inner_width = area.width.saturating_sub(2) as usize;
+        let bar_gap = if bars.len() * 2 <= inner_width { 1 } else { 0 };
+        let bar_width = (inner_width / bars.len().max(1)).saturating_sub(bar_gap).max(1) as u16;
+
+        let chart = BarChart::default().block(block).data(BarGroup::default().bars(&bars)).bar_width(bar_width).bar_gap(bar_gap as u16).bar_style(Style::new().fg(Color::Magenta));
+        frame.render_widget(chart, area);
+    }
+
+    fn open_import(app: &mut App) {
+        let health_module = &mut app.module_data.health_module;
+        health_module.editable_import_data.clear_form();
+        health_module.editable_import_data.update_validity();
+        health_module.import_error = None;
+        app.selected_module = SelectedModule::HealthModule(HealthModules::ImportSetup(ImportInput::default()));
+    }
+
+    /// Pulls everything logged on Fitbit since the last sync into the import preview, asking the
+    /// user to connect their account first if the server has no tokens for them.
+    fn start_fitbit_sync(app: &mut App) {
+        let user_id = app.module_data.user_module.selected_user.id;
+        let fitbit = FitbitClient::new(&app.config.fitbit, &app.http_client);
+        let unsaved = app.module_data.health_module.unsaved_fitbit_account.clone().filter(|(id, _)| *id == user_id).map(|(_, account)| account);
+        app.request("Syncing Fitbit", move |client| {
+            let (fitbit, unsaved) = (fitbit.clone(), unsaved.clone());
+            async move {
+                // Tokens refreshed by an earlier sync replace whatever the server still has
+                if let Some(account) = unsaved {
+                    client.set_fitbit_account(user_id, &account).await?;
+                    return HealthModule::sync_fitbit(&client, &fitbit, user_id, account).await;
+                }
+                match client.fitbit_account(user_id).await? {
+                    Some(account) => HealthModule::sync_fitbit(&client, &fitbit, user_id, account).await,
+                    None => Ok(FitbitSync::NeedsLogin(None)),
+                }
+            }
+        }, HealthModule::apply_fitbit_sync);
+    }
+
+    fn open_fitbit_login(app: &mut App, error: Option<String>) {
+        let client = FitbitClient::new(&app.config.fitbit, &app.http_client);
+        let health_module = &mut app.module_data.health_module;
+        health_module.fitbit_code = TextInput::default();
+        health_module.import_error = error;
+        health_module.fitbit_login = None;
+
+        if !client.is_configured() {
+            health_module.import_error = Some(format!("Set client_id under [fitbit] in {} to connect a Fitbit account", app.config.path().display()));
+        } else {
+            match client.begin_login() {
+                Ok(login) => health_module.fitbit_login = Some(login),
+                Err(error) => health_module.import_error = Some(error),
+            }
+        }
+        app.selected_module = SelectedModule::HealthModule(HealthModules::FitbitAuthorizing);
+    }
+
+    fn finish_fitbit_login(app: &mut App) {
+        let health_module = &mut app.module_data.health_module;
+        let Some(login) = health_module.fitbit_login.clone() else { return };
+        let code = match login.code_from_redirect(&health_module.fitbit_code.text) {
+            Ok(code) => code,
+            Err(error) => {
+                health_module.import_error = Some(error);
+                return;
+            }
+        };
+
+        let user_id = app.module_data.user_module.selected_user.id;
+        let fitbit = FitbitClient::new(&app.config.fitbit, &app.http_client);
+        app.request("Connecting Fitbit", move |client| {
+            let (fitbit, login, code) = (fitbit.clone(), login.clone(), code.clone());
+            async move {
+                let account = match fitbit.exchange_code(&login, &code).await {
+                    Ok(account) => account,
+                    Err(error) => return Ok(FitbitSync::LoginFailed(error)),
+                };
+                client.set_fitbit_account(user_id, &account).await?;
+                HealthModule::sync_fitbit(&client, &fitbit, user_id, account).await
+            }
+        }, HealthModule::apply_fitbit_sync);
+    }
+
+    /// Refreshes the tokens if they have run out, then fetches everything since the last sync.
+    async fn sync_fitbit(client: &ApiClient, fitbit: &FitbitClient, user_id: i64, mut account: FitbitAccount) -> ApiResult<FitbitSync> {
+        if account.is_expired() {
+            match fitbit.refresh(&account).await {
+                Ok(refreshed) => {
+                    if let Err(error) = client.set_fitbit_account(user_id, &refreshed).await {
+                        return Ok(FitbitSync::Unsaved { user_id, account: refreshed, error: error.to_string() });
+                    }
+                    account = refreshed;
+                },
+                // The refresh token has been revoked or has expired, so the user has to log in again
+                Err(error) => return Ok(FitbitSync::NeedsLogin(Some(error))),
+            }
+        }
+
+        let today = Local::now().date_naive();
+        match fitbit.fetch(&account, account.sync_start(today), today).await {
+            // The token can be revoked before it expires, from Fitbit's settings for example
+            Err(error @ FetchError::Unauthorized) => Ok(FitbitSync::NeedsLogin(Some(error.to_string()))),
+            import => Ok(FitbitSync::Fetched { account, import: import.map_err(|error| error.to_string()), today }),
+        }
+    }
+
+    fn apply_fitbit_sync(app: &mut App, sync: FitbitSync) {
+        let health_module = &mut app.module_data.health_module;
+        match sync {
+            FitbitSync::NeedsLogin(error) => {
+                health_module.fitbit_account = None;
+                health_module.unsaved_fitbit_account = None;
+                HealthModule::open_fitbit_login(app, error);
+            },
+            FitbitSync::LoginFailed(error) => health_module.import_error = Some(error),
+            FitbitSync::Unsaved { user_id, account, error } => {
+                health_module.unsaved_fitbit_account = Some((user_id, account));
+                health_module.fitbit_import = FitbitImport { errors: vec![format!("Could not save the refreshed Fitbit login, sync again to retry: {}", error)], ..Default::default() };
+                health_module.fitbit_synced_through = None;
+                app.selected_module = SelectedModule::HealthModule(HealthModules::ImportPreview);
+            },
+            FitbitSync::Fetched { account, import, today } => {
+                match import {
+                    Ok(import) => {
+                        health_module.fitbit_import = import.without_existing(&health_module.weight_list, &health_module.sleep_list);
+                        health_module.fitbit_synced_through = Some(today);
+                    },
+                    Err(error) => {
+                        health_module.fitbit_import = FitbitImport { errors: vec![error], ..Default::default() };
+                        health_module.fitbit_synced_through = None;
+                    }
+                }
+                health_module.fitbit_account = Some(account);
+                health_module.unsaved_fitbit_account = None;
+                health_module.fitbit_login = None;
+                app.selected_module = SelectedModule::HealthModule(HealthModules::ImportPreview);
+            }
+        }
+    }
+
+    fn render_fitbit_login(app: &mut App, frame: &mut ratatui::Frame) {
+        let health_module = &app.module_data.health_module;
+        let area = rendering::popup_area_percent(frame.area(), 70, 60);
+        frame.render_widget(Clear, area);
+
+        let rects = Layout::vertical([Constraint::Fill(1), Constraint::Length(1), Constraint::Length(3), Constraint::Length(3)]).split(area);
+
+        let instructions = match &health_module.fitbit_login {
+            Some(login) => vec![
+                text::Line::from("Open this address in a browser and allow access to weight and sleep data:"),
+                text::Line::from(""),
+                text::Line::from(login.authorization_url.as_str()).cyan(),
+                text::Line::from(""),
+                text::Line::from("Then paste the address Fitbit redirects to, or just its code, below."),
+            ],
+            None => vec![text::Line::from("Fitbit sync is not available")],
+        };
+        frame.render_widget(Paragraph::new(instructions).wrap(Wrap { trim: false }).block(Block::bordered().title("Connect Fitbit")), rects[0]);
+
+        if let Some(error) = &health_module.import_error {
+            frame.render_widget(Paragraph::new(error.as_str()).style(Style::new().fg(Color::Red)).on_black(), rects[1]);
+        }
+
+        let input_code = single_line_input(&health_module.fitbit_code, "Redirect Address or Code");
+        frame.render_widget(input_code, rects[2]);
+        frame.set_cursor_position(rendering::cursor_position(&health_module.fitbit_code, rects[2]));
+
+        let footer_contents = text::Line::from("Esc: Back | Enter: Connect");
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[3]);
+    }
+
+    fn render_import_preview(app: &mut App, frame: &mut ratatui::Frame) {
+        let import = &app.module_data.health_module.fitbit_import;
+        let area = rendering::popup_area_percent(frame.area(), 70, 70);
+        frame.render_widget(Clear, area);
+
+        let rects = Layout::vertical([Constraint::Length(3), Constraint::Fill(1), Constraint::Length(import.errors.len().min(5) as u16 + if import.errors.is_empty() { 0 } else { 2 }), Constraint::Length(3)]).split(area);
+
+        let summary = format!("{} new weight entries and {} new sleep entries will be uploaded", import.weights.len(), import.sleeps.len());
+        frame.render_widget(Paragraph::new(summary).block(Block::bordered().title("Fitbit Import Preview")).centered(), rects[0]);
+
+        let header = Row::new(vec!["Type", "Date", "Details"]).bold();
+        let weight_rows = import.weights.iter().map(|w| Row::new(vec![
+            "Weight".to_string(),
+            w.date.format("%Y-%m-%d").to_string(),
+            format!("{:.1} {}", w.weight, w.unit.name()),
+        ]));
+        let sleep_rows = import.sleeps.iter().map(|s| Row::new(vec![
+            "Sleep".to_string(),
+            night_of(s.bed_time).format("%Y-%m-%d").to_string(),
+            format!("{} - {} ({})", s.bed_time.format("%H:%M"), s.wake_time.format("%H:%M"), format_duration(s.wake_time.signed_duration_since(s.bed_time).num_minutes())),
+        ]));
+        let widths = [Constraint::Length(8), Constraint::Length(12), Constraint::Fill(1)];
+        let preview_table = Table::new(weight_rows.chain(sleep_rows), widths).header(header).block(Block::bordered().title("New Entries"));
+        frame.render_widget(preview_table, rects[1]);
+
+        if !import.errors.is_empty() {
+            let errors: Vec<text::Line> = import.errors.iter().take(5).map(|e| text::Line::from(e.as_str())).collect();
+            frame.render_widget(Paragraph::new(errors).style(Style::new().fg(Color::Red)).block(Block::bordered().title("Problems")), rects[2]);
+        }
+
+        let footer_contents = if import.is_empty() {
+            text::Line::from("Nothing new to import | Esc: Back")
+        } else {
+            text::Line::from("Esc: Cancel | Enter: Upload Entries")
+        };
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[3]);
+    }
+
+    fn render_sleep_table(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &mut app.module_data.health_module;
+
+        let header = Row::new(vec!["Night", "Bed", "Wake", "Duration", "Quality"]).bold();
+        let rows: Vec<Row> = health_module.sleep_list.iter().map(|s| Row::new(vec![
+            s.night().format("%Y-%m-%d").to_string(),
+            s.bed_time.format("%H:%M").to_string(),
+            s.wake_time.format("%H:%M").to_string(),
+            format_duration(s.duration_minutes()),
+            s.quality.map(|q| q.to_string()).unwrap_or_default(),
+        ])).collect();
+        let widths = [Constraint::Length(12), Constraint::Length(6), Constraint::Length(6), Constraint::Length(9), Constraint::Length(7)];
+
+        let sleep_table = Table::new(rows, widths).header(header).block(Block::bordered().title("Sleep Log")).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(sleep_table, area, &mut health_module.sleep_table_state);
+
+        if !health_module.sleep_list.is_empty() && health_module.sleep_table_state.selected().is_none() {
+            health_module.sleep_table_state.select_next();
+        }
+    }
+
+    fn render_sleep_stats(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &app.module_data.health_module;
+        let today = Local::now().date_naive();
+        let block = Block::bordered().title(format!("Sleep Summary (last {} days)", health_module.chart_window.days()));
+
+        let Some(stats) = SleepStats::from_logs(&health_module.sleep_in_window(today)) else {
+            frame.render_widget(Paragraph::new("No sleep logged in this window, press c to add an entry").centered().block(block), area);
+            return;
+        };
+
+        let lines = vec![
+            text::Line::from(format!("Nights logged: {}", stats.nights)),
+            text::Line::from(format!("Average duration: {}", format_duration(stats.average_duration))),
+            text::Line::from(format!("Average bed time: {}", stats.average_bed_time.format("%H:%M"))),
+            text::Line::from(format!("Bed time consistency: ±{} min", stats.bed_time_deviation)),
+            text::Line::from(format!("Average quality: {}", stats.average_quality.map(|q| format!("{:.0}", q)).unwrap_or("-".to_string()))),
+        ];
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn render_sleep_week(app: &mut App, frame: &mut ratatui::Frame, area: Rect) {
+        let health_module = &app.module_data.health_module;
+        let today = Local::now().date_naive();
+        let week_start = ChartWindow::Week.start(today);
+
+        // Nights are labelled by the morning they end on, so last night shows under today
+        let bars: Vec<Bar> = week_start.iter_days().take_while(|d| *d <= today).map(|morning| {
+            let night = morning.pred_opt().unwrap();
+            let minutes: i64 = health_module.sleep_list.iter().filter(|s| s.night() == night).map(|s| s.duration_minutes()).sum();
+            Bar::default().value(minutes.max(0) as u64).label(morning.format("%a").to_string().into()).text_value(format_duration(minutes))
+        }).collect();
+
+        let inner_width = area.width.saturating_sub(2);
+        let bar_width = (inner_width / 7).saturating_sub(1).max(1);
+
+        let chart = BarChart::default().block(Block::bordered().title("Sleep This Week")).data(BarGroup::default().bars(&bars)).bar_width(bar_width).bar_gap(1).bar_style(Style::new().fg(Color::Blue));
+        frame.render_widget(chart, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn weight(id: i64, day: &str, weight: f64, unit: WeightUnit) -> WeightLog {
+        WeightLog { id, date: date(day), weight, unit, fitbit_log_id: None }
+    }
+
+    fn assert_averages(actual: Vec<(NaiveDate, f64)>, expected: &[(&str, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((date, average), (expected_date, expected_average)) in actual.iter().zip(expected) {
+            assert_eq!(date.to_string(), *expected_date);
+            assert!((average - expected_average).abs() < 1e-9, "{} averaged {} instead of {}", date, average, expected_average);
+        }
+    }
+
+    #[test]
+    fn moving_average_covers_the_last_seven_days() {
+        let mut health = HealthModuleData::default();
+        health.set_logs(vec![
+            weight(1, "2024-03-01", 80.0, WeightUnit::Kg),
+            weight(2, "2024-03-02", 81.0, WeightUnit::Kg),
+            weight(3, "2024-03-02", 82.0, WeightUnit::Kg),
+            weight(4, "2024-03-08", 86.0, WeightUnit::Kg),
+            weight(5, "2024-03-10", 90.0 * WeightUnit::LB_PER_KG, WeightUnit::Lb),
+        ], Vec::new(), Vec::new());
+
+        // Every weigh-in of a day counts towards its single point, and a day
+        // falls out of the window once it is a week old, gap or not
+        assert_averages(health.moving_average(WeightUnit::Kg), &[
+            ("2024-03-01", 80.0),
+            ("2024-03-02", 81.0),
+            ("2024-03-08", 83.0),
+            ("2024-03-10", 88.0),
+        ]);
+    }
+
+    #[test]
+    fn moving_average_converts_to_the_display_unit() {
+        let mut health = HealthModuleData::default();
+        health.set_logs(vec![
+            weight(1, "2024-03-01", 100.0, WeightUnit::Kg),
+            weight(2, "2024-03-02", 200.0, WeightUnit::Lb),
+        ], Vec::new(), Vec::new());
+
+        // The newest entry decides the unit
+        assert!(health.display_unit() == WeightUnit::Lb);
+        let kg_in_lb = 100.0 * WeightUnit::LB_PER_KG;
+        assert_averages(health.moving_average(WeightUnit::Lb), &[("2024-03-01", kg_in_lb), ("2024-03-02", (kg_in_lb + 200.0) / 2.0)]);
+        let lb_in_kg = 200.0 / WeightUnit::LB_PER_KG;
+        assert_averages(health.moving_average(WeightUnit::Kg), &[("2024-03-01", 100.0), ("2024-03-02", (100.0 + lb_in_kg) / 2.0)]);
+    }
+
+    fn calories(day: &str, calories: u32) -> CaloriesLog {
+        CaloriesLog { date: date(day), calories }
+    }
+
+    #[test]
+    fn day_rows_merge_weights_and_calories() {
+        let mut health = HealthModuleData::default();
+        health.set_logs(vec![
+            weight(1, "2024-03-01", 80.0, WeightUnit::Kg),
+            weight(2, "2024-03-03", 81.0, WeightUnit::Kg),
+            weight(3, "2024-03-03", 80.5, WeightUnit::Kg),
+        ], vec![calories("2024-03-02", 2_300), calories("2024-03-03", 2_500)], Vec::new());
+
+        let rows: Vec<(String, Option<i64>, Option<u32>)> = health.day_rows().iter().map(|row| (row.date.to_string(), row.weight.map(|w| w.id), row.calories)).collect();
+        assert_eq!(rows, vec![
+            // The later of two weigh-ins stands for the day
+            ("2024-03-03".to_string(), Some(3), Some(2_500)),
+            ("2024-03-02".to_string(), None, Some(2_300)),
+            ("2024-03-01".to_string(), Some(1), None),
+        ]);
+    }
+
+    #[test]
+    fn window_summary_only_counts_the_window() {
+        let mut health = HealthModuleData { chart_window: ChartWindow::Week, ..Default::default() };
+        health.set_logs(vec![
+            weight(1, "2024-02-20", 70.0, WeightUnit::Kg),
+            weight(2, "2024-03-04", 82.0, WeightUnit::Kg),
+            weight(3, "2024-03-06", 80.0, WeightUnit::Kg),
+            weight(4, "2024-03-11", 79.0, WeightUnit::Kg),
+        ], vec![calories("2024-03-04", 2_000), calories("2024-03-05", 2_500), calories("2024-03-10", 3_001), calories("2024-03-12", 9_000)], Vec::new());
+
+        // The week ending on the 10th starts on the 4th
+        let summary = health.window_summary(WeightUnit::Kg, date("2024-03-10"));
+        assert!((summary.weight_change.unwrap() - -1.0).abs() < 1e-9);
+        assert_eq!((summary.total_burned, summary.logged_days, summary.average_burned), (7_501, 3, 2_500));
+
+        // A single day of weights has no change, and a window without calories averages to nothing
+        let summary = health.window_summary(WeightUnit::Kg, date("2024-02-25"));
+        assert_eq!(summary.weight_change, None);
+        assert_eq!((summary.total_burned, summary.logged_days, summary.average_burned), (0, 0, 0));
+    }
+}
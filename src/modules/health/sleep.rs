@@ -0,0 +1,145 @@
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+/// Longest sleep that is accepted as a single entry.
+const MAX_SLEEP_HOURS: i64 = 18;
+
+/// Bed times before this hour are past midnight and belong to the night before.
+const NIGHT_START_HOUR: u32 = 12;
+
+#[derive(serde::Deserialize, Clone)]
+pub struct SleepLog {
+    pub id: i64,
+    pub bed_time: NaiveDateTime,
+    pub wake_time: NaiveDateTime,
+    /// Optional 0 to 100 rating of how well the night went
+    pub quality: Option<u8>,
+    #[serde(default)]
+    pub fitbit_log_id: Option<i64>
+}
+
+impl SleepLog {
+    /// The night a sleep belongs to, which is the evening the user went to bed on.
+    pub fn night(&self) -> NaiveDate {
+        night_of(self.bed_time)
+    }
+
+    pub fn duration_minutes(&self) -> i64 {
+        self.wake_time.signed_duration_since(self.bed_time).num_minutes()
+    }
+}
+
+/// The evening of the night that a sleep starting at `bed_time` belongs to, so going to bed at
+/// 00:30 counts towards the day before.
+pub fn night_of(bed_time: NaiveDateTime) -> NaiveDate {
+    if bed_time.hour() < NIGHT_START_HOUR {
+        bed_time.date().pred_opt().unwrap_or(bed_time.date())
+    } else {
+        bed_time.date()
+    }
+}
+
+/// Builds the bed and wake times for a night from clock times. Bed times in the early hours are
+/// moved to the day after `night`, and the wake time rolls over to the next day when it is not
+/// after the bed time (going to bed at 23:30 and waking at 07:00).
+pub fn sleep_period(night: NaiveDate, bed: NaiveTime, wake: NaiveTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let bed_date = if bed.hour() < NIGHT_START_HOUR { night.checked_add_days(Days::new(1))? } else { night };
+    let bed_time = bed_date.and_time(bed);
+    let wake_date = if wake <= bed { bed_date.checked_add_days(Days::new(1))? } else { bed_date };
+    let wake_time = wake_date.and_time(wake);
+
+    let minutes = wake_time.signed_duration_since(bed_time).num_minutes();
+    (minutes > 0 && minutes <= MAX_SLEEP_HOURS * 60).then_some((bed_time, wake_time))
+}
+
+pub fn parse_clock_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").ok()
+}
+
+pub fn format_duration(minutes: i64) -> String {
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Minutes from noon, so bed times either side of midnight sit next to each other.
+fn minutes_from_noon(time: NaiveTime) -> i64 {
+    ((time.hour() as i64 * 60 + time.minute() as i64) - 12 * 60).rem_euclid(24 * 60)
+}
+
+fn time_from_noon(minutes: i64) -> NaiveTime {
+    let minutes = (minutes + 12 * 60).rem_euclid(24 * 60);
+    NaiveTime::from_hms_opt((minutes / 60) as u32, (minutes % 60) as u32, 0).unwrap()
+}
+
+pub struct SleepStats {
+    pub nights: usize,
+    pub average_duration: i64,
+    pub average_bed_time: NaiveTime,
+    /// Standard deviation of the bed time in minutes, lower is more consistent
+    pub bed_time_deviation: i64,
+    pub average_quality: Option<f64>
+}
+
+impl SleepStats {
+    pub fn from_logs(logs: &[&SleepLog]) -> Option<Self> {
+        if logs.is_empty() {
+            return None;
+        }
+        let nights = logs.len();
+
+        let average_duration = logs.iter().map(|l| l.duration_minutes()).sum::<i64>() / nights as i64;
+
+        let bed_minutes: Vec<f64> = logs.iter().map(|l| minutes_from_noon(l.bed_time.time()) as f64).collect();
+        let mean_bed = bed_minutes.iter().sum::<f64>() / nights as f64;
+        let variance = bed_minutes.iter().map(|m| (m - mean_bed).powi(2)).sum::<f64>() / nights as f64;
+
+        let qualities: Vec<f64> = logs.iter().filter_map(|l| l.quality).map(|q| q as f64).collect();
+        let average_quality = (!qualities.is_empty()).then(|| qualities.iter().sum::<f64>() / qualities.len() as f64);
+
+        Some(Self {
+            nights,
+            average_duration,
+            average_bed_time: time_from_noon(mean_bed.round() as i64),
+            bed_time_deviation: variance.sqrt().round() as i64,
+            average_quality,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: NaiveDate, time: &str) -> NaiveDateTime {
+        date.and_time(parse_clock_time(time).unwrap())
+    }
+
+    #[test]
+    fn wake_time_rolls_over_to_the_next_morning() {
+        let night = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let morning = night.succ_opt().unwrap();
+        let period = sleep_period(night, parse_clock_time("23:30").unwrap(), parse_clock_time("07:00").unwrap());
+        assert_eq!(period, Some((at(night, "23:30"), at(morning, "07:00"))));
+    }
+
+    #[test]
+    fn bed_time_after_midnight_is_on_the_next_day() {
+        let night = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let morning = night.succ_opt().unwrap();
+        let (bed_time, wake_time) = sleep_period(night, parse_clock_time("00:30").unwrap(), parse_clock_time("07:00").unwrap()).unwrap();
+        assert_eq!((bed_time, wake_time), (at(morning, "00:30"), at(morning, "07:00")));
+
+        // It still belongs to the night it was entered for
+        let log = SleepLog { id: 1, bed_time, wake_time, quality: None, fitbit_log_id: None };
+        assert_eq!(log.night(), night);
+        assert_eq!(log.duration_minutes(), 390);
+    }
+
+    #[test]
+    fn sleeps_longer_than_the_limit_are_rejected() {
+        let night = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        // 20:00 to 14:00 is exactly 18 hours
+        assert!(sleep_period(night, parse_clock_time("20:00").unwrap(), parse_clock_time("14:00").unwrap()).is_some());
+        assert!(sleep_period(night, parse_clock_time("20:00").unwrap(), parse_clock_time("14:01").unwrap()).is_none());
+        // A bed time after midnight cannot wake up the morning after that
+        assert!(sleep_period(night, parse_clock_time("01:00").unwrap(), parse_clock_time("00:30").unwrap()).is_none());
+    }
+}
@@ -0,0 +1,227 @@
+use crossterm::event::KeyCode;
+use ratatui::{layout::{Constraint, Layout}, style::{Color, Style, Stylize}, text, widgets::{Block, Paragraph, Row, Table, TableState}};
+use regex::Regex;
+
+use crate::{api::NewItem, app::App, modules::TextInput};
+
+use super::{input_functions, rendering::{self, single_line_input}, selection::SelectionModules, RenderableModule, SanitizedForm, SelectedModule};
+
+pub struct KitchenInventoryModule {}
+
+pub enum KitchenInventoryModules {
+    ItemList,
+    ItemEditing(SelectedInput)
+}
+
+#[derive(Clone, Default)]
+pub enum SelectedInput {
+    #[default]
+    Name
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum InventoryPane {
+    #[default]
+    InKitchen,
+    ToBuy
+}
+
+impl InventoryPane {
+    fn other(self) -> Self {
+        match self {
+            InventoryPane::InKitchen => InventoryPane::ToBuy,
+            InventoryPane::ToBuy => InventoryPane::InKitchen,
+        }
+    }
+
+    fn in_kitchen(&self) -> bool {
+        matches!(self, InventoryPane::InKitchen)
+    }
+}
+
+#[derive(Default)]
+pub struct KitchenInventoryModuleData {
+    item_list: Vec<InventoryItem>,
+    focused_pane: InventoryPane,
+    in_kitchen_table_state: TableState,
+    to_buy_table_state: TableState,
+    editable_item_data: ItemEntry
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct InventoryItem {
+    pub id: i64,
+    pub name: String,
+    pub in_kitchen: bool
+}
+
+#[derive(Default)]
+struct ItemEntry {
+    name: TextInput
+}
+
+impl SanitizedForm for ItemEntry {
+    fn verify_input(&self) -> bool {
+        self.name.valid
+    }
+
+    fn update_validity(&mut self) {
+        let name_regex = Regex::new(r"\S").unwrap();
+        self.name.valid = name_regex.is_match(&self.name.text);
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl KitchenInventoryModuleData {
+    fn pane_items(&self, pane: InventoryPane) -> Vec<&InventoryItem> {
+        self.item_list.iter().filter(|i| i.in_kitchen == pane.in_kitchen()).collect()
+    }
+
+    fn table_state(&mut self, pane: InventoryPane) -> &mut TableState {
+        match pane {
+            InventoryPane::InKitchen => &mut self.in_kitchen_table_state,
+            InventoryPane::ToBuy => &mut self.to_buy_table_state,
+        }
+    }
+
+    fn selected_item(&mut self) -> Option<InventoryItem> {
+        let pane = self.focused_pane;
+        let index = self.table_state(pane).selected()?;
+        self.pane_items(pane).get(index).map(|i| (*i).clone())
+    }
+}
+
+impl RenderableModule for KitchenInventoryModule {
+    fn input_handling(app: &mut App, key: crossterm::event::KeyEvent) {
+        if let SelectedModule::KitchenInventoryModule(module_status) = &app.selected_module {
+            let editable_data = &mut app.module_data.kitchen_inventory_module.editable_item_data;
+            match module_status {
+                KitchenInventoryModules::ItemList => {
+                    let inventory_module = &mut app.module_data.kitchen_inventory_module;
+                    let focused_pane = inventory_module.focused_pane;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Char('c') => app.selected_module = SelectedModule::KitchenInventoryModule(KitchenInventoryModules::ItemEditing(SelectedInput::default())),
+                        KeyCode::Char('r') => KitchenInventoryModule::refresh_item_list(app),
+                        KeyCode::Char('m') => {
+                            if let Some((item_id, item)) = inventory_module.selected_item().map(|i| (i.id, NewItem { name: i.name.clone(), in_kitchen: !i.in_kitchen })) {
+                                let user_id = app.module_data.user_module.selected_user.id;
+                                app.request("Moving item", move |client| {
+                                    let item = item.clone();
+                                    async move { client.update_item(user_id, item_id, &item).await }
+                                }, |app, _| KitchenInventoryModule::refresh_item_list(app));
+                            }
+                        },
+                        KeyCode::Char('d') => {
+                            if let Some(item_id) = inventory_module.selected_item().map(|i| i.id) {
+                                let user_id = app.module_data.user_module.selected_user.id;
+                                app.request("Deleting item", move |client| async move { client.delete_item(user_id, item_id).await }, |app, _| KitchenInventoryModule::refresh_item_list(app));
+                            }
+                        },
+                        KeyCode::Tab | KeyCode::Left | KeyCode::Right => inventory_module.focused_pane = focused_pane.other(),
+                        KeyCode::Up => inventory_module.table_state(focused_pane).select_previous(),
+                        KeyCode::Down => inventory_module.table_state(focused_pane).select_next(),
+                        _ => {}
+                    }
+                },
+                KitchenInventoryModules::ItemEditing(selected_input) => {
+                    let input = match selected_input {
+                        SelectedInput::Name => &mut editable_data.name,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::KitchenInventoryModule(KitchenInventoryModules::ItemList),
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // New items land in whichever pane currently has focus
+                            let in_kitchen = app.module_data.kitchen_inventory_module.focused_pane.in_kitchen();
+                            let item = NewItem { name: editable_data.name.text.trim().to_string(), in_kitchen };
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            app.request("Saving item", move |client| {
+                                let item = item.clone();
+                                async move { client.create_item(user_id, &item).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.kitchen_inventory_module.editable_item_data.clear_form();
+                                // Refresh Item List
+                                KitchenInventoryModule::refresh_item_list(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::KitchenInventoryModule(KitchenInventoryModules::ItemList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+            }
+        }
+    }
+
+    fn render(app: &mut App, frame: &mut ratatui::Frame) {
+        let rects = Layout::vertical([Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
+        let panes = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).split(rects[0]);
+
+        let inventory_module = &mut app.module_data.kitchen_inventory_module;
+
+        for (pane, title, rect) in [(InventoryPane::InKitchen, "In Kitchen", panes[0]), (InventoryPane::ToBuy, "Shopping List", panes[1])] {
+            let rows: Vec<Row> = inventory_module.pane_items(pane).iter().map(|i| Row::new(vec![i.name.clone()])).collect();
+            let row_count = rows.len();
+            let widths = [Constraint::Fill(1)];
+
+            let border_style = if inventory_module.focused_pane == pane { Style::new().fg(Color::Yellow) } else { Style::new() };
+            let items_table = Table::new(rows, widths).block(Block::bordered().title(title).border_style(border_style)).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+            let table_state = inventory_module.table_state(pane);
+            frame.render_stateful_widget(items_table, rect, table_state);
+
+            // Keep the selection inside the bounds of the pane's list
+            match table_state.selected() {
+                _ if row_count == 0 => table_state.select(None),
+                None => table_state.select_first(),
+                Some(i) if i >= row_count => table_state.select(Some(row_count - 1)),
+                Some(_) => {}
+            }
+        }
+
+        let footer_contents = text::Line::from("Esc: Back | Tab: Switch List | Arrow Keys: Navigation | c: Add Item | m: Move Item | d: Delete Item | r: Refresh");
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[1]);
+
+        if let SelectedModule::KitchenInventoryModule(KitchenInventoryModules::ItemEditing(_selected_input)) = &app.selected_module {
+            let area = rendering::popup_area_lengths(frame.area(), 37, 6);
+
+            let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3)]).split(area);
+
+            let editable_data = &app.module_data.kitchen_inventory_module.editable_item_data;
+
+            let title = match app.module_data.kitchen_inventory_module.focused_pane {
+                InventoryPane::InKitchen => "Item Name (In Kitchen)",
+                InventoryPane::ToBuy => "Item Name (Shopping List)",
+            };
+            let input_name = single_line_input(&editable_data.name, title);
+            frame.render_widget(input_name, rects[0]);
+
+            frame.set_cursor_position(rendering::cursor_position(&editable_data.name, rects[0]));
+
+            let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Item");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[1]);
+        }
+    }
+}
+
+impl KitchenInventoryModule {
+    pub fn refresh_item_list(app: &mut App) {
+        let user_id = app.module_data.user_module.selected_user.id;
+        app.request("Loading inventory", move |client| async move { client.inventory(user_id).await }, |app, items| {
+            let inventory_module = &mut app.module_data.kitchen_inventory_module;
+            inventory_module.item_list = items;
+            inventory_module.item_list.sort_by_key(|i| i.name.to_lowercase());
+        });
+    }
+}
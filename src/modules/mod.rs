@@ -1,25 +1,48 @@
+use financial::{FinancialModuleData, FinancialModules};
+use health::{HealthModuleData, HealthModules};
+use kitchen_inventory::{KitchenInventoryModuleData, KitchenInventoryModules};
+use notebooks::{NotebookModuleData, NotebookModules};
 use ratatui::{crossterm::event::KeyEvent, Frame};
+use selection::{SelectionModuleData, SelectionModules};
 use servers::{ServerModuleData, ServerModules};
 use users::{UserModuleData, UserModules};
 
+use unicode_segmentation::UnicodeSegmentation;
+
 use crate::app::App;
 
+pub mod financial;
+pub mod health;
+pub mod kitchen_inventory;
+pub mod notebooks;
+pub mod selection;
 pub mod servers;
 pub mod users;
 
+#[allow(clippy::enum_variant_names)]
 pub enum SelectedModule {
     ServerModule(ServerModules),
     UserModule(UserModules),
+    SelectionModule(SelectionModules),
+    NotebookModule(NotebookModules),
+    KitchenInventoryModule(KitchenInventoryModules),
+    FinancialModule(FinancialModules),
+    HealthModule(HealthModules),
 }
 
 #[derive(Default)]
 pub struct ModuleData {
     pub server_module: ServerModuleData,
-    user_module: UserModuleData
+    pub user_module: UserModuleData,
+    pub selection_module: SelectionModuleData,
+    pub notebook_module: NotebookModuleData,
+    pub kitchen_inventory_module: KitchenInventoryModuleData,
+    pub financial_module: FinancialModuleData,
+    pub health_module: HealthModuleData
 }
 
 pub trait RenderableModule {
-    async fn input_handling(app: &mut App, key: KeyEvent) -> ();
+    fn input_handling(app: &mut App, key: KeyEvent) -> ();
     fn render(app: &mut App, frame: &mut Frame) -> ();
 }
 
@@ -32,8 +55,26 @@ pub trait SanitizedForm {
 #[derive(Clone)]
 pub struct TextInput {
     pub text: String,
+    /// Cursor position counted in grapheme clusters, so an emoji or a letter with combining
+    /// accents is stepped over as one character
     pub index: usize,
-    pub valid: bool
+    pub valid: bool,
+    history: History
+}
+
+/// Earlier and undone states of a `TextInput`, for Ctrl-Z and Ctrl-Y.
+#[derive(Clone, Default)]
+struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// Set while characters are being typed, the next one joins the same undo step
+    typing: bool
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    text: String,
+    index: usize
 }
 
 impl TextInput {
@@ -42,22 +83,39 @@ impl TextInput {
             text,
             index: 0,
             valid: false,
+            history: History::default(),
         } 
     }
+
+    pub fn cursor_to_end(&mut self) {
+        self.index = self.text.graphemes(true).count();
+    }
+
+    /// Ends the current run of typing, so the next character typed is undone separately. Forms
+    /// call this when focus moves to another input.
+    pub fn end_typing(&mut self) {
+        self.history.typing = false;
+    }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for TextInput {
     fn default() -> Self {
         Self {
             text: Default::default(),
             index: Default::default(),
-            valid: false
+            valid: false,
+            history: History::default()
         }
     }
 }
 
 pub mod rendering {
-    use ratatui::{layout::{Constraint, Flex, Layout, Rect}, style::{Color, Style}, widgets::{Block, Paragraph}};
+    use ratatui::{buffer::Buffer, layout::{Constraint, Flex, Layout, Position, Rect}, style::{Color, Style}, widgets::{Block, Paragraph, Widget}};
+    use unicode_segmentation::UnicodeSegmentation;
+    use unicode_width::UnicodeWidthStr;
+
+    use super::TextInput;
 
     pub fn popup_area_percent(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
         let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
@@ -75,58 +133,441 @@ pub mod rendering {
         area
     }
 
-    pub fn single_line_input<'a>(content: &'a str, title: &'a str, valid: bool) -> Paragraph<'a> {
-        Paragraph::new(content).block(Block::bordered().title(title)).style(Style::new().fg(if valid { Color::White } else { Color::Red }))
+    /// A bordered text input, red while its contents are invalid.
+    pub struct SingleLineInput<'a> {
+        input: &'a TextInput,
+        title: &'a str
+    }
+
+    pub fn single_line_input<'a>(input: &'a TextInput, title: &'a str) -> SingleLineInput<'a> {
+        SingleLineInput { input, title }
+    }
+
+    /// The part of the text that is shown when it is wider than the input, and the column of the
+    /// cursor inside the border. Text scrolls only once the cursor would leave the input, and
+    /// whole graphemes are hidden so a wide character is never cut in half.
+    fn visible_text(input: &TextInput, area: Rect) -> (&str, u16) {
+        let width = area.width.saturating_sub(2) as usize;
+        let cursor_column: usize = input.text.graphemes(true).take(input.index).map(|grapheme| grapheme.width()).sum();
+        let hidden_columns = (cursor_column + 1).saturating_sub(width);
+
+        let mut start = 0;
+        let mut skipped = 0;
+        for (index, grapheme) in input.text.grapheme_indices(true) {
+            if skipped >= hidden_columns {
+                break;
+            }
+            skipped += grapheme.width();
+            start = index + grapheme.len();
+        }
+        (&input.text[start..], cursor_column.saturating_sub(skipped) as u16)
+    }
+
+    /// Where the terminal cursor goes for `input` rendered in `area`.
+    pub fn cursor_position(input: &TextInput, area: Rect) -> Position {
+        let (_, column) = visible_text(input, area);
+        Position::new(area.x + 1 + column, area.y + 1)
+    }
+
+    impl Widget for SingleLineInput<'_> {
+        fn render(self, area: Rect, buf: &mut Buffer) {
+            let (text, _) = visible_text(self.input, area);
+            Paragraph::new(text)
+                .block(Block::bordered().title(self.title))
+                .style(Style::new().fg(if self.input.valid { Color::White } else { Color::Red }))
+                .render(area, buf);
+        }
     }
 }
 
 pub mod input_functions {
-    use crate::modules::TextInput;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use unicode_segmentation::UnicodeSegmentation;
+
+    use crate::modules::{Snapshot, TextInput};
+
+    /// How many undo steps each input keeps.
+    pub(super) const HISTORY_LIMIT: usize = 100;
+
+    /// Applies an editing key to `input`. Every form sends the keys it does not use itself here,
+    /// so they all edit text the same way.
+    pub fn handle_key(input: &mut TextInput, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        // Moving the cursor ends a run of typing, so what is typed afterwards is undone separately
+        if !matches!(key.code, KeyCode::Char(_)) {
+            input.end_typing();
+        }
+        match key.code {
+            KeyCode::Char('z') if ctrl => undo(input),
+            KeyCode::Char('y') if ctrl => redo(input),
+            KeyCode::Left if ctrl => move_word_left(input),
+            KeyCode::Right if ctrl => move_word_right(input),
+            KeyCode::Left => move_cursor_left(input),
+            KeyCode::Right => move_cursor_right(input),
+            KeyCode::Home => input.index = 0,
+            KeyCode::End => input.cursor_to_end(),
+            KeyCode::Backspace => delete_char(input),
+            KeyCode::Delete => delete_char_forward(input),
+            KeyCode::Char('w') if ctrl => delete_word(input),
+            KeyCode::Char('u') if ctrl => delete_to_start(input),
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => enter_char(input, c),
+            _ => {}
+        }
+    }
+
+    /// Saves the text as it is before an edit so the edit can be undone. Characters typed one
+    /// after another are saved once, which makes the whole run a single undo step.
+    fn record(input: &mut TextInput, typing: bool) {
+        let history = &mut input.history;
+        if !(typing && history.typing) {
+            history.undo.push(Snapshot { text: input.text.clone(), index: input.index });
+            if history.undo.len() > HISTORY_LIMIT {
+                history.undo.remove(0);
+            }
+        }
+        history.redo.clear();
+        history.typing = typing;
+    }
+
+    fn undo(input: &mut TextInput) {
+        if let Some(snapshot) = input.history.undo.pop() {
+            let current = Snapshot { text: std::mem::replace(&mut input.text, snapshot.text), index: input.index };
+            input.index = snapshot.index;
+            input.history.redo.push(current);
+        }
+        input.history.typing = false;
+    }
+
+    fn redo(input: &mut TextInput) {
+        if let Some(snapshot) = input.history.redo.pop() {
+            let current = Snapshot { text: std::mem::replace(&mut input.text, snapshot.text), index: input.index };
+            input.index = snapshot.index;
+            input.history.undo.push(current);
+        }
+        input.history.typing = false;
+    }
+
+    fn graphemes(text: &str) -> Vec<&str> {
+        text.graphemes(true).collect()
+    }
+
+    /// Whether a grapheme is part of a word, which is what word motions jump over.
+    fn is_word(grapheme: &str) -> bool {
+        grapheme.chars().next().is_some_and(char::is_alphanumeric)
+    }
 
-    pub fn move_cursor_left(input: &mut TextInput) {
+    fn is_space(grapheme: &str) -> bool {
+        grapheme.chars().all(char::is_whitespace)
+    }
+
+    fn move_cursor_left(input: &mut TextInput) {
         let cursor_moved_left = input.index.saturating_sub(1);
-        input.index = clamp_cursor(&mut input.text, cursor_moved_left);
+        input.index = clamp_cursor(&input.text, cursor_moved_left);
     }
 
-    pub fn move_cursor_right(input: &mut TextInput) {
+    fn move_cursor_right(input: &mut TextInput) {
         let cursor_moved_right = input.index.saturating_add(1);
-        input.index = clamp_cursor(&mut input.text, cursor_moved_right);
+        input.index = clamp_cursor(&input.text, cursor_moved_right);
+    }
+
+    /// Jumps to the start of the word before the cursor, words being runs of letters and digits.
+    fn move_word_left(input: &mut TextInput) {
+        let graphemes = graphemes(&input.text);
+        let mut index = input.index.min(graphemes.len());
+        while index > 0 && !is_word(graphemes[index - 1]) {
+            index -= 1;
+        }
+        while index > 0 && is_word(graphemes[index - 1]) {
+            index -= 1;
+        }
+        input.index = index;
+    }
+
+    /// Jumps to the end of the word after the cursor.
+    fn move_word_right(input: &mut TextInput) {
+        let graphemes = graphemes(&input.text);
+        let mut index = input.index.min(graphemes.len());
+        while index < graphemes.len() && !is_word(graphemes[index]) {
+            index += 1;
+        }
+        while index < graphemes.len() && is_word(graphemes[index]) {
+            index += 1;
+        }
+        input.index = index;
     }
 
-    pub fn enter_char(input: &mut TextInput, new_char: char) {
+    fn enter_char(input: &mut TextInput, new_char: char) {
+        record(input, true);
         let index = byte_index(input);
         input.text.insert(index, new_char);
-        move_cursor_right(input);
+        // A combining mark joins the grapheme before it instead of starting a new one, in which
+        // case the cursor stays where it is
+        input.index = input.text[..index + new_char.len_utf8()].graphemes(true).count();
     }
 
-    fn byte_index(input: &mut TextInput) -> usize {
+    fn byte_index(input: &TextInput) -> usize {
         input.text
-            .char_indices()
+            .grapheme_indices(true)
             .map(|(i, _)| i)
             .nth(input.index)
             .unwrap_or(input.text.len())
     }
 
-    pub fn delete_char(input: &mut TextInput) {
+    /// Removes the graphemes between `start` and `end`, leaving the cursor where they were.
+    fn delete_range(input: &mut TextInput, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        record(input, false);
+        let graphemes = graphemes(&input.text);
+        let text: String = graphemes[..start].iter().chain(&graphemes[end..]).copied().collect();
+        input.text = text;
+        input.index = clamp_cursor(&input.text, start);
+    }
+
+    fn delete_char(input: &mut TextInput) {
         let is_not_cursor_leftmost = input.index != 0;
         if is_not_cursor_leftmost {
+            delete_range(input, input.index - 1, input.index);
+        }
+    }
+
+    fn delete_char_forward(input: &mut TextInput) {
+        if input.index < input.text.graphemes(true).count() {
+            delete_range(input, input.index, input.index + 1);
+        }
+    }
+
+    /// Deletes back to the previous whitespace like Ctrl-W in a shell, so a whole path or
+    /// address goes at once.
+    fn delete_word(input: &mut TextInput) {
+        let graphemes = graphemes(&input.text);
+        let end = input.index.min(graphemes.len());
+        let mut start = end;
+        while start > 0 && is_space(graphemes[start - 1]) {
+            start -= 1;
+        }
+        while start > 0 && !is_space(graphemes[start - 1]) {
+            start -= 1;
+        }
+        delete_range(input, start, end);
+    }
+
+    fn delete_to_start(input: &mut TextInput) {
+        delete_range(input, 0, input.index);
+    }
+
+    fn clamp_cursor(input_string: &str, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, input_string.graphemes(true).count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{buffer::Buffer, layout::{Position, Rect}, widgets::Widget};
+    use unicode_width::UnicodeWidthStr;
 
-            let current_index = input.index.clone();
-            let from_left_to_current_index = current_index - 1;
+    use super::{input_functions::{handle_key, HISTORY_LIMIT}, rendering::{cursor_position, single_line_input}, TextInput};
 
-            let before_char_to_delete = input.text.chars().take(from_left_to_current_index);
-            let after_char_to_delete = input.text.chars().skip(current_index);
+    fn press(input: &mut TextInput, code: KeyCode) {
+        handle_key(input, KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_text(input: &mut TextInput, text: &str) {
+        for c in text.chars() {
+            press(input, KeyCode::Char(c));
+        }
+    }
+
+    fn ctrl(input: &mut TextInput, c: char) {
+        handle_key(input, KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    fn input_with(text: &str, index: usize) -> TextInput {
+        TextInput { text: text.to_string(), index, ..TextInput::default() }
+    }
 
-            input.text = before_char_to_delete.chain(after_char_to_delete).collect();
-            move_cursor_left(input);
+    /// The text shown inside the border of `input` rendered `width` columns wide.
+    fn shown_text(input: &TextInput, width: u16) -> String {
+        let area = Rect::new(0, 0, width, 3);
+        let mut buffer = Buffer::empty(area);
+        single_line_input(input, "").render(area, &mut buffer);
+        let mut text = String::new();
+        let mut x = 1;
+        while x < width - 1 {
+            // The cell after a wide character is left blank
+            let symbol = buffer[(x, 1)].symbol();
+            text.push_str(symbol);
+            x += symbol.width().max(1) as u16;
         }
+        text.trim_end().to_string()
     }
 
-    fn clamp_cursor(input_string: &mut String, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, input_string.chars().count())
+    #[test]
+    fn combining_mark_joins_the_previous_character() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "e\u{301}");
+        assert_eq!(input.text, "e\u{301}");
+        assert_eq!(input.index, 1);
+
+        type_text(&mut input, "x");
+        assert_eq!(input.text, "e\u{301}x");
+        assert_eq!(input.index, 2);
+
+        // Typed in the middle, the mark goes onto the character before the cursor
+        let mut input = input_with("ab", 1);
+        type_text(&mut input, "\u{308}");
+        assert_eq!(input.text, "a\u{308}b");
+        assert_eq!(input.index, 1);
     }
 
-    fn reset_cursor(input_index: &mut usize) {
-        *input_index = 0;
+    #[test]
+    fn backspace_removes_whole_emoji() {
+        let mut input = input_with("a👍🏽b🇳🇱", 4);
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.text, "a👍🏽b");
+        press(&mut input, KeyCode::Backspace);
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.text, "a");
+        assert_eq!(input.index, 1);
+    }
+
+    #[test]
+    fn zwj_sequences_are_deleted_as_one() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let mut input = input_with(&format!("x{}y", family), 1);
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.text, "xy");
+        assert_eq!(input.index, 1);
+
+        let mut input = input_with(&format!("x{}y", family), 2);
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.text, "xy");
+        assert_eq!(input.index, 1);
+
+        // Nothing after the cursor, nothing to delete
+        let mut input = input_with(family, 1);
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.text, family);
+    }
+
+    #[test]
+    fn cursor_counts_wide_characters_twice() {
+        let area = Rect::new(5, 2, 10, 3);
+        let input = input_with("日本語", 2);
+        assert_eq!(cursor_position(&input, area), Position::new(5 + 1 + 4, 3));
+        assert_eq!(shown_text(&input, 10), "日本語");
+    }
+
+    #[test]
+    fn cjk_text_scrolls_by_whole_characters() {
+        // 8 columns inside the border, the cursor needs the last one once it is at the end
+        let area = Rect::new(0, 0, 10, 3);
+
+        // The cursor right at the edge still fits without scrolling
+        let input = input_with("日本語テキスト", 3);
+        assert_eq!(cursor_position(&input, area), Position::new(1 + 6, 1));
+        assert_eq!(shown_text(&input, 10), "日本語テ");
+
+        // One more step hides the first character rather than half of it
+        let input = input_with("日本語テキスト", 4);
+        assert_eq!(cursor_position(&input, area), Position::new(1 + 6, 1));
+        assert_eq!(shown_text(&input, 10), "本語テキ");
+
+        let input = input_with("日本語テキスト", 7);
+        assert_eq!(cursor_position(&input, area), Position::new(1 + 6, 1));
+        assert_eq!(shown_text(&input, 10), "キスト");
+
+        // Moving back to the start scrolls the text back
+        let input = input_with("日本語テキスト", 0);
+        assert_eq!(cursor_position(&input, area), Position::new(1, 1));
+        assert_eq!(shown_text(&input, 10), "日本語テ");
+    }
+
+    #[test]
+    fn mixed_width_text_scrolls_past_a_wide_character() {
+        let area = Rect::new(0, 0, 6, 3);
+        let input = input_with("ab日本", 4);
+        assert_eq!(cursor_position(&input, area), Position::new(1 + 2, 1));
+        assert_eq!(shown_text(&input, 6), "本");
+    }
+
+    #[test]
+    fn typed_run_is_one_undo_step() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "abc");
+        ctrl(&mut input, 'z');
+        assert_eq!((input.text.as_str(), input.index), ("", 0));
+        ctrl(&mut input, 'y');
+        assert_eq!((input.text.as_str(), input.index), ("abc", 3));
+    }
+
+    #[test]
+    fn moving_or_deleting_starts_a_new_step() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "ab");
+        press(&mut input, KeyCode::Left);
+        type_text(&mut input, "x");
+        press(&mut input, KeyCode::End);
+        press(&mut input, KeyCode::Backspace);
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.text, "a");
+
+        for expected in ["ax", "axb", "ab", ""] {
+            ctrl(&mut input, 'z');
+            assert_eq!(input.text, expected);
+        }
+        // Nothing left to undo
+        ctrl(&mut input, 'z');
+        assert_eq!(input.text, "");
+    }
+
+    #[test]
+    fn end_typing_splits_the_run() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "ab");
+        // What a form does when Up or Down moves focus away and back again
+        input.end_typing();
+        type_text(&mut input, "cd");
+        ctrl(&mut input, 'z');
+        assert_eq!(input.text, "ab");
+        ctrl(&mut input, 'z');
+        assert_eq!(input.text, "");
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "ab");
+        ctrl(&mut input, 'z');
+        type_text(&mut input, "x");
+        ctrl(&mut input, 'y');
+        assert_eq!(input.text, "x");
+
+        ctrl(&mut input, 'z');
+        assert_eq!(input.text, "");
+        ctrl(&mut input, 'y');
+        assert_eq!(input.text, "x");
+    }
+
+    #[test]
+    fn history_keeps_the_latest_steps() {
+        let mut input = TextInput::default();
+        let edits = HISTORY_LIMIT + 10;
+        for _ in 0..edits {
+            type_text(&mut input, "a");
+            input.end_typing();
+        }
+        for _ in 0..edits {
+            ctrl(&mut input, 'z');
+        }
+        // The oldest steps were dropped, so undo stops short of the empty input
+        assert_eq!(input.text.len(), edits - HISTORY_LIMIT);
+
+        for _ in 0..edits {
+            ctrl(&mut input, 'y');
+        }
+        assert_eq!(input.text.len(), edits);
     }
 }
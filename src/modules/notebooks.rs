@@ -0,0 +1,294 @@
+use chrono::{DateTime, Utc};
+use crossterm::event::KeyCode;
+use ratatui::{layout::{Constraint, Layout}, style::{Style, Stylize}, text, widgets::{Block, Paragraph, Row, Table, TableState}};
+use regex::Regex;
+
+use crate::{api::NewNotebook, app::App, modules::TextInput};
+
+use super::{input_functions, rendering::{self, single_line_input}, selection::SelectionModules, RenderableModule, SanitizedForm, SelectedModule};
+
+pub struct NotebookModule {}
+
+pub enum NotebookModules {
+    NotebookList,
+    NotebookEditing(SelectedInput)
+}
+
+#[derive(Clone, Default)]
+pub enum SelectedInput {
+    #[default]
+    Name,
+    Description
+}
+
+impl SelectedInput {
+    fn next(self) -> Self {
+        match self {
+            SelectedInput::Name => SelectedInput::Description,
+            SelectedInput::Description => SelectedInput::Name,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            SelectedInput::Name => SelectedInput::Description,
+            SelectedInput::Description => SelectedInput::Name,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Created,
+    LastUsed
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Created,
+            SortKey::Created => SortKey::LastUsed,
+            SortKey::LastUsed => SortKey::Name,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending
+}
+
+impl SortDirection {
+    fn toggle(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "▲",
+            SortDirection::Descending => "▼",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct NotebookModuleData {
+    notebook_list: Vec<Notebook>,
+    notebook_table_state: TableState,
+    sort_key: SortKey,
+    sort_direction: SortDirection,
+    editable_notebook_data: NotebookEntry
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct Notebook {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub created: DateTime<Utc>,
+    pub last_used: DateTime<Utc>
+}
+
+#[derive(Default)]
+struct NotebookEntry {
+    name: TextInput,
+    description: TextInput
+}
+
+impl NotebookEntry {
+    fn request(&self) -> NewNotebook {
+        NewNotebook { name: self.name.text.trim().to_string(), description: self.description.text.trim().to_string() }
+    }
+}
+
+impl SanitizedForm for NotebookEntry {
+    fn verify_input(&self) -> bool {
+        self.name.valid && self.description.valid
+    }
+
+    fn update_validity(&mut self) {
+        let name_regex = Regex::new(r"\S").unwrap();
+        self.name.valid = name_regex.is_match(&self.name.text);
+        // Descriptions are optional
+        self.description.valid = true;
+    }
+
+    fn clear_form(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl RenderableModule for NotebookModule {
+    fn input_handling(app: &mut App, key: crossterm::event::KeyEvent) {
+        if let SelectedModule::NotebookModule(module_status) = &app.selected_module {
+            let editable_data = &mut app.module_data.notebook_module.editable_notebook_data;
+            match module_status {
+                NotebookModules::NotebookList => {
+                    let notebook_module = &mut app.module_data.notebook_module;
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList),
+                        KeyCode::Char('c') => {
+                            notebook_module.editable_notebook_data.update_validity();
+                            app.selected_module = SelectedModule::NotebookModule(NotebookModules::NotebookEditing(SelectedInput::default()));
+                        },
+                        KeyCode::Char('r') => NotebookModule::refresh_notebook_list(app),
+                        KeyCode::Char('u') => {
+                            if let Some(notebook) = notebook_module.notebook_table_state.selected().and_then(|index| notebook_module.notebook_list.get(index)) {
+                                let user_id = app.module_data.user_module.selected_user.id;
+                                let notebook_id = notebook.id;
+                                app.request("Marking notebook used", move |client| async move { client.mark_notebook_used(user_id, notebook_id).await }, |app, _| NotebookModule::refresh_notebook_list(app));
+                            }
+                        },
+                        KeyCode::Char('s') => {
+                            notebook_module.sort_key = notebook_module.sort_key.next();
+                            notebook_module.sort_notebooks();
+                        },
+                        KeyCode::Char('d') => {
+                            notebook_module.sort_direction = notebook_module.sort_direction.toggle();
+                            notebook_module.sort_notebooks();
+                        },
+                        KeyCode::Up => notebook_module.notebook_table_state.select_previous(),
+                        KeyCode::Down => notebook_module.notebook_table_state.select_next(),
+                        _ => {}
+                    }
+                },
+                NotebookModules::NotebookEditing(selected_input) => {
+                    let input = match selected_input {
+                        SelectedInput::Name => &mut editable_data.name,
+                        SelectedInput::Description => &mut editable_data.description,
+                    };
+
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::NotebookModule(NotebookModules::NotebookList),
+                        KeyCode::Up => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::NotebookModule(NotebookModules::NotebookEditing(selected_input.clone().prev()));
+                        },
+                        KeyCode::Down => {
+                            input.end_typing();
+                            app.selected_module = SelectedModule::NotebookModule(NotebookModules::NotebookEditing(selected_input.clone().next()));
+                        },
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Send to server
+                            let user_id = app.module_data.user_module.selected_user.id;
+                            let notebook = editable_data.request();
+                            app.request("Saving notebook", move |client| {
+                                let notebook = notebook.clone();
+                                async move { client.create_notebook(user_id, &notebook).await }
+                            }, |app, _| {
+                                // Clear form fields
+                                app.module_data.notebook_module.editable_notebook_data.clear_form();
+                                // Refresh Notebook List
+                                NotebookModule::refresh_notebook_list(app);
+                                // Change state back to list
+                                app.selected_module = SelectedModule::NotebookModule(NotebookModules::NotebookList);
+                            });
+                            return;
+                        }
+                        _ => {}
+                    }
+                    editable_data.update_validity();
+                },
+            }
+        }
+    }
+
+    fn render(app: &mut App, frame: &mut ratatui::Frame) {
+        let rects = Layout::vertical([Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
+
+        let notebook_module = &mut app.module_data.notebook_module;
+
+        let column_title = |title: &str, key: SortKey| {
+            if key == notebook_module.sort_key {
+                format!("{} {}", title, notebook_module.sort_direction.symbol())
+            } else {
+                title.to_string()
+            }
+        };
+        let header = Row::new(vec![column_title("Name", SortKey::Name), "Description".to_string(), column_title("Created", SortKey::Created), column_title("Last Used", SortKey::LastUsed)]).bold();
+
+        let rows: Vec<Row> = notebook_module.notebook_list.iter().map(|n| Row::new(vec![
+            n.name.clone(),
+            n.description.clone(),
+            n.created.format("%Y-%m-%d %H:%M").to_string(),
+            n.last_used.format("%Y-%m-%d %H:%M").to_string(),
+        ])).collect();
+        let widths = [Constraint::Length(20), Constraint::Fill(1), Constraint::Length(18), Constraint::Length(18)];
+
+        let title = format!("Notebooks ({})", app.module_data.user_module.selected_user.name);
+        let notebooks_table = Table::new(rows, widths).header(header).block(Block::bordered().title(title)).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(notebooks_table, rects[0], &mut notebook_module.notebook_table_state);
+
+        if !notebook_module.notebook_list.is_empty() && notebook_module.notebook_table_state.selected().is_none() {
+            notebook_module.notebook_table_state.select_next();
+        }
+
+        let footer_contents = text::Line::from("Esc: Back | Arrow Keys: Navigation | c: Create New Notebook | u: Mark as Used | r: Refresh | s: Cycle Sort Key | d: Toggle Sort Direction");
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[1]);
+
+        if let SelectedModule::NotebookModule(NotebookModules::NotebookEditing(selected_input)) = &app.selected_module {
+            let area = rendering::popup_area_lengths(frame.area(), 50, 9);
+
+            let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3), Constraint::Length(3)]).split(area);
+
+            let editable_data = &app.module_data.notebook_module.editable_notebook_data;
+
+            let input_name = single_line_input(&editable_data.name, "Notebook Name");
+            frame.render_widget(input_name, rects[0]);
+
+            let input_description = single_line_input(&editable_data.description, "Description");
+            frame.render_widget(input_description, rects[1]);
+
+            let (rect, input) = match selected_input {
+                SelectedInput::Name => (0, &editable_data.name),
+                SelectedInput::Description => (1, &editable_data.description),
+            };
+            frame.set_cursor_position(rendering::cursor_position(input, rects[rect]));
+
+            let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Notebook");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[2]);
+        }
+    }
+}
+
+impl NotebookModuleData {
+    fn sort_notebooks(&mut self) {
+        match self.sort_key {
+            SortKey::Name => self.notebook_list.sort_by_key(|n| n.name.to_lowercase()),
+            SortKey::Created => self.notebook_list.sort_by_key(|n| n.created),
+            SortKey::LastUsed => self.notebook_list.sort_by_key(|n| n.last_used),
+        }
+        if let SortDirection::Descending = self.sort_direction {
+            self.notebook_list.reverse();
+        }
+    }
+}
+
+impl NotebookModule {
+    pub fn refresh_notebook_list(app: &mut App) {
+        let user_id = app.module_data.user_module.selected_user.id;
+        app.request("Loading notebooks", move |client| async move { client.notebooks(user_id).await }, |app, notebooks| {
+            let notebook_module = &mut app.module_data.notebook_module;
+            notebook_module.notebook_list = notebooks;
+            notebook_module.sort_notebooks();
+
+            // Keep the selection inside the bounds of the new list
+            if notebook_module.notebook_list.is_empty() {
+                notebook_module.notebook_table_state.select(None);
+            } else if notebook_module.notebook_table_state.selected().is_some_and(|i| i >= notebook_module.notebook_list.len()) {
+                notebook_module.notebook_table_state.select_last();
+            }
+        });
+    }
+}
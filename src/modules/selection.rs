@@ -0,0 +1,95 @@
+use crossterm::event::KeyCode;
+use ratatui::{layout::{Constraint, Layout}, style::{Style, Stylize}, text, widgets::{Block, List, ListState, Paragraph}};
+
+use crate::app::App;
+
+use super::{financial::{FinancialModule, FinancialModules}, health::{HealthModule, HealthModules}, kitchen_inventory::{KitchenInventoryModule, KitchenInventoryModules}, notebooks::{NotebookModule, NotebookModules}, users::UserModules, RenderableModule, SelectedModule};
+
+pub struct SelectionModule {}
+
+pub enum SelectionModules {
+    ModuleList
+}
+
+#[derive(Clone, Copy)]
+pub enum AvailableModule {
+    Notebooks,
+    KitchenInventory,
+    Financial,
+    Health
+}
+
+impl AvailableModule {
+    const ALL: [AvailableModule; 4] = [AvailableModule::Notebooks, AvailableModule::KitchenInventory, AvailableModule::Financial, AvailableModule::Health];
+
+    fn title(&self) -> &'static str {
+        match self {
+            AvailableModule::Notebooks => "Notebooks",
+            AvailableModule::KitchenInventory => "Kitchen Inventory",
+            AvailableModule::Financial => "Financial",
+            AvailableModule::Health => "Health",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SelectionModuleData {
+    module_list_state: ListState
+}
+
+impl RenderableModule for SelectionModule {
+    fn input_handling(app: &mut App, key: crossterm::event::KeyEvent) {
+        if let SelectedModule::SelectionModule(module_status) = &app.selected_module {
+            match module_status {
+                SelectionModules::ModuleList => {
+                    match key.code {
+                        KeyCode::Esc => app.selected_module = SelectedModule::UserModule(UserModules::UserList),
+                        KeyCode::Enter => {
+                            // select_next can run past the end until the next render clamps it
+                            if let Some(module) = app.module_data.selection_module.module_list_state.selected().and_then(|index| AvailableModule::ALL.get(index)) {
+                                match module {
+                                    AvailableModule::Notebooks => {
+                                        app.selected_module = SelectedModule::NotebookModule(NotebookModules::NotebookList);
+                                        NotebookModule::refresh_notebook_list(app);
+                                    },
+                                    AvailableModule::KitchenInventory => {
+                                        app.selected_module = SelectedModule::KitchenInventoryModule(KitchenInventoryModules::ItemList);
+                                        KitchenInventoryModule::refresh_item_list(app);
+                                    },
+                                    AvailableModule::Financial => {
+                                        app.selected_module = SelectedModule::FinancialModule(FinancialModules::AccountList);
+                                        FinancialModule::open_ledger(app);
+                                    },
+                                    AvailableModule::Health => {
+                                        app.selected_module = SelectedModule::HealthModule(HealthModules::WeightList);
+                                        HealthModule::refresh_health_data(app);
+                                    }
+                                }
+                            }
+                        },
+                        KeyCode::Up => app.module_data.selection_module.module_list_state.select_previous(),
+                        KeyCode::Down => app.module_data.selection_module.module_list_state.select_next(),
+                        _ => {}
+                    }
+                },
+            }
+        }
+    }
+
+    fn render(app: &mut App, frame: &mut ratatui::Frame) {
+        let rects = Layout::vertical([Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
+
+        let title = format!("Select Module ({})", app.module_data.user_module.selected_user.name);
+        let modules = List::new(AvailableModule::ALL.iter().map(|m| m.title())).block(Block::bordered().title(title)).highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(modules, rects[0], &mut app.module_data.selection_module.module_list_state);
+
+        if app.module_data.selection_module.module_list_state.selected().is_none() {
+            app.module_data.selection_module.module_list_state.select_first();
+        }
+
+        let footer_contents = text::Line::from("Esc: Back to Users | Enter: Open Module | Arrow Keys: Navigation");
+        let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+        frame.render_widget(keybinds, rects[1]);
+    }
+}
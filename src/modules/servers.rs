@@ -1,24 +1,34 @@
+use std::{net::{Ipv4Addr, Ipv6Addr}, path::{Path, PathBuf}, time::{Duration, Instant}};
+
 use crossterm::event::KeyCode;
-use ratatui::{crossterm::event, layout::{Constraint, Layout, Position}, style::{Style, Stylize}, text, widgets::{Block, Paragraph, Row, Table, TableState}};
-use regex::Regex;
+use ratatui::{crossterm::event, layout::{Constraint, Layout}, style::{Color, Style, Stylize}, text, widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap}};
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{app::App, config::config::Server};
+use crate::{api::{ApiClient, ApiError}, app::App, config::config::{Scheme, Server, ServerAuth}};
 use crate::modules::TextInput;
 
-use super::{input_functions, rendering::{self, single_line_input}, users::UserModules, RenderableModule, SanitizedForm, SelectedModule};
+use super::{input_functions, rendering::{self, single_line_input}, users::{UserModule, UserModules}, RenderableModule, SanitizedForm, SelectedModule};
 
+#[allow(clippy::enum_variant_names)]
 pub enum ServerModules {
     ServerList,
-    ServerEditing(SelectedInput)
+    ServerEditing(SelectedInput),
+    ServerDeleting
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum SelectedInput {
     Name,
     Address,
-    Port
+    Port,
+    Scheme,
+    Auth,
+    Certificate,
+    Username,
+    Secret
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for SelectedInput {
     fn default() -> Self {
         SelectedInput::Name
@@ -30,15 +40,25 @@ impl SelectedInput {
         match self {
             SelectedInput::Name => SelectedInput::Address,
             SelectedInput::Address => SelectedInput::Port,
-            SelectedInput::Port => SelectedInput::Name,
+            SelectedInput::Port => SelectedInput::Scheme,
+            SelectedInput::Scheme => SelectedInput::Auth,
+            SelectedInput::Auth => SelectedInput::Certificate,
+            SelectedInput::Certificate => SelectedInput::Username,
+            SelectedInput::Username => SelectedInput::Secret,
+            SelectedInput::Secret => SelectedInput::Name,
         }
     }
 
     fn prev(self) -> Self {
         match self {
-            SelectedInput::Name => SelectedInput::Port,
+            SelectedInput::Name => SelectedInput::Secret,
             SelectedInput::Address => SelectedInput::Name,
             SelectedInput::Port => SelectedInput::Address,
+            SelectedInput::Scheme => SelectedInput::Port,
+            SelectedInput::Auth => SelectedInput::Scheme,
+            SelectedInput::Certificate => SelectedInput::Auth,
+            SelectedInput::Username => SelectedInput::Certificate,
+            SelectedInput::Secret => SelectedInput::Username,
         }
     }
 }
@@ -49,59 +69,354 @@ pub struct ServerModuleData {
     servers_table_state: TableState,
     pub selected_server: ServerEntry,
     editable_server_data: ServerEntry,
+    /// Server being edited, or `None` when the form adds a new one
+    editing_index: Option<usize>,
+    /// Shown under the server list until the next key press
+    pub notice: Option<String>
+}
+
+impl ServerModuleData {
+    /// The selected row, or `None` when `select_next` moved past the end of the list and the
+    /// table has not been rendered since.
+    fn selected_list_index(&self) -> Option<usize> {
+        self.servers_table_state.selected().filter(|&index| index < self.server_list.len())
+    }
+}
+
+/// The kinds of auth a server entry picks between, the credentials have their own inputs.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+enum AuthMethod {
+    #[default]
+    None,
+    Token,
+    Password
+}
+
+impl AuthMethod {
+    fn next(self) -> Self {
+        match self {
+            AuthMethod::None => AuthMethod::Token,
+            AuthMethod::Token => AuthMethod::Password,
+            AuthMethod::Password => AuthMethod::None,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            AuthMethod::None => AuthMethod::Password,
+            AuthMethod::Token => AuthMethod::None,
+            AuthMethod::Password => AuthMethod::Token,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuthMethod::None => "none",
+            AuthMethod::Token => "token",
+            AuthMethod::Password => "password",
+        }
+    }
 }
 
 #[derive(Clone, Default)]
 pub struct ServerEntry {
     name: TextInput,
     pub address: TextInput,
-    pub port: TextInput
+    pub port: TextInput,
+    scheme: Scheme,
+    /// Optional path to a PEM file
+    certificate: TextInput,
+    auth: AuthMethod,
+    /// Only used for password auth
+    username: TextInput,
+    /// The token or the password, depending on `auth`
+    secret: TextInput,
+    status: ServerStatus
+}
+
+#[derive(Clone, Default)]
+pub enum ServerStatus {
+    #[default]
+    Unknown,
+    Checking,
+    Online { latency: Duration, version: String },
+    Offline(String)
+}
+
+impl ServerStatus {
+    fn cell(&self) -> Cell<'static> {
+        match self {
+            ServerStatus::Unknown => Cell::from(""),
+            ServerStatus::Checking => Cell::from("checking...").yellow(),
+            ServerStatus::Online { latency, version } => Cell::from(format!("online, {} ms, v{}", latency.as_millis(), version)).green(),
+            ServerStatus::Offline(reason) => Cell::from(format!("offline, {}", reason)).red(),
+        }
+    }
 }
 
 impl From<&Server> for ServerEntry {
     fn from(value: &Server) -> Self {
+        let (auth, username, secret) = match &value.auth {
+            ServerAuth::None => (AuthMethod::None, String::new(), String::new()),
+            ServerAuth::Token { token } => (AuthMethod::Token, String::new(), token.clone()),
+            ServerAuth::Password { username, password } => (AuthMethod::Password, username.clone(), password.clone()),
+        };
         Self {
             name: TextInput::new(value.name.clone()),
             address: TextInput::new(value.address.clone()),
             port: TextInput::new(value.port.clone()),
+            scheme: value.scheme,
+            certificate: TextInput::new(value.ca_certificate.as_ref().map(|path| path.display().to_string()).unwrap_or_default()),
+            auth,
+            username: TextInput::new(username),
+            secret: TextInput::new(secret),
+            status: ServerStatus::Unknown,
+        }
+    }
+}
+
+impl From<&ServerEntry> for Server {
+    fn from(value: &ServerEntry) -> Self {
+        let certificate = value.certificate.text.trim();
+        Self {
+            name: value.name.text.clone(),
+            address: value.address.text.clone(),
+            port: value.port.text.clone(),
+            scheme: value.scheme,
+            ca_certificate: (!certificate.is_empty()).then(|| PathBuf::from(certificate)),
+            auth: value.server_auth(),
+        }
+    }
+}
+
+impl ServerEntry {
+    fn server_auth(&self) -> ServerAuth {
+        match self.auth {
+            AuthMethod::None => ServerAuth::None,
+            AuthMethod::Token => ServerAuth::Token { token: self.secret.text.clone() },
+            AuthMethod::Password => ServerAuth::Password { username: self.username.text.clone(), password: self.secret.text.clone() },
+        }
+    }
+
+    /// The text input behind a field, scheme and auth are picked from fixed choices instead.
+    fn input(&self, input: &SelectedInput) -> Option<&TextInput> {
+        match input {
+            SelectedInput::Name => Some(&self.name),
+            SelectedInput::Address => Some(&self.address),
+            SelectedInput::Port => Some(&self.port),
+            SelectedInput::Scheme | SelectedInput::Auth => None,
+            SelectedInput::Certificate => Some(&self.certificate),
+            SelectedInput::Username => Some(&self.username),
+            SelectedInput::Secret => Some(&self.secret),
+        }
+    }
+
+    fn input_mut(&mut self, input: &SelectedInput) -> Option<&mut TextInput> {
+        match input {
+            SelectedInput::Name => Some(&mut self.name),
+            SelectedInput::Address => Some(&mut self.address),
+            SelectedInput::Port => Some(&mut self.port),
+            SelectedInput::Scheme | SelectedInput::Auth => None,
+            SelectedInput::Certificate => Some(&mut self.certificate),
+            SelectedInput::Username => Some(&mut self.username),
+            SelectedInput::Secret => Some(&mut self.secret),
+        }
+    }
+
+    fn choice(&self, input: &SelectedInput) -> &'static str {
+        match input {
+            SelectedInput::Scheme => self.scheme.as_str(),
+            SelectedInput::Auth => self.auth.as_str(),
+            _ => "",
+        }
+    }
+
+    /// Steps the scheme or auth to the next choice, or back to the previous one.
+    fn cycle_choice(&mut self, input: &SelectedInput, forward: bool) {
+        match input {
+            SelectedInput::Scheme => self.scheme = match self.scheme {
+                Scheme::Http => Scheme::Https,
+                Scheme::Https => Scheme::Http,
+            },
+            SelectedInput::Auth if forward => self.auth = self.auth.next(),
+            SelectedInput::Auth => self.auth = self.auth.prev(),
+            _ => {}
+        }
+    }
+
+    fn title(&self, input: &SelectedInput) -> &'static str {
+        match input {
+            SelectedInput::Name => "Server Name",
+            SelectedInput::Address => "Server Address",
+            SelectedInput::Port => "Server Port",
+            SelectedInput::Scheme => "Scheme",
+            SelectedInput::Auth => "Auth",
+            SelectedInput::Certificate => "CA Certificate (optional)",
+            SelectedInput::Username => "Username",
+            SelectedInput::Secret => match self.auth {
+                AuthMethod::Password => "Password",
+                _ => "Token",
+            },
+        }
+    }
+
+    /// What is wrong with one of the fields, short enough to fit beneath it.
+    fn validate(&self, input: &SelectedInput) -> Result<(), &'static str> {
+        match input {
+            SelectedInput::Name if self.name.text.is_empty() => Err("Enter a name"),
+            SelectedInput::Name => Ok(()),
+            SelectedInput::Address => validate_address(&self.address.text),
+            SelectedInput::Port => validate_port(&self.port.text),
+            SelectedInput::Scheme | SelectedInput::Auth => Ok(()),
+            SelectedInput::Certificate => {
+                let certificate = self.certificate.text.trim();
+                if certificate.is_empty() {
+                    Ok(())
+                } else if self.scheme != Scheme::Https {
+                    Err("Certificates need https")
+                } else if !Path::new(certificate).is_file() {
+                    Err("Certificate file not found")
+                } else {
+                    Ok(())
+                }
+            },
+            SelectedInput::Username => match self.server_auth() {
+                ServerAuth::Password { username, .. } if username.is_empty() => Err("Enter a username"),
+                _ => Ok(()),
+            },
+            SelectedInput::Secret => match self.server_auth() {
+                ServerAuth::Token { token } if token.is_empty() => Err("Enter the token"),
+                ServerAuth::Password { password, .. } if password.is_empty() => Err("Enter the password"),
+                _ => Ok(()),
+            },
         }
     }
 }
 
+/// Checks that `address` is an IPv4 address, an IPv6 address (optionally in brackets) or an
+/// RFC 1123 host name, the error fits beneath the address input.
+fn validate_address(address: &str) -> Result<(), &'static str> {
+    if address.is_empty() {
+        return Err("Enter an IP address or host name");
+    }
+    if address.contains(':') {
+        let address = address.strip_prefix('[').and_then(|a| a.strip_suffix(']')).unwrap_or(address);
+        return address.parse::<Ipv6Addr>().map(|_| ()).map_err(|_| "Not a valid IPv6 address");
+    }
+    // A name made of only digits and dots could never be a host name, as the last label of one
+    // can't be all numbers, so it has to be an IPv4 address
+    if address.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return address.parse::<Ipv4Addr>().map(|_| ()).map_err(|_| "Not a valid IPv4 address");
+    }
+
+    if address.len() > 253 {
+        return Err("Host names are at most 253 chars");
+    }
+    for label in address.split('.') {
+        if label.is_empty() {
+            return Err("Host name has an empty part");
+        }
+        if label.len() > 63 {
+            return Err("Host name parts are 1-63 chars");
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err("Use letters, digits, - and . only");
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err("Parts can't start or end with -");
+        }
+    }
+    if address.rsplit('.').next().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit())) {
+        return Err("Last part can't be all digits");
+    }
+    Ok(())
+}
+
+fn validate_port(port: &str) -> Result<(), &'static str> {
+    if port.is_empty() || !port.chars().all(|c| c.is_ascii_digit()) {
+        return Err("Port must be a number");
+    }
+    match port.parse::<u16>() {
+        Ok(1..) => Ok(()),
+        _ => Err("Port must be between 1 and 65535"),
+    }
+}
+
 impl SanitizedForm for ServerEntry {
     fn verify_input(&self) -> bool {
-        self.name.valid && self.address.valid && self.port.valid
+        [&self.name, &self.address, &self.port, &self.certificate, &self.username, &self.secret].iter().all(|input| input.valid)
     }
 
+    #[allow(clippy::unused_unit)]
     fn update_validity(&mut self) -> () {
-        let name_regex = Regex::new(r".+").unwrap();
-        let addr_regex = Regex::new(r"([0-9]{1,3}\.[0-9]{1,3}\.[0-9]{1,3}\.[0-9]{1,3})|(localhost)").unwrap();
-        let port_regex = Regex::new(r"[0-9]{1,5}").unwrap();
-        
-        self.name.valid = name_regex.is_match(&self.name.text);
-        self.address.valid = addr_regex.is_match(&self.address.text);
-        self.port.valid = port_regex.is_match(&self.port.text);
+        for input in [SelectedInput::Name, SelectedInput::Address, SelectedInput::Port, SelectedInput::Scheme, SelectedInput::Auth, SelectedInput::Certificate, SelectedInput::Username, SelectedInput::Secret] {
+            let valid = self.validate(&input).is_ok();
+            if let Some(text_input) = self.input_mut(&input) {
+                text_input.valid = valid;
+            }
+        }
     }
 
+    #[allow(clippy::unused_unit)]
     fn clear_form(&mut self) -> () {
-        *self = ServerEntry::default();
+        // New servers start out as plain http without auth
+        *self = ServerEntry::from(&Server::default());
+        self.update_validity();
     }
 }
 
 pub struct ServerModule {}
 
 impl RenderableModule for ServerModule {
-    async fn input_handling(app: &mut App, key: event::KeyEvent) -> () {
+    #[allow(clippy::unused_unit)]
+    fn input_handling(app: &mut App, key: event::KeyEvent) -> () {
         if let SelectedModule::ServerModule(module_status) = &app.selected_module {
             let editable_data = &mut app.module_data.server_module.editable_server_data;
             match module_status {
                 ServerModules::ServerList => {
+                    app.module_data.server_module.notice = None;
                     match key.code {
                         KeyCode::Esc => app.should_quit = true,
-                        KeyCode::Char('c') => app.selected_module = SelectedModule::ServerModule(ServerModules::ServerEditing(SelectedInput::default())),
+                        KeyCode::Char('c') => {
+                            editable_data.clear_form();
+                            app.module_data.server_module.editing_index = None;
+                            app.selected_module = SelectedModule::ServerModule(ServerModules::ServerEditing(SelectedInput::default()));
+                        },
+                        KeyCode::Char('e') => {
+                            if let Some(index) = app.module_data.server_module.selected_list_index() {
+                                let mut entry = ServerEntry::from(&app.config.servers[index]);
+                                for input in [&mut entry.name, &mut entry.address, &mut entry.port, &mut entry.certificate, &mut entry.username, &mut entry.secret] {
+                                    input.cursor_to_end();
+                                }
+                                entry.update_validity();
+                                app.module_data.server_module.editable_server_data = entry;
+                                app.module_data.server_module.editing_index = Some(index);
+                                app.selected_module = SelectedModule::ServerModule(ServerModules::ServerEditing(SelectedInput::default()));
+                            }
+                        },
+                        KeyCode::Char('d') if app.module_data.server_module.selected_list_index().is_some() => {
+                            app.selected_module = SelectedModule::ServerModule(ServerModules::ServerDeleting);
+                        },
+                        KeyCode::Char('p') => ServerModule::ping_servers(app),
                         KeyCode::Enter => {
-                            app.module_data.server_module.selected_server = app.module_data.server_module.server_list[app.module_data.server_module.servers_table_state.selected().unwrap()].clone();
-                            app.selected_module = SelectedModule::UserModule(UserModules::UserList);
+                            if let Some(index) = app.module_data.server_module.selected_list_index() {
+                                let server = &app.module_data.server_module.server_list[index];
+                                if let ServerStatus::Offline(reason) = &server.status {
+                                    app.module_data.server_module.notice = Some(format!("{} is offline ({}), press p to check again", server.name.text, reason));
+                                    return;
+                                }
+                                let client = match ApiClient::new(&Server::from(server), &app.http_client) {
+                                    Ok(client) => client,
+                                    Err(error) => {
+                                        app.module_data.server_module.notice = Some(format!("Could not connect to {}: {}", server.name.text, error));
+                                        return;
+                                    }
+                                };
+                                app.module_data.server_module.selected_server = server.clone();
+                                app.api_client = client;
+                                app.selected_module = SelectedModule::UserModule(UserModules::UserList);
+                                UserModule::refresh_user_list(app);
+                            }
                         },
                         KeyCode::Up => app.module_data.server_module.servers_table_state.select_previous(),
                         KeyCode::Down => app.module_data.server_module.servers_table_state.select_next(),
@@ -109,102 +424,352 @@ impl RenderableModule for ServerModule {
                     }
                 },
                 ServerModules::ServerEditing(selected_input) => {
+                    let is_choice = editable_data.input(selected_input).is_none();
 
-                    let input = match selected_input {
-                        SelectedInput::Name => &mut editable_data.name,
-                        SelectedInput::Address => &mut editable_data.address,
-                        SelectedInput::Port => &mut editable_data.port,
-                    };
-                    
                     match key.code {
                         KeyCode::Esc => app.selected_module = SelectedModule::ServerModule(ServerModules::ServerList),
                         KeyCode::Up => {
+                            if let Some(input) = editable_data.input_mut(selected_input) {
+                                input.end_typing();
+                            }
                             if let SelectedModule::ServerModule(ServerModules::ServerEditing(inputs)) = &app.selected_module {
                                 app.selected_module = SelectedModule::ServerModule(ServerModules::ServerEditing(inputs.clone().prev()));
                             }
                         },
                         KeyCode::Down => {
+                            if let Some(input) = editable_data.input_mut(selected_input) {
+                                input.end_typing();
+                            }
                             if let SelectedModule::ServerModule(ServerModules::ServerEditing(inputs)) = &app.selected_module {
                                 app.selected_module = SelectedModule::ServerModule(ServerModules::ServerEditing(inputs.clone().next()));
                             }
                         },
-                        KeyCode::Backspace => input_functions::delete_char(input),
-                        KeyCode::Left => input_functions::move_cursor_left(input),
-                        KeyCode::Right => input_functions::move_cursor_right(input),
-                        KeyCode::Char(c) => input_functions::enter_char(input, c),
-                        KeyCode::Enter => {
-                            if editable_data.verify_input() {
-                                // Add to config file
-                                let config_server = Server {
-                                    name: editable_data.name.text.clone(),
-                                    address: editable_data.address.text.clone(),
-                                    port: editable_data.port.text.clone(),
-                                };
-                                app.config.add_new_server(config_server);
+                        KeyCode::Left if is_choice => editable_data.cycle_choice(selected_input, false),
+                        KeyCode::Right | KeyCode::Char(' ') if is_choice => editable_data.cycle_choice(selected_input, true),
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => {
+                            if let Some(input) = editable_data.input_mut(selected_input) {
+                                input_functions::handle_key(input, key);
+                            }
+                        },
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Save to the config file first, the form stays open when that fails
+                            let editing_index = app.module_data.server_module.editing_index;
+                            let saved = match editing_index {
+                                Some(index) => app.config.update_server(index, Server::from(&*editable_data)),
+                                None => app.config.add_new_server(Server::from(&*editable_data)),
+                            };
+                            if let Err(error) = saved {
+                                app.module_data.server_module.notice = Some(format!("Could not save the config: {:#}", error));
+                                return;
+                            }
+                            app.module_data.server_module.notice = None;
+                            app.module_data.server_module.editing_index = None;
 
-                                // update app's server list
-                                app.module_data.server_module.server_list.push(editable_data.clone());
-                                
-                                // clear form fields
-                                editable_data.clear_form();
+                            // Then the app's server list
+                            let server_list = &mut app.module_data.server_module.server_list;
+                            let index = match editing_index {
+                                Some(index) => {
+                                    server_list[index] = editable_data.clone();
+                                    index
+                                },
+                                None => {
+                                    server_list.push(editable_data.clone());
+                                    server_list.len() - 1
+                                }
+                            };
 
-                                // Change state to now go back to the server list
-                                app.selected_module = SelectedModule::ServerModule(ServerModules::ServerList);
-                            }
+                            // clear form fields
+                            editable_data.clear_form();
+
+                            // Change state to now go back to the server list
+                            app.selected_module = SelectedModule::ServerModule(ServerModules::ServerList);
+
+                            // Find out whether the server is reachable at its new address
+                            ServerModule::ping_server(app, index);
+                            return;
                         }
                         _ => {},
                     }
                     editable_data.update_validity();
                 },
+                ServerModules::ServerDeleting => {
+                    match key.code {
+                        KeyCode::Char('y') => {
+                            if let Some(index) = app.module_data.server_module.selected_list_index() {
+                                let server_module = &mut app.module_data.server_module;
+                                match app.config.remove_server(index) {
+                                    Err(error) => server_module.notice = Some(format!("Could not save the config: {:#}", error)),
+                                    Ok(()) => {
+                                        server_module.server_list.remove(index);
+
+                                        // Keep the selection inside the bounds of the new list
+                                        if server_module.server_list.is_empty() {
+                                            server_module.servers_table_state.select(None);
+                                        } else if index >= server_module.server_list.len() {
+                                            server_module.servers_table_state.select_last();
+                                        }
+                                    },
+                                }
+                            }
+                            app.selected_module = SelectedModule::ServerModule(ServerModules::ServerList);
+                        },
+                        KeyCode::Esc | KeyCode::Char('n') => app.selected_module = SelectedModule::ServerModule(ServerModules::ServerList),
+                        _ => {}
+                    }
+                },
             }
         }
     }
 
+    #[allow(clippy::unused_unit)]
     fn render(app: &mut App, frame: &mut ratatui::Frame) -> () {
-        let rects = Layout::vertical([Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
+        // Config errors can be longer than a line, so the notice wraps. Word wrapping leaves the
+        // lines a bit short, which the extra line makes room for.
+        let notice_height = match &app.module_data.server_module.notice {
+            Some(notice) => match (notice.chars().count() as u16).div_ceil(frame.area().width.max(1)) {
+                1 => 1,
+                lines => lines + 1,
+            },
+            None => 0,
+        };
+        let rects = Layout::vertical([Constraint::Fill(10), Constraint::Length(notice_height), Constraint::Length(3)]).split(frame.area());
         
-        let rows: Vec<Row> = app.module_data.server_module.server_list.iter().map(|f| Row::new(vec![f.name.text.clone(), f.address.text.clone(), f.port.text.clone()])).collect();
-        let widths = [Constraint::Length(15), Constraint::Length(15), Constraint::Length(5)];
+        let rows: Vec<Row> = app.module_data.server_module.server_list.iter().map(|f| Row::new(vec![Cell::from(f.name.text.clone()), Cell::from(f.address.text.clone()), Cell::from(f.port.text.clone()), f.status.cell()])).collect();
+        let widths = [Constraint::Length(15), Constraint::Length(15), Constraint::Length(5), Constraint::Fill(1)];
 
         let servers_table = Table::new(rows, widths).block(Block::bordered().title("Select Server")).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
 
         frame.render_stateful_widget(servers_table, rects[0], &mut app.module_data.server_module.servers_table_state);
 
-        if app.module_data.server_module.server_list.len() > 0 && app.module_data.server_module.servers_table_state.selected().is_none() {
+        if !app.module_data.server_module.server_list.is_empty() && app.module_data.server_module.servers_table_state.selected().is_none() {
             app.module_data.server_module.servers_table_state.select_next();
         }
 
-        let footer_contents = text::Line::from("Esc: Exit | Enter: Select Server | Arrow Keys: Navigation | c: Create New Server Entry");
+        if let Some(notice) = &app.module_data.server_module.notice {
+            frame.render_widget(Paragraph::new(notice.as_str()).wrap(Wrap { trim: true }).style(Style::new().fg(Color::Red)).centered(), rects[1]);
+        }
+
+        let footer_contents = text::Line::from("Esc: Exit | Enter: Select Server | Arrow Keys: Navigation | c: Create New Server Entry | e: Edit Server | d: Delete Server | p: Ping Servers");
         let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
-        frame.render_widget(keybinds, rects[1]);
+        frame.render_widget(keybinds, rects[2]);
         
         if let SelectedModule::ServerModule(ServerModules::ServerEditing(selected_input)) = &app.selected_module {
-            let area = rendering::popup_area_lengths(frame.area(), 37, 12);
-                    
-            let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3), Constraint::Length(3), Constraint::Length(3)]).split(area);
-            
             let editable_data = &app.module_data.server_module.editable_server_data;
 
-            let input_name = single_line_input(editable_data.name.text.as_str(), "Server Name", editable_data.name.valid);
-                    frame.render_widget(input_name, rects[0]);
+            // Each row of inputs gets a line beneath it for the first error in it
+            let rows: [&[SelectedInput]; 6] = [
+                &[SelectedInput::Name],
+                &[SelectedInput::Address],
+                &[SelectedInput::Port],
+                &[SelectedInput::Scheme, SelectedInput::Auth],
+                &[SelectedInput::Certificate],
+                &[SelectedInput::Username, SelectedInput::Secret],
+            ];
+            let errors: Vec<Option<&str>> = rows.iter().map(|row| row.iter().find_map(|input| editable_data.validate(input).err())).collect();
 
-            let input_address = single_line_input(editable_data.address.text.as_str(), "Server Address", editable_data.address.valid);
-                    frame.render_widget(input_address, rects[1]);
+            let mut constraints = Vec::new();
+            for error in &errors {
+                constraints.push(Constraint::Length(3));
+                constraints.push(Constraint::Length(error.is_some() as u16));
+            }
+            constraints.push(Constraint::Length(3));
 
-            let input_port = single_line_input(editable_data.port.text.as_str(), "Server Port", editable_data.port.valid);
-                    frame.render_widget(input_port, rects[2]);
+            let height = rows.len() as u16 * 3 + errors.iter().flatten().count() as u16 + 3;
+            let area = rendering::popup_area_lengths(frame.area(), 50, height);
+            frame.render_widget(Clear, area);
+            let rects = Layout::vertical(constraints).split(area);
 
-            let (rect, index) = match selected_input {
-                SelectedInput::Name => (0, editable_data.name.index as u16),
-                SelectedInput::Address => (1, editable_data.address.index as u16),
-                SelectedInput::Port => (2, editable_data.port.index as u16),
-            };
-            frame.set_cursor_position(Position::new(rects[rect].x + index + 1, rects[rect].y + 1));
+            for (row_index, row) in rows.iter().enumerate() {
+                let columns = Layout::horizontal(vec![Constraint::Fill(1); row.len()]).split(rects[row_index * 2]);
+                for (input, column) in row.iter().zip(columns.iter()) {
+                    // Tokens and passwords are never shown, and the choices show which keys change them
+                    let text_input = match (input, editable_data.input(input)) {
+                        (SelectedInput::Secret, _) => &TextInput {
+                            text: "•".repeat(editable_data.secret.text.graphemes(true).count()),
+                            index: editable_data.secret.index,
+                            valid: editable_data.secret.valid,
+                            ..TextInput::default()
+                        },
+                        (_, Some(text_input)) => text_input,
+                        (_, None) => &TextInput { text: format!("< {} >", editable_data.choice(input)), valid: true, ..TextInput::default() },
+                    };
+                    frame.render_widget(single_line_input(text_input, editable_data.title(input)), *column);
 
-            let footer_contents = text::Line::from("Esc: Back | Enter: Confirm Server");
+                    if input == selected_input && editable_data.input(input).is_some() {
+                        frame.set_cursor_position(rendering::cursor_position(text_input, *column));
+                    }
+                }
+                if let Some(error) = errors[row_index] {
+                    frame.render_widget(Paragraph::new(error).red(), rects[row_index * 2 + 1]);
+                }
+            }
+
+            let footer_contents = if app.module_data.server_module.editing_index.is_some() {
+                text::Line::from("Esc: Back | Enter: Save Changes")
+            } else {
+                text::Line::from("Esc: Back | Enter: Confirm Server")
+            };
             let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
-            frame.render_widget(keybinds, rects[3]);
+            frame.render_widget(keybinds, rects[rects.len() - 1]);
             
         }
+
+        if let SelectedModule::ServerModule(ServerModules::ServerDeleting) = &app.selected_module {
+            let server_module = &app.module_data.server_module;
+            let Some(server) = server_module.servers_table_state.selected().and_then(|i| server_module.server_list.get(i)) else { return };
+            let area = rendering::popup_area_lengths(frame.area(), 60, 6);
+            frame.render_widget(Clear, area);
+
+            let rects = Layout::vertical([Constraint::Length(3), Constraint::Length(3)]).split(area);
+
+            let warning = Paragraph::new(format!("Remove {} ({}:{}) from the server list?", server.name.text, server.address.text, server.port.text)).block(Block::bordered().title("Delete Server")).centered().red();
+            frame.render_widget(warning, rects[0]);
+
+            let footer_contents = text::Line::from("y: Delete | n: Cancel");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[1]);
+        }
+    }
+}
+
+impl ServerModule {
+    /// Checks every server in the background, filling in the status column as the answers come in.
+    pub fn ping_servers(app: &mut App) {
+        for index in 0..app.module_data.server_module.server_list.len() {
+            ServerModule::ping_server(app, index);
+        }
+    }
+
+    fn ping_server(app: &mut App, index: usize) {
+        let server = &mut app.module_data.server_module.server_list[index];
+        let client = match ApiClient::new(&Server::from(&*server), &app.http_client) {
+            Ok(client) => client,
+            Err(error) => {
+                server.status = ServerStatus::Offline(error.to_string());
+                return;
+            }
+        };
+        server.status = ServerStatus::Checking;
+        let (address, port) = (server.address.text.clone(), server.port.text.clone());
+
+        // Applied on any screen, so the list is up to date when the user comes back to it
+        app.request_to(&format!("Pinging {}:{}", address, port), &client, true, |client| async move {
+            let started = Instant::now();
+            // An unreachable server is a status to show rather than an error to report
+            Ok(match client.server_info().await {
+                Ok(info) => ServerStatus::Online { latency: started.elapsed(), version: info.version },
+                Err(ApiError::Connection(_)) => ServerStatus::Offline("not reachable".to_string()),
+                Err(error) => ServerStatus::Offline(error.to_string()),
+            })
+        }, move |app, status| {
+            // Entries pointing at the same address share the answer
+            for server in app.module_data.server_module.server_list.iter_mut().filter(|s| s.address.text == address && s.port.text == port) {
+                server.status = status.clone();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_addresses() {
+        assert_eq!(validate_address("192.168.1.20"), Ok(()));
+        assert_eq!(validate_address("127.0.0.1"), Ok(()));
+        assert_eq!(validate_address("999.1.1.1"), Err("Not a valid IPv4 address"));
+        assert_eq!(validate_address("1.2.3"), Err("Not a valid IPv4 address"));
+        assert_eq!(validate_address("1.2.3.4."), Err("Not a valid IPv4 address"));
+    }
+
+    #[test]
+    fn ipv6_addresses() {
+        assert_eq!(validate_address("::1"), Ok(()));
+        assert_eq!(validate_address("[::1]"), Ok(()));
+        assert_eq!(validate_address("fe80::1:2"), Ok(()));
+        assert_eq!(validate_address("[::1"), Err("Not a valid IPv6 address"));
+        assert_eq!(validate_address("::g"), Err("Not a valid IPv6 address"));
+        assert_eq!(validate_address("tracker.lan:8000"), Err("Not a valid IPv6 address"));
+    }
+
+    #[test]
+    fn host_names() {
+        assert_eq!(validate_address("tracker.lan"), Ok(()));
+        assert_eq!(validate_address("localhost"), Ok(()));
+        assert_eq!(validate_address("my-server.example.com"), Ok(()));
+        // Only the last label has to contain a letter, so this is a host name rather than a
+        // mistyped IPv4 address
+        assert_eq!(validate_address("abc1.2.3.4xyz"), Ok(()));
+
+        assert_eq!(validate_address(""), Err("Enter an IP address or host name"));
+        assert_eq!(validate_address("-tracker.lan"), Err("Parts can't start or end with -"));
+        assert_eq!(validate_address("tracker-.lan"), Err("Parts can't start or end with -"));
+        assert_eq!(validate_address("tracker..lan"), Err("Host name has an empty part"));
+        assert_eq!(validate_address("tracker.lan."), Err("Host name has an empty part"));
+        assert_eq!(validate_address("track_er.lan"), Err("Use letters, digits, - and . only"));
+        assert_eq!(validate_address("tracker.123"), Err("Last part can't be all digits"));
+    }
+
+    #[test]
+    fn host_name_lengths() {
+        let label_63 = "a".repeat(63);
+        let label_64 = "a".repeat(64);
+        assert_eq!(validate_address(&format!("{}.lan", label_63)), Ok(()));
+        assert_eq!(validate_address(&format!("{}.lan", label_64)), Err("Host name parts are 1-63 chars"));
+
+        // Four 63 char labels and their dots make 255 chars
+        let too_long = [label_63.as_str(); 4].join(".");
+        assert_eq!(validate_address(&too_long), Err("Host names are at most 253 chars"));
+        assert_eq!(validate_address(&too_long[..253]), Ok(()));
+    }
+
+    #[test]
+    fn scheme_and_auth_cycle_through_their_choices() {
+        let mut entry = ServerEntry::default();
+        entry.cycle_choice(&SelectedInput::Scheme, true);
+        assert_eq!(entry.scheme, Scheme::Https);
+        entry.cycle_choice(&SelectedInput::Scheme, false);
+        assert_eq!(entry.scheme, Scheme::Http);
+
+        let mut methods = Vec::new();
+        for _ in 0..3 {
+            entry.cycle_choice(&SelectedInput::Auth, true);
+            methods.push(entry.auth);
+        }
+        assert_eq!(methods, [AuthMethod::Token, AuthMethod::Password, AuthMethod::None]);
+        entry.cycle_choice(&SelectedInput::Auth, false);
+        assert_eq!(entry.auth, AuthMethod::Password);
+        // Other fields are left alone
+        entry.cycle_choice(&SelectedInput::Name, true);
+        assert_eq!((entry.scheme, entry.auth), (Scheme::Http, AuthMethod::Password));
+    }
+
+    #[test]
+    fn entries_keep_the_auth_of_their_server() {
+        let server = Server {
+            scheme: Scheme::Https,
+            auth: ServerAuth::Password { username: "sam".to_string(), password: "secret".to_string() },
+            ..Server::default()
+        };
+        let entry = ServerEntry::from(&server);
+        assert_eq!((entry.scheme, entry.auth, entry.title(&SelectedInput::Secret)), (Scheme::Https, AuthMethod::Password, "Password"));
+
+        let saved = Server::from(&entry);
+        assert_eq!(saved.scheme, Scheme::Https);
+        assert!(matches!(saved.auth, ServerAuth::Password { username, password } if username == "sam" && password == "secret"));
+    }
+
+    #[test]
+    fn ports() {
+        assert_eq!(validate_port("1"), Ok(()));
+        assert_eq!(validate_port("8000"), Ok(()));
+        assert_eq!(validate_port("65535"), Ok(()));
+        assert_eq!(validate_port("0"), Err("Port must be between 1 and 65535"));
+        assert_eq!(validate_port("65536"), Err("Port must be between 1 and 65535"));
+        assert_eq!(validate_port("99999"), Err("Port must be between 1 and 65535"));
+        assert_eq!(validate_port(""), Err("Port must be a number"));
+        assert_eq!(validate_port("80a"), Err("Port must be a number"));
+        assert_eq!(validate_port("-1"), Err("Port must be a number"));
     }
 }
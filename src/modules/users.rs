@@ -1,13 +1,13 @@
 use crossterm::event::KeyCode;
-use ratatui::{layout::{Constraint, Layout, Position}, text, widgets::{Block, Paragraph, TableState}};
-use regex::Regex;
+use ratatui::{layout::{Constraint, Layout}, style::{Style, Stylize}, text, widgets::{Block, Paragraph, Row, Table, TableState}};
 
-use crate::modules::TextInput;
+use crate::{api::NewUser, app::App, modules::TextInput};
 
-use super::{input_functions, rendering::{self, single_line_input}, RenderableModule, SanitizedForm, SelectedModule};
+use super::{input_functions, selection::SelectionModules, rendering::{self, single_line_input}, RenderableModule, SanitizedForm, SelectedModule};
 
 pub struct UserModule {}
 
+#[allow(clippy::enum_variant_names)]
 pub enum UserModules {
     UserList,
     UserEditing(SelectedInput)
@@ -18,6 +18,7 @@ pub enum SelectedInput {
     Name
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for SelectedInput {
     fn default() -> Self {
         Self::Name
@@ -26,10 +27,16 @@ impl Default for SelectedInput {
 
 #[derive(Default)]
 pub struct UserModuleData {
-    user_list: Vec<String>,
+    user_list: Vec<User>,
     user_table_state: TableState,
     editable_user_data: UserEntry,
-    selected_user: UserEntry
+    pub selected_user: User
+}
+
+#[derive(serde::Deserialize, Clone, Default)]
+pub struct User {
+    pub id: i64,
+    pub name: String
 }
 
 #[derive(Default)]
@@ -37,30 +44,50 @@ struct UserEntry {
     pub name: TextInput
 }
 
+impl UserEntry {
+    fn request(&self) -> NewUser {
+        NewUser { name: self.name.text.trim().to_string() }
+    }
+}
+
 impl SanitizedForm for UserEntry {
     fn verify_input(&self) -> bool {
        self.name.valid 
     }
 
+    #[allow(clippy::unused_unit)]
     fn update_validity(&mut self) -> () {
-        let name_regex = Regex::new(r".+").unwrap();
-        self.name.valid = name_regex.is_match(&self.name.text);
+        self.name.valid = !self.name.text.trim().is_empty();
     }
 
+    #[allow(clippy::unused_unit)]
     fn clear_form(&mut self) -> () {
         *self = Self::default();
     }
 }
 
 impl RenderableModule for UserModule {
-    async fn input_handling(app: &mut crate::app::App, key: crossterm::event::KeyEvent) -> () {
+    #[allow(clippy::unused_unit)]
+    fn input_handling(app: &mut crate::app::App, key: crossterm::event::KeyEvent) -> () {
         if let SelectedModule::UserModule(module_status) = &app.selected_module {
             let editable_data = &mut app.module_data.user_module.editable_user_data;
             match module_status {
                 UserModules::UserList => {
                     match key.code {
                         KeyCode::Esc => app.should_quit = true,
-                        KeyCode::Char('c') => app.selected_module = SelectedModule::UserModule(UserModules::UserEditing(SelectedInput::default())),
+                        KeyCode::Char('c') => {
+                            editable_data.clear_form();
+                            app.selected_module = SelectedModule::UserModule(UserModules::UserEditing(SelectedInput::default()));
+                        },
+                        KeyCode::Char('r') => UserModule::refresh_user_list(app),
+                        KeyCode::Enter => {
+                            if let Some(user) = app.module_data.user_module.selected_list_user().cloned() {
+                                app.module_data.user_module.selected_user = user;
+                                app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList);
+                            }
+                        },
+                        KeyCode::Up => app.module_data.user_module.user_table_state.select_previous(),
+                        KeyCode::Down => app.module_data.user_module.user_table_state.select_next(),
                         _ => {}
                     }
                 },
@@ -71,21 +98,22 @@ impl RenderableModule for UserModule {
 
                     match key.code {
                         KeyCode::Esc => app.selected_module = SelectedModule::UserModule(UserModules::UserList),
-                        KeyCode::Backspace => input_functions::delete_char(input),
-                        KeyCode::Left => input_functions::move_cursor_left(input),
-                        KeyCode::Right => input_functions::move_cursor_right(input),
-                        KeyCode::Char(c) => input_functions::enter_char(input, c),
-                        KeyCode::Enter => {
-                            if editable_data.verify_input() {
-                                // Send to server
-                                let url = format!("http://{}:{}/api/users", app.module_data.server_module.selected_server.address.text, app.module_data.server_module.selected_server.port.text);
-                                server_functions::send_new_user(url, &app.http_client, editable_data).await;
+                        KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Char(_) => input_functions::handle_key(input, key),
+                        KeyCode::Enter if editable_data.verify_input() => {
+                            // Send to server
+                            let user = editable_data.request();
+                            app.request("Saving user", move |client| {
+                                let user = user.clone();
+                                async move { client.create_user(&user).await }
+                            }, |app, _| {
                                 // Clear form fields
-                                editable_data.clear_form();
+                                app.module_data.user_module.editable_user_data.clear_form();
                                 // Refresh User List
+                                UserModule::refresh_user_list(app);
                                 // Change state back to list
-                                app.selected_module = SelectedModule::UserModule(UserModules::UserList)
-                            }
+                                app.selected_module = SelectedModule::UserModule(UserModules::UserList);
+                            });
+                            return;
                         }
                         _ => {}
                     }
@@ -95,11 +123,21 @@ impl RenderableModule for UserModule {
         }
     }
 
+    #[allow(clippy::unused_unit)]
     fn render(app: &mut crate::app::App, frame: &mut ratatui::Frame) -> () {
         let rects = Layout::vertical([Constraint::Fill(10), Constraint::Length(3)]).split(frame.area());
 
-        // Main list goes here
-        
+        let rows: Vec<Row> = app.module_data.user_module.user_list.iter().map(|u| Row::new(vec![u.id.to_string(), u.name.clone()])).collect();
+        let widths = [Constraint::Length(6), Constraint::Fill(1)];
+
+        let users_table = Table::new(rows, widths).block(Block::bordered().title("Select User")).row_highlight_style(Style::new().reversed()).highlight_symbol(">>");
+
+        frame.render_stateful_widget(users_table, rects[0], &mut app.module_data.user_module.user_table_state);
+
+        if !app.module_data.user_module.user_list.is_empty() && app.module_data.user_module.user_table_state.selected().is_none() {
+            app.module_data.user_module.user_table_state.select_next();
+        }
+
         let footer_contents = text::Line::from("Esc: Exit | Enter: Select User | Arrow Keys: Navigation | c: Create New User | r: Refresh User List");
         let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
         frame.render_widget(keybinds, rects[1]);
@@ -111,10 +149,10 @@ impl RenderableModule for UserModule {
             
             let editable_data = &app.module_data.user_module.editable_user_data;
 
-            let input_name = single_line_input(editable_data.name.text.as_str(), "User Name", editable_data.name.valid);
+            let input_name = single_line_input(&editable_data.name, "User Name");
             frame.render_widget(input_name, rects[0]);
 
-            frame.set_cursor_position(Position::new(rects[0].x + editable_data.name.index as u16 + 1, rects[0].y + 1));
+            frame.set_cursor_position(rendering::cursor_position(&editable_data.name, rects[0]));
 
             let footer_contents = text::Line::from("Esc: Back | Enter: Confirm User");
             let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
@@ -123,15 +161,24 @@ impl RenderableModule for UserModule {
     }
 }
 
-mod server_functions {
-    use super::UserEntry;
-
-    pub async fn send_new_user(url: String, http_client: &reqwest::Client, user: &mut UserEntry) {
-        let params = [("name".to_string(), user.name.text.clone())];
-        http_client.post(url).form(&params).send().await.unwrap();
+impl UserModuleData {
+    fn selected_list_user(&self) -> Option<&User> {
+        self.user_table_state.selected().and_then(|i| self.user_list.get(i))
     }
+}
 
-    pub async fn get_user_list(url: String, http_client: &reqwest::Client) {
-        let users = http_client.get(url).await
+impl UserModule {
+    pub fn refresh_user_list(app: &mut App) {
+        app.request("Loading users", |client| async move { client.users().await }, |app, users| {
+            let user_module = &mut app.module_data.user_module;
+            user_module.user_list = users;
+
+            // Keep the selection inside the bounds of the new list
+            if user_module.user_list.is_empty() {
+                user_module.user_table_state.select(None);
+            } else if user_module.user_table_state.selected().is_some_and(|i| i >= user_module.user_list.len()) {
+                user_module.user_table_state.select_last();
+            }
+        });
     }
 }
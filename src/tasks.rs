@@ -0,0 +1,300 @@
+use std::{collections::VecDeque, future::Future, mem::{discriminant, Discriminant}, sync::Arc, time::Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use futures::future::BoxFuture;
+use ratatui::{layout::{Constraint, Layout, Rect}, style::{Color, Style, Stylize}, text, widgets::{Block, Clear, Paragraph, Wrap}, Frame};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{api::{ApiClient, ApiError, ApiResult}, app::App, modules::{rendering, SelectedModule}};
+
+/// Applies the result of a finished request to the app.
+type Update = Box<dyn FnOnce(&mut App) + Send>;
+
+/// A request that can be sent again, which is what makes retrying after an error possible.
+type Job = Arc<dyn Fn(ApiClient) -> BoxFuture<'static, ApiResult<Update>> + Send + Sync>;
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// What makes two requests the same: pressing a key twice must not save the same form twice,
+/// but the same label on another screen or for another user is a different request.
+#[derive(Clone, PartialEq, Debug)]
+struct RequestKey {
+    label: String,
+    /// Module that asked for the request, its spinner is only shown there
+    screen: Discriminant<SelectedModule>,
+    user_id: i64
+}
+
+struct Request {
+    id: u64,
+    key: RequestKey,
+    /// Visit to `key.screen` the request was sent from
+    visit: u64,
+    /// Apply the result even after the user has left the screen
+    any_screen: bool,
+    client: ApiClient,
+    job: Job,
+    started: Instant
+}
+
+pub struct Finished {
+    id: u64,
+    result: ApiResult<Update>
+}
+
+struct FailedRequest {
+    request: Request,
+    error: ApiError
+}
+
+/// Network requests running in the background. Each one reports back through a channel that
+/// `App::run` reads alongside terminal events, so the UI keeps drawing while the server answers.
+pub struct Tasks {
+    sender: UnboundedSender<Finished>,
+    pub receiver: UnboundedReceiver<Finished>,
+    running: Vec<Request>,
+    failed: VecDeque<FailedRequest>,
+    next_id: u64,
+    /// Screen the user is on and how many times they have changed screens so far
+    screen: Option<Discriminant<SelectedModule>>,
+    visit: u64
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Self {
+            sender,
+            receiver,
+            running: Vec::new(),
+            failed: VecDeque::new(),
+            next_id: 0,
+            screen: None,
+            visit: 0,
+        }
+    }
+}
+
+impl Tasks {
+    /// Notes that the user is on `screen`, counting a new visit when they have moved there from
+    /// somewhere else.
+    pub fn visit(&mut self, screen: &SelectedModule) {
+        let screen = discriminant(screen);
+        if self.screen != Some(screen) {
+            self.screen = Some(screen);
+            self.visit += 1;
+        }
+    }
+
+    /// Whether the result of `request` still matters to the user.
+    fn is_current(&self, request: &Request) -> bool {
+        request.any_screen || request.visit == self.visit
+    }
+
+    /// Starts `job` unless the same request is still running. Requests from an earlier visit
+    /// to the screen don't count, their results are going to be dropped.
+    fn spawn(&mut self, key: RequestKey, any_screen: bool, client: &ApiClient, job: Job) {
+        if self.running.iter().any(|r| r.key == key && self.is_current(r)) {
+            return;
+        }
+        self.start(key, self.visit, any_screen, client.clone(), job);
+    }
+
+    fn start(&mut self, key: RequestKey, visit: u64, any_screen: bool, client: ApiClient, job: Job) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let sender = self.sender.clone();
+        let future = job(client.clone());
+        tokio::spawn(async move {
+            // The receiver only goes away when the app is shutting down
+            let _ = sender.send(Finished { id, result: future.await });
+        });
+        self.running.push(Request { id, key, visit, any_screen, client, job, started: Instant::now() });
+    }
+
+    pub fn has_error(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    pub fn input_handling(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('r') => {
+                if let Some(failed) = self.failed.pop_front() {
+                    let request = failed.request;
+                    self.start(request.key, request.visit, request.any_screen, request.client, request.job);
+                }
+            },
+            KeyCode::Esc | KeyCode::Enter => { self.failed.pop_front(); },
+            _ => {}
+        }
+    }
+}
+
+impl App {
+    /// Sends a request to the selected server in the background and hands the response to
+    /// `on_success` once it arrives.
+    pub fn request<T, F, R, S>(&mut self, label: &str, request: R, on_success: S)
+    where
+        T: Send + 'static,
+        R: Fn(ApiClient) -> F + Send + Sync + 'static,
+        F: Future<Output = ApiResult<T>> + Send + 'static,
+        S: Fn(&mut App, T) + Send + Sync + 'static,
+    {
+        let client = self.api_client.clone();
+        self.request_to(label, &client, false, request, on_success);
+    }
+
+    /// Like `request`, but for a server other than the selected one. With `any_screen` the
+    /// result is applied even if the user has moved on to another module by then.
+    pub fn request_to<T, F, R, S>(&mut self, label: &str, client: &ApiClient, any_screen: bool, request: R, on_success: S)
+    where
+        T: Send + 'static,
+        R: Fn(ApiClient) -> F + Send + Sync + 'static,
+        F: Future<Output = ApiResult<T>> + Send + 'static,
+        S: Fn(&mut App, T) + Send + Sync + 'static,
+    {
+        let on_success = Arc::new(on_success);
+        let job: Job = Arc::new(move |client| {
+            let response = request(client);
+            let on_success = on_success.clone();
+            Box::pin(async move {
+                let value = response.await?;
+                Ok(Box::new(move |app: &mut App| on_success(app, value)) as Update)
+            })
+        });
+        self.tasks.visit(&self.selected_module);
+        let key = RequestKey {
+            label: label.to_string(),
+            screen: discriminant(&self.selected_module),
+            user_id: self.module_data.user_module.selected_user.id,
+        };
+        self.tasks.spawn(key, any_screen, client, job);
+    }
+
+    /// Applies a finished request. Results for a module the user has since left are dropped,
+    /// even if they came back to it, as opening it again loads everything fresh, unless the
+    /// request asked for `any_screen`. Errors are always shown.
+    pub fn finish_request(&mut self, finished: Finished) {
+        let Some(index) = self.tasks.running.iter().position(|r| r.id == finished.id) else { return };
+        let request = self.tasks.running.remove(index);
+
+        match finished.result {
+            Ok(update) => {
+                self.tasks.visit(&self.selected_module);
+                if self.tasks.is_current(&request) {
+                    update(self);
+                }
+            },
+            Err(error) => self.tasks.failed.push_back(FailedRequest { request, error }),
+        }
+    }
+
+    pub fn render_tasks(&self, frame: &mut Frame) {
+        let screen = discriminant(&self.selected_module);
+        if let Some(request) = self.tasks.running.iter().find(|r| r.key.screen == screen && self.tasks.is_current(r)) {
+            let spinner = SPINNER[(request.started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+            let status = format!(" {} {}... ", spinner, request.key.label);
+            let width = (status.chars().count() as u16).min(frame.area().width);
+            let area = Rect { x: frame.area().right().saturating_sub(width + 1), y: frame.area().y, width, height: 1 };
+            frame.render_widget(Paragraph::new(status).yellow(), area);
+        }
+
+        if let Some(failed) = self.tasks.failed.front() {
+            let area = rendering::popup_area_lengths(frame.area(), 60, 9);
+            frame.render_widget(Clear, area);
+
+            let rects = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).split(area);
+
+            let title = match self.tasks.failed.len() {
+                1 => format!("{} failed", failed.request.key.label),
+                count => format!("{} failed (1 of {})", failed.request.key.label, count),
+            };
+            let message = Paragraph::new(failed.error.to_string()).wrap(Wrap { trim: true }).style(Style::new().fg(Color::Red)).block(Block::bordered().title(title));
+            frame.render_widget(message, rects[0]);
+
+            let footer_contents = text::Line::from("r: Retry | Esc: Dismiss");
+            let keybinds = Paragraph::new(footer_contents).block(Block::bordered().title("Keybinds")).centered();
+            frame.render_widget(keybinds, rects[1]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::config::Config, modules::{financial::{FinancialModule, FinancialModules}, selection::SelectionModules, users::User}};
+
+    use super::*;
+
+    fn app_on(screen: SelectedModule) -> App {
+        let mut app = App::new(Config::default(), None);
+        app.selected_module = screen;
+        app.module_data.user_module.selected_user = User { id: 1, name: "Sam".to_string() };
+        app
+    }
+
+    fn ledger_screen() -> SelectedModule {
+        SelectedModule::FinancialModule(FinancialModules::AccountList)
+    }
+
+    fn save(app: &mut App) {
+        app.request("Saving", |_| async { Ok(()) }, |app, _| app.should_quit = true);
+    }
+
+    /// Finishes the request with `id` as if the server had answered.
+    fn answer(app: &mut App, id: u64) {
+        let update: Update = Box::new(|app: &mut App| app.should_quit = true);
+        app.finish_request(Finished { id, result: Ok(update) });
+    }
+
+    #[tokio::test]
+    async fn same_request_is_sent_once() {
+        let mut app = app_on(ledger_screen());
+        save(&mut app);
+        save(&mut app);
+        assert_eq!(app.tasks.running.len(), 1);
+
+        // Once it is done it can be sent again
+        answer(&mut app, 0);
+        save(&mut app);
+        assert_eq!(app.tasks.running.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn opening_and_refreshing_the_ledger_are_different_requests() {
+        let mut app = app_on(ledger_screen());
+        FinancialModule::open_ledger(&mut app);
+        FinancialModule::refresh_ledger(&mut app);
+        assert_eq!(app.tasks.running.len(), 2);
+        FinancialModule::refresh_ledger(&mut app);
+        assert_eq!(app.tasks.running.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn same_label_for_another_user_is_sent() {
+        let mut app = app_on(ledger_screen());
+        save(&mut app);
+        app.module_data.user_module.selected_user = User { id: 2, name: "Alex".to_string() };
+        save(&mut app);
+        assert_eq!(app.tasks.running.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn request_from_an_earlier_visit_does_not_block() {
+        let mut app = app_on(ledger_screen());
+        save(&mut app);
+
+        // Leave the screen and come back while the first request is still running
+        app.selected_module = SelectedModule::SelectionModule(SelectionModules::ModuleList);
+        app.tasks.visit(&app.selected_module);
+        app.selected_module = ledger_screen();
+        save(&mut app);
+        assert_eq!(app.tasks.running.len(), 2);
+
+        // Only the answer to the request from this visit is applied
+        answer(&mut app, 0);
+        assert!(!app.should_quit);
+        answer(&mut app, 1);
+        assert!(app.should_quit);
+    }
+}
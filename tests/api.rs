@@ -0,0 +1,179 @@
+mod common;
+
+use chrono::NaiveDate;
+use ntntkr::api::{ApiClient, ApiError, NewAccount, NewCalories, NewContribution, NewItem, NewNotebook, NewPlanned, NewPurchase, NewRecurring, NewSleep, NewUser, NewWeight};
+use ntntkr::modules::health::WeightUnit;
+use reqwest::StatusCode;
+
+use common::{ScratchDir, Server};
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+fn status<T>(result: Result<T, ApiError>) -> StatusCode {
+    match result {
+        Err(ApiError::Status { status, .. }) => status,
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("the request succeeded"),
+    }
+}
+
+async fn add_user(client: &ApiClient) -> i64 {
+    client.create_user(&NewUser { name: "Sam".to_string() }).await.unwrap().id
+}
+
+fn account(name: &str) -> NewAccount {
+    NewAccount { name: name.to_string(), account_type: "checking".to_string(), starting_balance: 10_000 }
+}
+
+fn purchase(account_id: i64, amount: i64, recurring_id: Option<i64>) -> NewPurchase {
+    NewPurchase { account_id, date: date("2024-03-01"), amount, payee: "Shop".to_string(), category: "Food".to_string(), recurring_id }
+}
+
+fn recurring(account_id: i64, payee: &str) -> NewRecurring {
+    NewRecurring {
+        account_id,
+        amount: 500,
+        payee: payee.to_string(),
+        category: "Health".to_string(),
+        schedule: "monthly:1".to_string(),
+        start_date: date("2024-01-01"),
+        end_date: None,
+    }
+}
+
+#[tokio::test]
+async fn users_are_renamed_and_deleted() {
+    let dir = ScratchDir::new("api-users");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = server.client();
+    let user_id = add_user(&client).await;
+
+    let renamed = client.update_user(user_id, &NewUser { name: "Alex".to_string() }).await.unwrap();
+    assert_eq!((renamed.id, renamed.name.as_str()), (user_id, "Alex"));
+    client.delete_user(user_id).await.unwrap();
+    assert!(client.users().await.unwrap().is_empty());
+    assert_eq!(status(client.delete_user(user_id).await), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn notebooks_and_items_are_edited_and_deleted() {
+    let dir = ScratchDir::new("api-notebooks");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = server.client();
+    let user_id = add_user(&client).await;
+
+    let notebook = client.create_notebook(user_id, &NewNotebook { name: "Ideas".to_string(), description: String::new() }).await.unwrap();
+    let edited = client.update_notebook(user_id, notebook.id, &NewNotebook { name: "Plans".to_string(), description: "For later".to_string() }).await.unwrap();
+    assert_eq!((edited.name.as_str(), edited.description.as_str()), ("Plans", "For later"));
+    // Editing a notebook doesn't count as using it
+    assert_eq!((edited.created, edited.last_used), (notebook.created, notebook.last_used));
+    client.delete_notebook(user_id, notebook.id).await.unwrap();
+    assert!(client.notebooks(user_id).await.unwrap().is_empty());
+    assert_eq!(status(client.delete_notebook(user_id, notebook.id).await), StatusCode::NOT_FOUND);
+
+    let item = client.create_item(user_id, &NewItem { name: "Flour".to_string(), in_kitchen: true }).await.unwrap();
+    let moved = client.update_item(user_id, item.id, &NewItem { name: "Rye flour".to_string(), in_kitchen: false }).await.unwrap();
+    assert_eq!((moved.name.as_str(), moved.in_kitchen), ("Rye flour", false));
+    client.delete_item(user_id, item.id).await.unwrap();
+    assert_eq!(status(client.update_item(user_id, item.id, &NewItem { name: "Flour".to_string(), in_kitchen: true }).await), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn accounts_in_use_are_kept() {
+    let dir = ScratchDir::new("api-accounts");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = server.client();
+    let user_id = add_user(&client).await;
+
+    let account_id = client.create_account(user_id, &account("Checking")).await.unwrap().id;
+    let edited = client.update_account(user_id, account_id, &NewAccount { starting_balance: 2_500, ..account("Savings") }).await.unwrap();
+    assert_eq!((edited.name.as_str(), edited.starting_balance), ("Savings", 2_500));
+
+    let purchase_id = client.create_purchase(user_id, &purchase(account_id, 300, None)).await.unwrap().id;
+    assert_eq!(status(client.delete_account(user_id, account_id).await), StatusCode::CONFLICT);
+    client.delete_purchase(user_id, purchase_id).await.unwrap();
+    client.delete_account(user_id, account_id).await.unwrap();
+    assert!(client.accounts(user_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn purchases_and_recurring_purchases_are_edited_and_deleted() {
+    let dir = ScratchDir::new("api-purchases");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = server.client();
+    let user_id = add_user(&client).await;
+    let account_id = client.create_account(user_id, &account("Checking")).await.unwrap().id;
+
+    let purchase_id = client.create_purchase(user_id, &purchase(account_id, 300, None)).await.unwrap().id;
+    let edited = client.update_purchase(user_id, purchase_id, &purchase(account_id, 450, None)).await.unwrap();
+    assert_eq!(edited.amount, 450);
+    let other_account = client.create_account(user_id, &account("Cash")).await.unwrap().id;
+    assert_eq!(client.update_purchase(user_id, purchase_id, &purchase(other_account, 450, None)).await.unwrap().account_id, other_account);
+    assert_eq!(status(client.update_purchase(user_id, purchase_id, &purchase(other_account + 1, 450, None)).await), StatusCode::BAD_REQUEST);
+
+    let recurring_id = client.create_recurring(user_id, &recurring(account_id, "Gym")).await.unwrap().id;
+    client.set_last_materialized(user_id, recurring_id, date("2024-03-01")).await.unwrap();
+    let materialized = client.create_purchase(user_id, &purchase(account_id, 500, Some(recurring_id))).await.unwrap();
+    let edited = client.update_recurring(user_id, recurring_id, &recurring(account_id, "Pool")).await.unwrap();
+    assert_eq!((edited.payee.as_str(), edited.last_materialized), ("Pool", Some(date("2024-03-01"))));
+
+    // The purchases it materialized outlive the recurring purchase
+    client.delete_recurring(user_id, recurring_id).await.unwrap();
+    assert!(client.recurring(user_id).await.unwrap().is_empty());
+    let purchases = client.purchases(user_id).await.unwrap();
+    let kept = purchases.iter().find(|p| p.id == materialized.id).unwrap();
+    assert_eq!(kept.recurring_id, None);
+    assert_eq!(status(client.delete_recurring(user_id, recurring_id).await), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn planned_purchases_keep_their_contributions() {
+    let dir = ScratchDir::new("api-planned");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = server.client();
+    let user_id = add_user(&client).await;
+    let account_id = client.create_account(user_id, &account("Checking")).await.unwrap().id;
+
+    let new_planned = NewPlanned { account_id, name: "Bike".to_string(), target_amount: 80_000, target_date: date("2024-06-01") };
+    let planned_id = client.create_planned(user_id, &new_planned).await.unwrap().id;
+    client.create_contribution(user_id, planned_id, &NewContribution { date: date("2024-03-05"), amount: 10_000 }).await.unwrap();
+    let edited = client.update_planned(user_id, planned_id, &NewPlanned { target_amount: 90_000, ..new_planned }).await.unwrap();
+    assert_eq!((edited.target_amount, edited.contributions.len()), (90_000, 1));
+
+    client.delete_planned(user_id, planned_id).await.unwrap();
+    assert!(client.planned(user_id).await.unwrap().is_empty());
+    // Nothing refers to the account any more
+    client.delete_account(user_id, account_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn health_logs_are_edited_and_deleted() {
+    let dir = ScratchDir::new("api-health");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = server.client();
+    let user_id = add_user(&client).await;
+
+    let weight = NewWeight { date: date("2024-03-01"), weight: 80.0, unit: WeightUnit::Kg, fitbit_log_id: None };
+    let weight_id = client.create_weight(user_id, &weight).await.unwrap().id;
+    let edited = client.update_weight(user_id, weight_id, &NewWeight { weight: 176.5, unit: WeightUnit::Lb, ..weight }).await.unwrap();
+    assert!(edited.weight == 176.5 && edited.unit == WeightUnit::Lb);
+    assert_eq!(status(client.update_weight(user_id, weight_id, &NewWeight { weight: -1.0, ..weight }).await), StatusCode::BAD_REQUEST);
+    client.delete_weight(user_id, weight_id).await.unwrap();
+    assert!(client.weights(user_id).await.unwrap().is_empty());
+
+    client.set_calories(user_id, &NewCalories { date: date("2024-03-01"), calories: 2_100 }).await.unwrap();
+    assert_eq!(client.update_calories(user_id, date("2024-03-01"), 2_400).await.unwrap().calories, 2_400);
+    client.delete_calories(user_id, date("2024-03-01")).await.unwrap();
+    assert!(client.calories(user_id).await.unwrap().is_empty());
+    assert_eq!(status(client.delete_calories(user_id, date("2024-03-01")).await), StatusCode::NOT_FOUND);
+
+    let bed_time = date("2024-03-01").and_hms_opt(23, 0, 0).unwrap();
+    let sleep = NewSleep { bed_time, wake_time: date("2024-03-02").and_hms_opt(7, 0, 0).unwrap(), quality: None, fitbit_log_id: None };
+    let sleep_id = client.create_sleep(user_id, &sleep).await.unwrap().id;
+    assert_eq!(client.update_sleep(user_id, sleep_id, &NewSleep { quality: Some(70), ..sleep.clone() }).await.unwrap().quality, Some(70));
+    assert_eq!(status(client.update_sleep(user_id, sleep_id, &NewSleep { wake_time: bed_time, ..sleep }).await), StatusCode::BAD_REQUEST);
+    client.delete_sleep(user_id, sleep_id).await.unwrap();
+    assert!(client.sleeps(user_id).await.unwrap().is_empty());
+}
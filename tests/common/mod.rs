@@ -0,0 +1,89 @@
+// Each test crate only uses some of these helpers
+#![allow(dead_code)]
+
+use std::{fs, io::{BufRead, BufReader}, net::SocketAddr, path::{Path, PathBuf}, process::{Child, ChildStdout, Command, Output, Stdio}};
+
+use ntntkr::{api::ApiClient, config::config::{Scheme, Server as ServerConfig, ServerAuth}};
+
+/// An empty directory of its own for each test, removed again when the test is done.
+pub struct ScratchDir {
+    pub path: PathBuf
+}
+
+impl ScratchDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ntntkr-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        ScratchDir { path }
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Runs `ntntkr-server` with `args` to completion, for the commands that exit on their own.
+pub fn run_server(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ntntkr-server")).args(args).output().unwrap()
+}
+
+/// A `ntntkr-server` process on a free port, killed when it goes out of scope.
+pub struct Server {
+    process: Child,
+    /// Kept open so the server can still write to it
+    _stdout: BufReader<ChildStdout>,
+    pub address: SocketAddr,
+    pub base_url: String
+}
+
+impl Server {
+    pub fn start(data: &Path, args: &[&str]) -> Self {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_ntntkr-server"))
+            .args(["--port", "0", "--data"])
+            .arg(data)
+            .args(args)
+            .env_remove("NTNTKR_TOKEN")
+            .env_remove("NTNTKR_LOGIN")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // The first line says where the server ended up listening
+        let mut stdout = BufReader::new(process.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        let address = line.split_once("listening on ").and_then(|(_, rest)| rest.split_once(',')).and_then(|(address, _)| address.parse::<SocketAddr>().ok());
+        let Some(address) = address else {
+            let _ = process.kill();
+            panic!("unexpected server output: {:?}", line);
+        };
+        Server { process, _stdout: stdout, address, base_url: format!("http://{}/api", address) }
+    }
+
+    /// The terminal client's view of this server.
+    pub fn client(&self) -> ApiClient {
+        let config = ServerConfig {
+            name: "test".to_string(),
+            address: self.address.ip().to_string(),
+            port: self.address.port().to_string(),
+            scheme: Scheme::Http,
+            ca_certificate: None,
+            auth: ServerAuth::None,
+        };
+        ApiClient::new(&config, &reqwest::Client::new()).unwrap()
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
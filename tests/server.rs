@@ -0,0 +1,275 @@
+mod common;
+
+use reqwest::{blocking::Client, StatusCode};
+use serde_json::{json, Value};
+
+use common::{run_server, ScratchDir, Server};
+
+fn send(request: reqwest::blocking::RequestBuilder, expected: StatusCode) -> Value {
+    let response = request.send().unwrap();
+    let status = response.status();
+    let text = response.text().unwrap();
+    assert_eq!(status, expected, "{}", text);
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+fn get(client: &Client, server: &Server, path: &str) -> Value {
+    send(client.get(server.url(path)), StatusCode::OK)
+}
+
+fn post(client: &Client, server: &Server, path: &str, body: Value) -> Value {
+    send(client.post(server.url(path)).json(&body), StatusCode::CREATED)
+}
+
+fn add_user(client: &Client, server: &Server, name: &str) -> i64 {
+    post(client, server, "users", json!({ "name": name }))["id"].as_i64().unwrap()
+}
+
+fn add_account(client: &Client, server: &Server, user_id: i64) -> i64 {
+    let account = json!({ "name": "Checking", "account_type": "checking", "starting_balance": 150_000 });
+    post(client, server, &format!("users/{}/accounts", user_id), account)["id"].as_i64().unwrap()
+}
+
+#[test]
+fn fresh_database_is_migrated_and_kept() {
+    let dir = ScratchDir::new("fresh");
+    let data = dir.path.join("ntntkr.db");
+    let client = Client::new();
+
+    let server = Server::start(&data, &[]);
+    let health = get(&client, &server, "health");
+    let schema_version = health["schema_version"].as_u64().unwrap();
+    assert!(schema_version >= 1);
+    let user_id = add_user(&client, &server, "Sam");
+    drop(server);
+
+    // Opening the migrated database again keeps its version and records
+    let server = Server::start(&data, &[]);
+    assert_eq!(get(&client, &server, "health")["schema_version"].as_u64(), Some(schema_version));
+    assert_eq!(get(&client, &server, "users"), json!([{ "id": user_id, "name": "Sam" }]));
+}
+
+#[test]
+fn newer_schema_is_refused() {
+    let dir = ScratchDir::new("newer");
+    let data = dir.path.join("ntntkr.db");
+    rusqlite::Connection::open(&data).unwrap().pragma_update(None, "user_version", 1000).unwrap();
+
+    let output = run_server(&["--data", data.to_str().unwrap(), "--port", "0"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("schema version 1000"), "{}", stderr);
+}
+
+#[test]
+fn users_round_trip() {
+    let dir = ScratchDir::new("users");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+
+    let user_id = add_user(&client, &server, "Sam");
+    assert_eq!(get(&client, &server, "users"), json!([{ "id": user_id, "name": "Sam" }]));
+
+    let renamed = send(client.put(server.url(&format!("users/{}", user_id))).json(&json!({ "name": "Alex" })), StatusCode::OK);
+    assert_eq!(renamed, json!({ "id": user_id, "name": "Alex" }));
+    send(client.put(server.url(&format!("users/{}", user_id + 1))).json(&json!({ "name": "Nobody" })), StatusCode::NOT_FOUND);
+    send(client.post(server.url("users")).json(&json!({ "name": "  " })), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn notebooks_round_trip() {
+    let dir = ScratchDir::new("notebooks");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let user_id = add_user(&client, &server, "Sam");
+
+    let notebook = post(&client, &server, &format!("users/{}/notebooks", user_id), json!({ "name": "Ideas", "description": "Loose notes" }));
+    assert_eq!((notebook["name"].as_str(), notebook["description"].as_str()), (Some("Ideas"), Some("Loose notes")));
+    assert_eq!(get(&client, &server, &format!("users/{}/notebooks", user_id)), json!([notebook]));
+
+    let used = send(client.post(server.url(&format!("users/{}/notebooks/{}/used", user_id, notebook["id"]))), StatusCode::OK);
+    assert_eq!(used["created"], notebook["created"]);
+    assert!(used["last_used"].as_str() >= notebook["last_used"].as_str());
+}
+
+#[test]
+fn inventory_round_trip() {
+    let dir = ScratchDir::new("inventory");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let user_id = add_user(&client, &server, "Sam");
+    let items = format!("users/{}/inventory", user_id);
+
+    let item = post(&client, &server, &items, json!({ "name": "Flour", "in_kitchen": true }));
+    assert_eq!(get(&client, &server, &items), json!([item]));
+
+    let item_url = server.url(&format!("{}/{}", items, item["id"]));
+    let moved = send(client.put(&item_url).json(&json!({ "name": "Flour", "in_kitchen": false })), StatusCode::OK);
+    assert_eq!(moved["in_kitchen"], json!(false));
+    send(client.delete(&item_url), StatusCode::NO_CONTENT);
+    send(client.delete(&item_url), StatusCode::NOT_FOUND);
+    assert_eq!(get(&client, &server, &items), json!([]));
+}
+
+#[test]
+fn financial_records_round_trip() {
+    let dir = ScratchDir::new("financial");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let user_id = add_user(&client, &server, "Sam");
+    let account_id = add_account(&client, &server, user_id);
+    let user_path = |path: &str| format!("users/{}/{}", user_id, path);
+
+    let accounts = get(&client, &server, &user_path("accounts"));
+    assert_eq!(accounts, json!([{ "id": account_id, "user_id": user_id, "name": "Checking", "account_type": "checking", "starting_balance": 150_000 }]));
+
+    let recurring = post(&client, &server, &user_path("recurring"), json!({
+        "account_id": account_id, "amount": 120_000, "payee": "Landlord", "category": "Rent",
+        "schedule": "monthly:1", "start_date": "2024-01-01", "end_date": "2024-12-31"
+    }));
+    assert_eq!(recurring["last_materialized"], Value::Null);
+    assert_eq!(get(&client, &server, &user_path("recurring")), json!([recurring]));
+    let materialized = send(client.put(server.url(&user_path(&format!("recurring/{}/last_materialized", recurring["id"])))).json(&json!({ "last_materialized": "2024-03-01" })), StatusCode::OK);
+    assert_eq!(materialized["last_materialized"], json!("2024-03-01"));
+
+    let purchase = json!({ "account_id": account_id, "date": "2024-03-01", "amount": 120_000, "payee": "Landlord", "category": "Rent", "recurring_id": recurring["id"] });
+    let stored = post(&client, &server, &user_path("purchases"), purchase.clone());
+    // Materializing the same date twice hands back the purchase that is already there
+    let mut again = purchase;
+    again["amount"] = json!(1);
+    assert_eq!(post(&client, &server, &user_path("purchases"), again), stored);
+    assert_eq!(get(&client, &server, &user_path("purchases")), json!([stored]));
+
+    let planned = post(&client, &server, &user_path("planned"), json!({ "account_id": account_id, "name": "Bike", "target_amount": 80_000, "target_date": "2024-06-01" }));
+    assert_eq!(planned["contributions"], json!([]));
+    let contribution = json!({ "date": "2024-03-05", "amount": 10_000 });
+    let with_contribution = post(&client, &server, &user_path(&format!("planned/{}/contributions", planned["id"])), contribution.clone());
+    assert_eq!(with_contribution["contributions"], json!([contribution]));
+    assert_eq!(get(&client, &server, &user_path("planned")), json!([with_contribution]));
+}
+
+#[test]
+fn recurring_schedules_are_validated() {
+    let dir = ScratchDir::new("schedules");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let user_id = add_user(&client, &server, "Sam");
+    let account_id = add_account(&client, &server, user_id);
+    let recurring = |schedule: &str| json!({ "account_id": account_id, "amount": 500, "payee": "Gym", "schedule": schedule, "start_date": "2024-01-01" });
+    let path = format!("users/{}/recurring", user_id);
+
+    for (schedule, stored) in [("daily", "daily"), ("Weekly: Mon", "weekly:mon"), ("monthly:31", "monthly:31"), ("nth:2:tuesday", "nth:2:tue"), ("LAST:fri", "last:fri")] {
+        assert_eq!(post(&client, &server, &path, recurring(schedule))["schedule"], json!(stored), "{}", schedule);
+    }
+    for schedule in ["", "fortnightly", "weekly", "weekly:someday", "monthly:0", "monthly:32", "nth:5:tue", "nth:1", "last:fri:1"] {
+        let response = send(client.post(server.url(&path)).json(&recurring(schedule)), StatusCode::BAD_REQUEST);
+        assert!(response.as_str().is_some_and(|message| !message.is_empty()), "{}", schedule);
+    }
+
+    // Updates are held to the same grammar
+    let id = get(&client, &server, &path)[0]["id"].clone();
+    send(client.put(server.url(&format!("{}/{}", path, id))).json(&recurring("monthly:32")), StatusCode::BAD_REQUEST);
+    assert_eq!(get(&client, &server, &path)[0]["schedule"], json!("daily"));
+}
+
+#[test]
+fn health_records_round_trip() {
+    let dir = ScratchDir::new("health");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let user_id = add_user(&client, &server, "Sam");
+    let user_path = |path: &str| format!("users/{}/{}", user_id, path);
+
+    let fitbit_weight = json!({ "date": "2024-03-01", "weight": 81.4, "unit": "kg", "fitbit_log_id": 42 });
+    let weight = post(&client, &server, &user_path("weight"), fitbit_weight.clone());
+    // A Fitbit log that is already stored is handed back instead of being stored twice
+    assert_eq!(post(&client, &server, &user_path("weight"), fitbit_weight), weight);
+    assert_eq!(get(&client, &server, &user_path("weight")), json!([weight]));
+
+    send(client.post(server.url(&user_path("calories"))).json(&json!({ "date": "2024-03-01", "calories": 2100 })), StatusCode::OK);
+    let calories = send(client.post(server.url(&user_path("calories"))).json(&json!({ "date": "2024-03-01", "calories": 2400 })), StatusCode::OK);
+    assert_eq!(get(&client, &server, &user_path("calories")), json!([calories]));
+
+    let sleep = post(&client, &server, &user_path("sleep"), json!({ "bed_time": "2024-03-01T23:30:00", "wake_time": "2024-03-02T07:00:00", "quality": 85 }));
+    let fitbit_sleep = json!({ "bed_time": "2024-03-02T23:00:00", "wake_time": "2024-03-03T06:30:00", "fitbit_log_id": 7 });
+    let synced = post(&client, &server, &user_path("sleep"), fitbit_sleep.clone());
+    assert_eq!(post(&client, &server, &user_path("sleep"), fitbit_sleep), synced);
+    assert_eq!(get(&client, &server, &user_path("sleep")), json!([sleep, synced]));
+
+    send(client.get(server.url(&user_path("fitbit"))), StatusCode::NOT_FOUND);
+    let account = json!({ "access_token": "access", "refresh_token": "refresh", "expires_at": "2024-03-01T12:00:00Z", "last_synced": null });
+    send(client.put(server.url(&user_path("fitbit"))).json(&account), StatusCode::OK);
+    let refreshed = json!({ "access_token": "access-2", "refresh_token": "refresh", "expires_at": "2024-03-01T12:00:00Z", "last_synced": "2024-03-01" });
+    send(client.put(server.url(&user_path("fitbit"))).json(&refreshed), StatusCode::OK);
+    let stored = get(&client, &server, &user_path("fitbit"));
+    assert_eq!((&stored["access_token"], &stored["last_synced"]), (&json!("access-2"), &json!("2024-03-01")));
+}
+
+#[test]
+fn records_are_scoped_to_their_user() {
+    let dir = ScratchDir::new("scoped");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let (sam, alex) = (add_user(&client, &server, "Sam"), add_user(&client, &server, "Alex"));
+
+    let item = post(&client, &server, &format!("users/{}/inventory", sam), json!({ "name": "Flour", "in_kitchen": true }));
+    let as_alex = server.url(&format!("users/{}/inventory/{}", alex, item["id"]));
+    send(client.put(&as_alex).json(&json!({ "name": "Flour", "in_kitchen": false })), StatusCode::NOT_FOUND);
+    send(client.delete(&as_alex), StatusCode::NOT_FOUND);
+    assert_eq!(get(&client, &server, &format!("users/{}/inventory", alex)), json!([]));
+
+    // Another user's account can't be used either
+    let account_id = add_account(&client, &server, sam);
+    let purchase = json!({ "account_id": account_id, "date": "2024-03-01", "amount": 500, "payee": "Shop" });
+    send(client.post(server.url(&format!("users/{}/purchases", alex))).json(&purchase), StatusCode::BAD_REQUEST);
+    send(client.get(server.url("users/99/inventory")), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn deleting_a_user_removes_their_records() {
+    let dir = ScratchDir::new("delete-user");
+    let server = Server::start(&dir.path.join("ntntkr.db"), &[]);
+    let client = Client::new();
+    let (sam, alex) = (add_user(&client, &server, "Sam"), add_user(&client, &server, "Alex"));
+    for user_id in [sam, alex] {
+        let account_id = add_account(&client, &server, user_id);
+        let planned = post(&client, &server, &format!("users/{}/planned", user_id), json!({ "account_id": account_id, "name": "Bike", "target_amount": 80_000, "target_date": "2024-06-01" }));
+        post(&client, &server, &format!("users/{}/planned/{}/contributions", user_id, planned["id"]), json!({ "date": "2024-03-05", "amount": 10_000 }));
+    }
+
+    send(client.delete(server.url(&format!("users/{}", sam))), StatusCode::NO_CONTENT);
+    send(client.get(server.url(&format!("users/{}/accounts", sam))), StatusCode::NOT_FOUND);
+    assert_eq!(get(&client, &server, "users").as_array().unwrap().len(), 1);
+    assert_eq!(get(&client, &server, &format!("users/{}/planned", alex))[0]["contributions"].as_array().unwrap().len(), 1);
+    send(client.delete(server.url(&format!("users/{}", sam))), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn legacy_json_is_imported_with_its_ids() {
+    let dir = ScratchDir::new("import");
+    let (data, json_file) = (dir.path.join("ntntkr.db"), dir.path.join("ntntkr-data.json"));
+    let legacy = json!({
+        "next_id": 9,
+        "users": [{ "id": 3, "name": "Sam" }],
+        "accounts": [{ "id": 4, "user_id": 3, "name": "Checking", "account_type": "checking", "starting_balance": 1000 }],
+        "recurring": [{ "id": 5, "user_id": 3, "account_id": 4, "amount": 500, "payee": "Gym", "category": "Health", "schedule": "monthly:1", "start_date": "2024-01-01", "end_date": null, "last_materialized": "2024-03-01" }],
+        "purchases": [{ "id": 6, "user_id": 3, "account_id": 4, "date": "2024-03-01", "amount": 500, "payee": "Gym", "category": "Health", "recurring_id": 5 }],
+        "planned": [{ "id": 7, "user_id": 3, "account_id": 4, "name": "Bike", "target_amount": 8000, "target_date": "2024-06-01", "contributions": [{ "date": "2024-03-05", "amount": 1000 }] }],
+        "calories": [{ "user_id": 3, "date": "2024-03-01", "calories": 2100 }]
+    });
+    std::fs::write(&json_file, legacy.to_string()).unwrap();
+    let import = ["--data", data.to_str().unwrap(), "--import", json_file.to_str().unwrap()];
+
+    let output = run_server(&import);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Imported 6 records"));
+    // A database that already has users is never imported into
+    assert!(!run_server(&import).status.success());
+
+    let server = Server::start(&data, &[]);
+    let client = Client::new();
+    assert_eq!(get(&client, &server, "users/3/purchases")[0]["recurring_id"], json!(5));
+    assert_eq!(get(&client, &server, "users/3/planned")[0]["contributions"].as_array().unwrap().len(), 1);
+    // New records carry on after the imported ids
+    assert_eq!(add_user(&client, &server, "Alex"), 4);
+}